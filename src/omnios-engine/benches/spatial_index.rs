@@ -0,0 +1,81 @@
+//! Spatial index benchmarks on a 10k-element page.
+//! Run with `cargo bench --bench spatial_index`.
+
+use omnios_engine::plugins::spatial_index::{ElementBounds, SpatialIndex, SPATIAL_INDEX};
+use omnios_engine::plugins::vqa::{compute_parity_score, get_full_layout_dump};
+use std::time::Instant;
+
+const ELEMENTS: usize = 10_000;
+const RUNS: u32 = 20;
+
+/// 100 x 100 grid of cards, shifted by `offset` on x.
+fn page(offset: f32) -> Vec<ElementBounds> {
+    (0..ELEMENTS)
+        .map(|i| ElementBounds::new(format!("el-{}", i), (i % 100) as f32 * 120.0 + offset, (i / 100) as f32 * 80.0, 100.0, 60.0))
+        .collect()
+}
+
+fn bench<T>(name: &str, mut setup: impl FnMut() -> T, mut run: impl FnMut(T)) {
+    let mut total = 0.0;
+    for _ in 0..RUNS {
+        let input = setup();
+        let start = Instant::now();
+        run(input);
+        total += start.elapsed().as_secs_f64();
+    }
+    println!("{:<40} {:>10.3} ms", name, total / RUNS as f64 * 1000.0);
+}
+
+fn main() {
+    println!("{} elements, mean of {} runs", ELEMENTS, RUNS);
+
+    bench("insert one by one", || page(0.0), |items| {
+        let mut index = SpatialIndex::new();
+        for b in items {
+            index.insert_or_update(b.id.clone(), b.min_x, b.min_y, b.max_x - b.min_x, b.max_y - b.min_y);
+        }
+    });
+
+    bench("rebuild (bulk load)", || page(0.0), |items| SpatialIndex::new().rebuild(items));
+
+    let loaded = || {
+        let mut index = SpatialIndex::new();
+        index.rebuild(page(0.0));
+        index
+    };
+
+    bench("sync, nothing changed", || (loaded(), page(0.0)), |(mut index, items)| { index.sync(items); });
+
+    bench("sync, 1% moved", || {
+        let mut items = page(0.0);
+        for b in items.iter_mut().step_by(100) {
+            b.min_y += 5.0;
+            b.max_y += 5.0;
+        }
+        (loaded(), items)
+    }, |(mut index, items)| { index.sync(items); });
+
+    bench("sync, everything moved", || (loaded(), page(3.0)), |(mut index, items)| { index.sync(items); });
+
+    bench("10k lookups by id", || (loaded(), page(0.0)), |(index, items)| {
+        for b in &items {
+            assert!(index.get(&b.id).is_some());
+        }
+    });
+
+    bench("10k hit tests", loaded, |index| {
+        for i in 0..ELEMENTS {
+            index.hit_test((i % 100) as f32 * 120.0 + 50.0, (i / 100) as f32 * 80.0 + 30.0);
+        }
+    });
+
+    // Parity and dump go through the global index
+    SPATIAL_INDEX.lock().unwrap().rebuild(page(0.0));
+    let dom: Vec<serde_json::Value> = page(0.5).iter()
+        .map(|b| serde_json::json!({ "id": b.id, "x": b.min_x, "y": b.min_y, "width": b.max_x - b.min_x, "height": b.max_y - b.min_y }))
+        .collect();
+    let dom = serde_json::to_string(&dom).unwrap();
+
+    bench("compute_parity_score", || (), |_| { compute_parity_score(&dom); });
+    bench("get_full_layout_dump", || (), |_| { get_full_layout_dump(); });
+}
@@ -1,4 +1,6 @@
+use omnios_engine::plugins::effects::{MockEffects, NativeEffects};
 use omnios_engine::plugins::logic_kernel::{LogicKernel, UnifiedBlueprint};
+use omnios_engine::plugins::validator::{self, Severity, ValidationContext};
 use std::env;
 use std::fs;
 
@@ -6,9 +8,19 @@ fn main() {
     // Basic Logger init (if we had env_logger, but simple print for MVP)
     println!("OMNIOS Logic Kernel - Standalone Mode");
     
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    // --mock-effects: record effects instead of sending HTTP requests
+    let mock_effects = args.iter().any(|a| a == "--mock-effects");
+    // --trace: print every node run, the port it took and its nesting
+    let trace = args.iter().any(|a| a == "--trace");
+    args.retain(|a| a != "--mock-effects" && a != "--trace");
+    if args.len() == 3 && args[1] == "validate" {
+        validate(&args[2]);
+        return;
+    }
     if args.len() < 3 {
-        eprintln!("Usage: omnios-cli <blueprint_file.json> <trigger_name> [payload_json]");
+        eprintln!("Usage: omnios-cli <blueprint_file.json> <trigger_name> [payload_json] [--mock-effects] [--trace]");
+        eprintln!("       omnios-cli validate <blueprint_file.json>");
         return;
     }
 
@@ -43,8 +55,56 @@ fn main() {
 
     println!("Executing Trigger: '{}' on Blueprint: '{}'", trigger, blueprint.id);
     
-    kernel.execute(&blueprint.id, trigger, &payload);
+    let aborted = kernel.execute(&blueprint.id, trigger, &payload).err();
 
+    // Performs effects and fast-forwards timers so wait/await_effect nodes finish too
+    let (effects, outcomes) = if mock_effects {
+        kernel.run_until_idle(&mut MockEffects::default())
+    } else {
+        kernel.run_until_idle(&mut NativeEffects::default())
+    };
+    if trace {
+        println!("Trace:");
+        for line in kernel.trace.lines() {
+            println!("  {}", line);
+        }
+    }
     println!("Execution Complete.");
-    println!("Final Runtime Variables: {:?}", kernel.runtime_variables);
+    println!("Final Execution Variables: {:?}", kernel.variables.execution);
+    println!("Blueprint Variables: {:?}", kernel.variables.blueprint.get(&blueprint.id));
+
+    if !effects.is_empty() {
+        println!("Effects: {}", serde_json::to_string_pretty(&effects).unwrap_or_default());
+        println!("Effect Outcomes: {}", serde_json::to_string_pretty(&outcomes).unwrap_or_default());
+    }
+    for error in &kernel.errors {
+        eprintln!("Error in {} ({}): {}", error.node_id, error.blueprint_id, error.message);
+    }
+    if let Some(error) = aborted.or_else(|| kernel.error.take()) {
+        eprintln!("Execution aborted: {}", error);
+    }
+}
+
+/// Prints the static findings for a blueprint file; exits non-zero if any are errors.
+fn validate(path: &str) {
+    let blueprint: UnifiedBlueprint = match fs::read_to_string(path).map_err(|e| e.to_string())
+        .and_then(|c| serde_json::from_str(&c).map_err(|e| e.to_string()))
+    {
+        Ok(bp) => bp,
+        Err(e) => {
+            eprintln!("Error loading blueprint: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let findings = validator::validate(&blueprint, &ValidationContext::default());
+    for f in &findings {
+        let level = if f.severity == Severity::Error { "error" } else { "warning" };
+        println!("{}[{}]: {} (nodes: {}; connections: {})", level, f.code, f.message, f.node_ids.join(", "), f.connection_ids.join(", "));
+    }
+    let errors = findings.iter().filter(|f| f.severity == Severity::Error).count();
+    println!("{} error(s), {} warning(s)", errors, findings.len() - errors);
+    if errors > 0 {
+        std::process::exit(1);
+    }
 }
@@ -96,7 +96,10 @@ fn handle_connection(mut stream: TcpStream) {
             req.trigger = trigger_name;
         }
 
-        let res = RuntimeAdapter::handle_request(req);
+        // Hosts flows may call natively, e.g. OMNIOS_ALLOWED_HOSTS=api.example.com; unset: effects are mocked
+        let allowed_hosts: Vec<String> = std::env::var("OMNIOS_ALLOWED_HOSTS").unwrap_or_default()
+            .split(',').map(|h| h.trim().to_string()).filter(|h| !h.is_empty()).collect();
+        let res = RuntimeAdapter::handle_request(req, &allowed_hosts);
         let res_json = serde_json::to_string(&res).unwrap();
         
         format!(
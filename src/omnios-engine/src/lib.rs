@@ -1,5 +1,4 @@
 use wasm_bindgen::prelude::*;
-use crate::sdk::OmniosPlugin;
 #[cfg(feature = "browser")]
 use web_sys::{console, Window, Document, HtmlCanvasElement, CanvasRenderingContext2d};
 use taffy::prelude::*;
@@ -66,6 +65,14 @@ pub struct DesignerElement {
 // --- LOGIC SYSTEM ---
 
 pub use plugins::logic_kernel::{UnifiedNode, UnifiedConnection, UnifiedBlueprint};
+pub use plugins::variables::{VariableChange, VariableScope};
+pub use plugins::effects::{Effect, EffectHandler, EffectOutcome, MockEffects, QueuedEffect};
+pub use plugins::executions::{ExecutionRecord, WaitFor};
+pub use plugins::metering::{ExecutionError, ExecutionLimits};
+pub use plugins::validator::{Finding, Severity};
+pub use plugins::debugger::{DebugCommand, DebugState, PausedRun};
+pub use plugins::trace::TraceEvent;
+pub use plugins::nodes::{FieldKind, FieldSpec, Flow, NodeContext, NodeError, NodeHandler, NodeRegistry, NodeSpec};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -87,12 +94,7 @@ pub struct ProjectState {
     pub view_mode: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct AnimationValue {
-    pub current: f32,
-    pub target: f32,
-    pub velocity: f32,
-}
+pub use plugins::animation::{AnimationValue, AnimationEvent, AnimationEventKind, ExitGhost, PresenceConfig, TransitionConfig, TransitionSpec};
 
 // --- GLOBAL STATE ---
 
@@ -101,6 +103,11 @@ lazy_static! {
     static ref LAYOUT_TREE: Mutex<HashMap<String, Node>> = Mutex::new(HashMap::new());
     pub static ref TAFFY: Mutex<Taffy> = Mutex::new(Taffy::new());
     static ref ANIMATION_STATE: Mutex<HashMap<String, HashMap<String, AnimationValue>>> = Mutex::new(HashMap::new());
+    static ref ANIMATION_EVENTS: Mutex<Vec<AnimationEvent>> = Mutex::new(Vec::new()); // Pending dispatch to logic
+    static ref ANIMATION_EVENT_LOG: Mutex<Vec<AnimationEvent>> = Mutex::new(Vec::new()); // Drained by the host
+    static ref PENDING_ENTER: Mutex<HashSet<String>> = Mutex::new(HashSet::new()); // Play `enter` on next layout
+    static ref PENDING_FLIP: Mutex<HashMap<String, (f32, f32)>> = Mutex::new(HashMap::new()); // Absolute x/y before a tree change
+    static ref EXIT_GHOSTS: Mutex<HashMap<String, ExitGhost>> = Mutex::new(HashMap::new());
     static ref PLUGIN_REGISTRY: Mutex<sdk::PluginRegistry> = Mutex::new({
         let mut reg = sdk::PluginRegistry::new();
         // Register Standard Library
@@ -112,10 +119,35 @@ lazy_static! {
     static ref PARTICLES_ENGINE: Mutex<plugins::visuals::ParticlesPlugin> = Mutex::new(plugins::visuals::ParticlesPlugin::new());
     static ref STATEMACHINE_ENGINE: Mutex<plugins::logic::StateMachinePlugin> = Mutex::new(plugins::logic::StateMachinePlugin::new("Idle"));
     static ref DIRTY_ELEMENTS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
-    static ref LOGIC_KERNEL: Mutex<plugins::logic_kernel::LogicKernel> = Mutex::new(plugins::logic_kernel::LogicKernel::new());
+    static ref VARIABLE_CHANGES: Mutex<Vec<VariableChange>> = Mutex::new(Vec::new()); // Global writes from logic, drained by the host
+    static ref LOGIC_KERNEL: Mutex<plugins::logic_kernel::LogicKernel> = Mutex::new({
+        let mut kernel = plugins::logic_kernel::LogicKernel::new();
+        kernel.nodes.extend(PLUGIN_REGISTRY.lock().unwrap().nodes().clone()); // Node types from built-in plugins
+        kernel
+    });
+    static ref FRAME_SCHEDULER: Mutex<plugins::scheduler::FrameScheduler> = Mutex::new({
+        use plugins::scheduler::FramePhase;
+        let mut scheduler = plugins::scheduler::FrameScheduler::new(1.0 / 60.0);
+        // Built-in engine systems, in phase order
+        scheduler.add_system(FramePhase::Logic, dispatch_animation_events);
+        scheduler.add_system(FramePhase::Logic, resume_due_executions);
+        scheduler.add_system(FramePhase::Physics, step_physics);
+        scheduler.add_system(FramePhase::Animation, step_animations);
+        scheduler.add_system(FramePhase::Render, render_frame);
+        scheduler
+    });
 }
 
-pub use plugins::spatial_index::{update_element_bounds, remove_element_bounds, hit_test, clear_spatial_index, SPATIAL_INDEX, get_element_bounds};
+pub use plugins::selection::{get_selection, select_elements, toggle_selection, clear_selection, select_all, marquee_select, get_selection_deltas};
+pub use plugins::resize::{begin_resize, update_resize, end_resize, resize_elements};
+pub use plugins::arrange::{align_elements, distribute_elements, tidy_elements};
+pub use plugins::drop_target::resolve_drop_target;
+pub use plugins::camera::{set_viewport_size, get_camera, pan_camera, zoom_camera_at, fit_to_selection, fit_to_page, visible_elements};
+pub use plugins::pointer::{pointer_move, pointer_down, pointer_up, pointer_leave, get_hover_stack};
+pub use plugins::navigation::perform_action;
+pub use plugins::expression::evaluate_expression;
+pub use plugins::measure::{measure_elements, measure_selection_to_hovered, get_box_model};
+pub use plugins::spatial_index::{update_element_bounds, remove_element_bounds, hit_test, clear_spatial_index, SPATIAL_INDEX, get_element_bounds, ElementBounds};
 
 #[wasm_bindgen]
 pub fn sync_state(json_state: &str) -> Result<(), JsValue> {
@@ -141,6 +173,7 @@ pub fn sync_state(json_state: &str) -> Result<(), JsValue> {
         for bp in s.blueprints.values() {
             kernel.register_blueprint(bp.clone());
         }
+        kernel.variables.globals = s.global_variables.values().map(|v| (v.name.clone(), v.value.clone())).collect();
     }
 
     Ok(())
@@ -243,7 +276,7 @@ pub fn compute_layout() -> Result<String, JsValue> {
     }
 
     // NEW: Sync animation targets after layout computation
-    update_animation_targets_internal(&taffy, &tree_map);
+    update_animation_targets_internal(&taffy, &tree_map, state);
 
     // NEW: Batch 24.3 - Update Spatial Index with Absolute Coordinates
     update_spatial_index_internal(&taffy, &tree_map);
@@ -252,21 +285,16 @@ pub fn compute_layout() -> Result<String, JsValue> {
 }
 
 fn update_spatial_index_internal(taffy: &Taffy, tree_map: &HashMap<String, Node>) {
-    let mut index = SPATIAL_INDEX.lock().unwrap();
-    index.clear();
-
-    for (id, node) in tree_map.iter() {
-        if let Ok(layout) = taffy.layout(*node) {
-             let (x, y) = get_absolute_position_internal(taffy, *node);
-             index.insert_or_update(
-                id.clone(),
-                x,
-                y,
-                layout.size.width,
-                layout.size.height
-            );
-        }
-    }
+    let items: Vec<ElementBounds> = tree_map.iter()
+        .filter_map(|(id, node)| {
+            let layout = taffy.layout(*node).ok()?;
+            let (x, y) = get_absolute_position_internal(taffy, *node);
+            Some(ElementBounds::new(id.clone(), x, y, layout.size.width, layout.size.height))
+        })
+        .collect();
+
+    // Only elements whose box changed touch the R-tree
+    SPATIAL_INDEX.lock().unwrap().sync(items);
 }
 
 fn get_absolute_position_internal(taffy: &Taffy, node: Node) -> (f32, f32) {
@@ -290,80 +318,367 @@ fn get_absolute_position_internal(taffy: &Taffy, node: Node) -> (f32, f32) {
     (x, y)
 }
 
-fn update_animation_targets_internal(taffy: &Taffy, tree_map: &HashMap<String, Node>) {
+fn update_animation_targets_internal(taffy: &Taffy, tree_map: &HashMap<String, Node>, state: &ProjectState) {
     let mut anim_state = ANIMATION_STATE.lock().unwrap();
+    let mut events = ANIMATION_EVENTS.lock().unwrap();
+    let mut entering = PENDING_ENTER.lock().unwrap();
+    let mut flips = PENDING_FLIP.lock().unwrap();
 
     for (id, node) in tree_map.iter() {
         let layout = taffy.layout(*node).unwrap();
-        let target_x = layout.location.x;
-        let target_y = layout.location.y;
-        let target_w = layout.size.width;
-        let target_h = layout.size.height;
+        let el = &state.elements[id];
+        let styles = resolve_final_styles(el, &state.view_mode);
+        let spec = TransitionSpec::resolve(&styles, &el.props);
+        let transition_for = |key: &str| spec.as_ref().and_then(|s| s.for_property(key));
 
         let props = anim_state.entry(id.clone()).or_insert_with(|| HashMap::new());
-        
-        let update_prop = |props: &mut HashMap<String, AnimationValue>, key: &str, target: f32| {
-            let val = props.entry(key.to_string()).or_insert(AnimationValue {
-                current: target, target, velocity: 0.0
-            });
-            val.target = target;
+        let was_idle = props.values().all(|v| v.sleeping);
+        let mut changes: Vec<(String, AnimationEventKind)> = Vec::new();
+
+        // Geometry always animates; elements without a `transition` keep the neutral spring
+        let geometry = [
+            ("x", layout.location.x),
+            ("y", layout.location.y),
+            ("width", layout.size.width),
+            ("height", layout.size.height),
+        ];
+        for (key, target) in geometry {
+            let val = props.entry(key.to_string()).or_insert_with(|| AnimationValue::at_rest(target));
+            if let Some(kind) = val.retarget(target, transition_for(key).unwrap_or_default()) {
+                changes.push((key.to_string(), kind));
+            }
+        }
+
+        // Numeric style properties (opacity, borderRadius, ...) animate when a transition covers them,
+        // and snap once a previously animated property is no longer covered
+        for (key, value) in &styles {
+            if plugins::animation::is_layout_property(key) { continue; }
+            let target = match plugins::animation::parse_numeric(value) {
+                Some(t) => t,
+                None => continue,
+            };
+            let transition = match transition_for(key) {
+                Some(t) => t,
+                None if props.contains_key(key.as_str()) => TransitionConfig::instant(),
+                None => continue,
+            };
+            let val = props.entry(key.clone()).or_insert_with(|| AnimationValue::at_rest(target));
+            if let Some(kind) = val.retarget(target, transition) {
+                changes.push((key.clone(), kind));
+            }
+        }
+
+        // FLIP: elements moved by a tree change start from where they were drawn before it
+        if let Some((first_x, first_y)) = flips.remove(id) {
+            let (last_x, last_y) = get_absolute_position_internal(taffy, *node);
+            for (key, delta) in [("x", first_x - last_x), ("y", first_y - last_y)] {
+                if delta.abs() < 0.5 { continue; }
+                if let Some(val) = props.get_mut(key) {
+                    if let Some(kind) = val.jump_to(val.target + delta, transition_for(key).unwrap_or_default()) {
+                        changes.push((key.to_string(), kind));
+                    }
+                }
+            }
+        }
+
+        // Enter: newly added elements start from their `enter` values
+        if entering.remove(id) {
+            if let Some(enter) = PresenceConfig::resolve("enter", &styles, &el.props) {
+                for (key, start) in &enter.values {
+                    let transition = enter.transition.or_else(|| transition_for(key)).unwrap_or_default();
+                    let kind = if plugins::animation::is_geometry(key) {
+                        props.get_mut(key.as_str()).and_then(|val| val.jump_to(val.target + start, transition))
+                    } else {
+                        let target = styles.get(key.as_str())
+                            .and_then(plugins::animation::parse_numeric)
+                            .unwrap_or_else(|| plugins::animation::initial_value(key));
+                        let val = props.entry(key.clone()).or_insert_with(|| AnimationValue::at_rest(target));
+                        val.target = target;
+                        val.jump_to(*start, transition)
+                    };
+                    if let Some(kind) = kind {
+                        changes.push((key.clone(), kind));
+                    }
+                }
+            }
+        }
+
+        let keys_of = |wanted: AnimationEventKind| -> Vec<String> {
+            changes.iter().filter(|(_, k)| *k == wanted).map(|(key, _)| key.clone()).collect()
         };
+        let interrupted = keys_of(AnimationEventKind::Interrupt);
+        let started = keys_of(AnimationEventKind::Start);
+
+        if !interrupted.is_empty() {
+            events.push(AnimationEvent { element_id: id.clone(), kind: AnimationEventKind::Interrupt, properties: interrupted });
+        } else if was_idle && !started.is_empty() {
+            events.push(AnimationEvent { element_id: id.clone(), kind: AnimationEventKind::Start, properties: started });
+        }
+    }
+
+    // Snapshots only describe the tree change that was just laid out
+    flips.clear();
+}
+
+/// FLIP "first" step: remembers where elements are drawn right now
+/// (layout position plus any in-flight animation offset) before a tree change moves them.
+fn capture_flip_positions(ids: &[String]) {
+    let index = SPATIAL_INDEX.lock().unwrap();
+    let anim_state = ANIMATION_STATE.lock().unwrap();
+    let mut flips = PENDING_FLIP.lock().unwrap();
+
+    for id in ids {
+        if let Some(bounds) = index.query_one(id) {
+            let offset = |key: &str| anim_state.get(id)
+                .and_then(|props| props.get(key))
+                .map(|v| v.current - v.target)
+                .unwrap_or(0.0);
+            flips.entry(id.clone()).or_insert((bounds.min_x + offset("x"), bounds.min_y + offset("y")));
+        }
+    }
+}
+
+/// Replaces the element's animation state with an exit ghost if it declares `exit` values.
+/// Returns false when the element has no exit animation (it simply disappears).
+fn start_exit_animation(element: &DesignerElement, styles: &ElementStyles) -> bool {
+    let values = ANIMATION_STATE.lock().unwrap().remove(&element.id).unwrap_or_default();
+    let exit = match PresenceConfig::resolve("exit", styles, &element.props) {
+        Some(e) => e,
+        None => return false,
+    };
+    let (x, y, w, h) = match SPATIAL_INDEX.lock().unwrap().get_bounds(&element.id) {
+        Some(b) => b,
+        None => return false,
+    };
+
+    let offset = |key: &str| values.get(key).map(|v| v.current - v.target).unwrap_or(0.0);
+    let bounds = (x + offset("x"), y + offset("y"), w + offset("width"), h + offset("height"));
+    let current: HashMap<String, f32> = values.iter().map(|(k, v)| (k.clone(), v.current)).collect();
+    let spec = TransitionSpec::resolve(styles, &element.props);
+
+    let ghost = ExitGhost::new(&element.id, element.parent_id.clone(), bounds, &current, &exit, spec.as_ref());
+    let properties = ghost.values.keys().cloned().collect();
+    EXIT_GHOSTS.lock().unwrap().insert(element.id.clone(), ghost);
+    ANIMATION_EVENTS.lock().unwrap().push(AnimationEvent { element_id: element.id.clone(), kind: AnimationEventKind::Start, properties });
+    true
+}
+
+/// Removed elements still animating out, as drawable snapshots (`id`, `parentId`, box, `styles`).
+#[wasm_bindgen]
+pub fn get_exit_ghosts() -> String {
+    let ghosts = EXIT_GHOSTS.lock().unwrap();
+    let snapshots: Vec<serde_json::Value> = ghosts.values().map(|g| g.snapshot()).collect();
+    serde_json::to_string(&snapshots).unwrap_or("[]".to_string())
+}
+
+/// Prototype playback: plays the element's `enter` animation as if it had just been added.
+#[wasm_bindgen]
+pub fn play_enter_animation(id: &str) -> bool {
+    PENDING_ENTER.lock().unwrap().insert(id.to_string());
+    compute_layout().is_ok()
+}
 
-        update_prop(props, "x", target_x);
-        update_prop(props, "y", target_y);
-        update_prop(props, "width", target_w);
-        update_prop(props, "height", target_h);
+/// Prototype playback: plays the element's `exit` animation without removing it from the document.
+#[wasm_bindgen]
+pub fn play_exit_animation(id: &str) -> bool {
+    let resolved = {
+        let state_lock = PROJECT_STATE.lock().unwrap();
+        state_lock.as_ref()
+            .and_then(|state| state.elements.get(id).map(|el| (el.clone(), resolve_final_styles(el, &state.view_mode))))
+    };
+    match resolved {
+        Some((el, styles)) => start_exit_animation(&el, &styles),
+        None => false,
     }
 }
 
+/// Single frame entry point. Runs as many fixed simulation steps as `real_dt` covers
+/// (input, logic, physics, animation), then one render pass. Returns the interpolation alpha.
+#[wasm_bindgen]
+pub fn tick(real_dt: f32) -> f32 {
+    plugins::scheduler::run_frame(&FRAME_SCHEDULER, real_dt).alpha
+}
+
+/// Legacy frame entry point, kept for hosts that have not moved to `tick`.
 #[wasm_bindgen]
 pub fn update_animations(dt: f32) {
+    tick(dt);
+}
+
+#[wasm_bindgen]
+pub fn set_fixed_timestep(hz: f32) {
+    FRAME_SCHEDULER.lock().unwrap().set_fixed_rate(hz);
+}
+
+#[wasm_bindgen]
+pub fn get_frame_info() -> String {
+    let scheduler = FRAME_SCHEDULER.lock().unwrap();
+    serde_json::json!({
+        "frame": scheduler.frame_count,
+        "step": scheduler.step_count,
+        "alpha": scheduler.alpha,
+        "fixedDt": scheduler.fixed_dt,
+    }).to_string()
+}
+
+#[wasm_bindgen]
+pub fn start_frame_recording() {
+    FRAME_SCHEDULER.lock().unwrap().start_recording();
+}
+
+/// Returns the recorded frame times as a JSON array, suitable for `replay_frames`.
+#[wasm_bindgen]
+pub fn stop_frame_recording() -> String {
+    let frames = FRAME_SCHEDULER.lock().unwrap().stop_recording();
+    serde_json::to_string(&frames).unwrap_or("[]".to_string())
+}
+
+/// Re-runs a recorded sequence of frame times from step zero. Returns the number of fixed steps run.
+#[wasm_bindgen]
+pub fn replay_frames(frames_json: &str) -> Result<u64, JsValue> {
+    let frames: Vec<f32> = serde_json::from_str(frames_json)
+        .map_err(|e| JsValue::from_str(&format!("Replay Parse Error: {}", e)))?;
+
+    FRAME_SCHEDULER.lock().unwrap().reset();
+    for dt in frames {
+        plugins::scheduler::run_frame(&FRAME_SCHEDULER, dt);
+    }
+    Ok(FRAME_SCHEDULER.lock().unwrap().step_count)
+}
+
+fn step_physics(ctx: &plugins::scheduler::FrameContext) {
+    // Batch 9.1: Run Physics Step
+    PHYSICS_ENGINE.lock().unwrap().step(ctx.dt);
+    // Batch 9.2: Run Particles Step
+    PARTICLES_ENGINE.lock().unwrap().update(ctx.dt);
+}
+
+fn step_animations(ctx: &plugins::scheduler::FrameContext) {
     let mut anim_state = ANIMATION_STATE.lock().unwrap();
-    // Neutral Spring (matches framer-motion defaults roughly)
-    let stiffness = 170.0;
-    let damping = 26.0;
-    let mass = 1.0;
-
-    for props in anim_state.values_mut() {
-        for val in props.values_mut() {
-            // Spring equation: F = -kx - cv
-            let force = -stiffness * (val.current - val.target);
-            let damping_force = -damping * val.velocity;
-            let acceleration = (force + damping_force) / mass;
-            
-            val.velocity += acceleration * dt;
-            val.current += val.velocity * dt;
+    let mut events = ANIMATION_EVENTS.lock().unwrap();
+
+    for (id, props) in anim_state.iter_mut() {
+        // Sleeping values return immediately, so idle documents cost one flag check per value
+        let settled: Vec<String> = props.iter_mut()
+            .filter_map(|(key, val)| if val.step(ctx.dt) { Some(key.clone()) } else { None })
+            .collect();
+
+        if !settled.is_empty() && props.values().all(|v| v.sleeping) {
+            events.push(AnimationEvent { element_id: id.clone(), kind: AnimationEventKind::Complete, properties: settled });
         }
     }
 
-    // --- PLUGIN RENDER HOOK ---
-    // We treat update_animations as the "frame" for now for visual effects
+    // Keyframe timelines drive their channels directly, after transitions
+    let samples = plugins::timeline::TIMELINE_ENGINE.lock().unwrap().advance(ctx.dt);
+    plugins::timeline::apply_samples(&mut anim_state, &samples);
+
+    // Exit ghosts are dropped once they settle
+    EXIT_GHOSTS.lock().unwrap().retain(|id, ghost| {
+        if !ghost.step(ctx.dt) { return true; }
+        events.push(AnimationEvent { element_id: id.clone(), kind: AnimationEventKind::Complete, properties: ghost.values.keys().cloned().collect() });
+        false
+    });
+}
+
+fn render_frame(ctx: &plugins::scheduler::FrameContext) {
     let state_lock = PROJECT_STATE.lock().unwrap();
     if let Some(_) = &*state_lock {
          // In a real engine we'd get canvas dimensions. Partial mock for now.
-         // We use static counter for frame_count
-         static mut FRAME_COUNTER: u64 = 0;
-         unsafe { FRAME_COUNTER += 1; }
-         
-         let ctx = sdk::RenderContext {
+         let render_ctx = sdk::RenderContext {
              width: 1920.0,
              height: 1080.0,
-             frame_count: unsafe { FRAME_COUNTER },
+             frame_count: ctx.frame,
+             dt: ctx.dt,
+             alpha: ctx.alpha,
          };
          
          let registry = PLUGIN_REGISTRY.lock().unwrap();
-         registry.render_all(&ctx);
-         
-         // Batch 9.1: Run Physics Step
-         let physics = PHYSICS_ENGINE.lock().unwrap();
-         physics.render(&ctx);
+         registry.render_all(&render_ctx);
+
+         PARTICLES_ENGINE.lock().unwrap().draw();
+    }
+}
+
+const MAX_ANIMATION_EVENT_LOG: usize = 256;
+
+/// Routes queued lifecycle events to the owning element's blueprint (`on_animation_end`, ...)
+/// and keeps them for the host to drain.
+fn dispatch_animation_events(_ctx: &plugins::scheduler::FrameContext) {
+    let events = std::mem::take(&mut *ANIMATION_EVENTS.lock().unwrap());
+    if events.is_empty() { return; }
+
+    // Resolve blueprints first so the kernel never runs while the project lock is held
+    let routes: Vec<(String, &AnimationEvent)> = {
+        let state_lock = PROJECT_STATE.lock().unwrap();
+        match &*state_lock {
+            Some(state) => events.iter()
+                .filter_map(|ev| state.elements.get(&ev.element_id)
+                    .and_then(|el| el.blueprint_id.clone())
+                    .map(|bp| (bp, ev)))
+                .collect(),
+            None => Vec::new(),
+        }
+    };
+
+    for (blueprint_id, ev) in routes {
+        let payload = serde_json::json!({ "elementId": ev.element_id, "properties": ev.properties });
+        run_logic(&blueprint_id, ev.kind.trigger_name(), &payload);
+    }
 
-         // Batch 9.2: Run Particles Step
-         let particles = PARTICLES_ENGINE.lock().unwrap();
-         particles.render(&ctx);
+    let mut log = ANIMATION_EVENT_LOG.lock().unwrap();
+    log.extend(events);
+    if log.len() > MAX_ANIMATION_EVENT_LOG {
+        let overflow = log.len() - MAX_ANIMATION_EVENT_LOG;
+        log.drain(..overflow);
     }
 }
 
+#[wasm_bindgen]
+pub fn drain_animation_events() -> String {
+    let mut log = ANIMATION_EVENT_LOG.lock().unwrap();
+    let json = serde_json::to_string(&*log).unwrap_or("[]".to_string());
+    log.clear();
+    json
+}
+
+/// False once every animated value is asleep and no timeline is playing; the host can stop its frame loop.
+#[wasm_bindgen]
+pub fn is_animating() -> bool {
+    let anim_state = ANIMATION_STATE.lock().unwrap();
+    let springs_awake = anim_state.values().any(|props| props.values().any(|v| !v.sleeping));
+    springs_awake || plugins::timeline::TIMELINE_ENGINE.lock().unwrap().timelines.values().any(|tl| tl.playback.playing)
+}
+
+#[wasm_bindgen]
+pub fn get_animated_styles(id: &str) -> String {
+    let anim_state = ANIMATION_STATE.lock().unwrap();
+    let values: HashMap<&str, f32> = anim_state.get(id)
+        .map(|props| props.iter()
+            .filter(|(k, _)| !matches!(k.as_str(), "x" | "y" | "width" | "height"))
+            .map(|(k, v)| (k.as_str(), v.current))
+            .collect())
+        .unwrap_or_default();
+    serde_json::to_string(&values).unwrap_or("{}".to_string())
+}
+
+/// Base styles with the active breakpoint's overrides (tablet / mobile) merged on top.
+fn resolve_final_styles(el: &DesignerElement, view_mode: &str) -> ElementStyles {
+    let mut final_styles = el.styles.clone().unwrap_or(HashMap::new());
+
+    let overrides = match view_mode {
+        "tablet" => el.tablet_styles.as_ref(),
+        "mobile" => el.mobile_styles.as_ref(),
+        _ => None,
+    };
+    if let Some(os) = overrides {
+        for (k, v) in os {
+            final_styles.insert(k.clone(), v.clone());
+        }
+    }
+
+    final_styles
+}
+
 fn build_node(
     id: &str, 
     state: &ProjectState, 
@@ -374,21 +689,7 @@ fn build_node(
     let mut style = Style::default();
 
     // Determine active styles based on view_mode
-    let mut final_styles = el.styles.clone().unwrap_or(HashMap::new());
-
-    if state.view_mode == "tablet" {
-        if let Some(ts) = &el.tablet_styles {
-             for (k, v) in ts {
-                 final_styles.insert(k.clone(), v.clone());
-             }
-        }
-    } else if state.view_mode == "mobile" {
-        if let Some(ms) = &el.mobile_styles {
-             for (k, v) in ms {
-                 final_styles.insert(k.clone(), v.clone());
-             }
-        }
-    }
+    let final_styles = resolve_final_styles(el, &state.view_mode);
 
     // Map Final Styles to Taffy Style
     let s = &final_styles;
@@ -414,6 +715,13 @@ fn build_node(
         _ => style.flex_direction = FlexDirection::Row,
     }
 
+    // Flex Wrap
+    match get_str("flexWrap") {
+        Some("wrap") => style.flex_wrap = FlexWrap::Wrap,
+        Some("wrap-reverse") => style.flex_wrap = FlexWrap::WrapReverse,
+        _ => {}
+    }
+
     // Justify Content
     if let Some(jc) = get_str("justifyContent") {
         style.justify_content = match jc {
@@ -466,6 +774,10 @@ fn build_node(
         let val = parse_length_percentage(p);
         style.padding = Rect { left: val, right: val, top: val, bottom: val };
     }
+    if let Some(m) = get_val("margin") {
+        let val = parse_length_percentage_auto(m);
+        style.margin = Rect { left: val, right: val, top: val, bottom: val };
+    }
 
 
     // Recursively build children
@@ -595,7 +907,7 @@ pub fn fire_trigger(blueprint_id: &str, trigger_type: &str, payload_json: &str)
     let payload: serde_json::Value = serde_json::from_str(payload_json)
         .unwrap_or(serde_json::Value::Null);
     
-    LOGIC_KERNEL.lock().unwrap().execute(blueprint_id, trigger_type, &payload);
+    run_logic(blueprint_id, trigger_type, &payload);
     Ok(())
 }
 
@@ -846,16 +1158,109 @@ pub fn fire_blueprint_trigger(blueprint_id: &str, trigger: &str, _payload: &str)
 
 #[wasm_bindgen]
 pub fn set_variable(id: &str, value_json: &str) -> bool {
+    let updated = {
+        let mut state_guard = PROJECT_STATE.lock().unwrap();
+        let state = match state_guard.as_mut() { Some(s) => s, None => return false };
+        let val: serde_json::Value = match serde_json::from_str(value_json) { Ok(v) => v, Err(_) => return false };
+        match state.global_variables.get_mut(id) {
+            Some(var) => {
+                var.value = val.clone();
+                let name = var.name.clone();
+                apply_variable_bindings(state, &name, id, &val);
+                Some((name, val))
+            }
+            None => None,
+        }
+    };
+    // Keep logic reading the same value
+    match updated {
+        Some((name, val)) => {
+            LOGIC_KERNEL.lock().unwrap().variables.globals.insert(name, val);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Runs a logic trigger, then publishes the global variables it wrote.
+/// The project lock must not be held by the caller.
+/// A run aborted by an execution limit is logged and kept for `get_execution_error`.
+pub(crate) fn run_logic(blueprint_id: &str, trigger_type: &str, payload: &serde_json::Value) {
+    let _ = with_logic(|kernel| kernel.execute(blueprint_id, trigger_type, payload));
+}
+
+/// Runs `f` on the kernel (execute, resume, ...), then publishes the global variables it wrote.
+fn with_logic<R>(f: impl FnOnce(&mut plugins::logic_kernel::LogicKernel) -> R) -> R {
+    let (result, changes) = {
+        let mut kernel = LOGIC_KERNEL.lock().unwrap();
+        let result = f(&mut kernel);
+        (result, kernel.variables.drain_changes())
+    };
+    apply_variable_changes(changes);
+    result
+}
+
+/// Logic phase: resumes wait/delay nodes (and awaited effects that timed out) on the fixed-step clock.
+fn resume_due_executions(ctx: &plugins::scheduler::FrameContext) {
+    with_logic(|kernel| kernel.advance_clock(ctx.dt as f64));
+}
+
+/// Writes logic-made global changes into ProjectState (creating globals declared only in logic),
+/// updates bound elements and logs the changes for the host.
+fn apply_variable_changes(changes: Vec<VariableChange>) {
+    if changes.is_empty() { return; }
     let mut state_guard = PROJECT_STATE.lock().unwrap();
-    if let Some(state) = state_guard.as_mut() {
-        if let Some(var) = state.global_variables.get_mut(id) {
-            if let Ok(val) = serde_json::from_str(value_json) {
-                var.value = val;
-                return true;
+    let mut log = VARIABLE_CHANGES.lock().unwrap();
+
+    for mut change in changes {
+        if let Some(state) = state_guard.as_mut() {
+            let id = match state.global_variables.values_mut().find(|v| v.name == change.name) {
+                Some(var) => {
+                    var.value = change.value.clone();
+                    var.id.clone()
+                }
+                None => {
+                    let var = LogicVariable {
+                        id: change.name.clone(),
+                        name: change.name.clone(),
+                        r#type: plugins::expression::type_name(&change.value).to_string(),
+                        value: change.value.clone(),
+                    };
+                    state.global_variables.insert(var.id.clone(), var);
+                    change.name.clone()
+                }
+            };
+            apply_variable_bindings(state, &change.name, &id, &change.value);
+            change.id = Some(id);
+        }
+        log.push(change);
+    }
+}
+
+/// Pushes a variable's value into every element bound to it (`variableBindings`: prop -> variable
+/// name or id) and marks those elements dirty so the change shows up in `get_state_deltas`.
+fn apply_variable_bindings(state: &mut ProjectState, name: &str, id: &str, value: &serde_json::Value) {
+    let mut dirty = DIRTY_ELEMENTS.lock().unwrap();
+    for el in state.elements.values_mut() {
+        let props: Vec<String> = match &el.variable_bindings {
+            Some(bindings) => bindings.iter().filter(|(_, v)| v.as_str() == name || v.as_str() == id).map(|(p, _)| p.clone()).collect(),
+            None => continue,
+        };
+        for prop in &props {
+            match prop.as_str() {
+                "content" => el.content = Some(plugins::expression::to_text(value)),
+                _ => { el.styles.get_or_insert_with(HashMap::new).insert(prop.clone(), value.clone()); }
             }
         }
+        if !props.is_empty() { dirty.insert(el.id.clone()); }
     }
-    false
+}
+
+/// Global variable changes made by logic since the last call (`id`, `name`, `value`, `previous`, `blueprintId`).
+#[wasm_bindgen]
+pub fn get_variable_changes() -> String {
+    let changes = std::mem::take(&mut *VARIABLE_CHANGES.lock().unwrap());
+    serde_json::to_string(&changes).unwrap_or("[]".to_string())
 }
 
 #[wasm_bindgen]
@@ -871,7 +1276,7 @@ pub fn get_variable(id: &str) -> String {
 
 // --- PHASE 10: HYPER COMMAND PROTOCOL ---
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct HyperCommand {
     pub id: String,
     pub action: String,
@@ -880,6 +1285,26 @@ pub struct HyperCommand {
     pub timestamp: u64,
 }
 
+static ENGINE_COMMAND_SEQ: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+impl HyperCommand {
+    /// Engine-generated command; the host stamps `timestamp` when it records history.
+    pub fn new(action: &str, target_id: &str, payload: serde_json::Value) -> Self {
+        let seq = ENGINE_COMMAND_SEQ.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        Self {
+            id: format!("engine-{}", seq),
+            action: action.to_string(),
+            target_id: target_id.to_string(),
+            payload,
+            timestamp: 0,
+        }
+    }
+
+    pub fn update_style(target_id: &str, updates: serde_json::Value, view_mode: &str) -> Self {
+        Self::new("UPDATE_STYLE", target_id, serde_json::json!({ "updates": updates, "viewMode": view_mode }))
+    }
+}
+
 #[wasm_bindgen]
 pub fn apply_command(command_json: &str) -> bool {
     let cmd: HyperCommand = match serde_json::from_str(command_json) {
@@ -932,6 +1357,11 @@ pub fn apply_command(command_json: &str) -> bool {
                 // Track change for Batch 10.2
                 DIRTY_ELEMENTS.lock().unwrap().insert(cmd.target_id.clone());
 
+                // Locking or hiding an element drops it from the selection
+                if prop == "locked" || prop == "visible" {
+                    plugins::selection::SELECTION.lock().unwrap().prune(&state.elements);
+                }
+
                 return true;
             }
         },
@@ -971,6 +1401,8 @@ pub fn apply_command(command_json: &str) -> bool {
                 // 2. Add to parent's children
                 if let Some(parent) = state.elements.get_mut(&parent_id) {
                     let children = parent.children.get_or_insert_with(|| Vec::new());
+                    // Siblings animate from their current spot to make room (FLIP)
+                    capture_flip_positions(children);
                     if let Some(idx) = index {
                         if idx <= children.len() {
                             children.insert(idx, element.id.clone());
@@ -981,6 +1413,8 @@ pub fn apply_command(command_json: &str) -> bool {
                         children.push(element.id.clone());
                     }
                 }
+
+                PENDING_ENTER.lock().unwrap().insert(element.id.clone());
                 
                 DIRTY_ELEMENTS.lock().unwrap().insert(parent_id);
                 DIRTY_ELEMENTS.lock().unwrap().insert(element.id);
@@ -990,14 +1424,19 @@ pub fn apply_command(command_json: &str) -> bool {
         "REMOVE_ELEMENT" => {
             let id = cmd.target_id.clone();
             if let Some(element) = state.elements.remove(&id) {
+                let styles = resolve_final_styles(&element, &state.view_mode);
+                start_exit_animation(&element, &styles);
+
                 if let Some(parent_id) = &element.parent_id {
                     if let Some(parent) = state.elements.get_mut(parent_id) {
                         if let Some(children) = &mut parent.children {
                             children.retain(|c| c != &id);
+                            capture_flip_positions(children);
                         }
                     }
                     DIRTY_ELEMENTS.lock().unwrap().insert(parent_id.clone());
                 }
+                plugins::selection::SELECTION.lock().unwrap().prune(&state.elements);
                 return true;
             }
         },
@@ -1009,6 +1448,7 @@ pub fn apply_command(command_json: &str) -> bool {
              if let Some(parent) = state.elements.get_mut(&parent_id) {
                  if let Some(children) = &mut parent.children {
                      if let Some(old_index) = children.iter().position(|c| c == &element_id) {
+                         capture_flip_positions(children);
                          children.remove(old_index);
                          let target_idx = if new_index > children.len() { children.len() } else { new_index };
                          children.insert(target_idx, element_id);
@@ -1067,7 +1507,142 @@ pub fn trigger_logic(blueprint_id: &str, trigger_type: &str, payload_json: &str)
     let payload: serde_json::Value = serde_json::from_str(payload_json)
         .unwrap_or(serde_json::Value::Null);
     
-    LOGIC_KERNEL.lock().unwrap().execute(blueprint_id, trigger_type, &payload);
+    run_logic(blueprint_id, trigger_type, &payload);
+    Ok(())
+}
+
+/// Unhandled node errors (`blueprintId`, `nodeId`, `code`, `message`) from the most recent logic execution
+/// and the resumptions since. Failures handled on an `error` port or by a `try` node are not listed.
+#[wasm_bindgen]
+pub fn get_logic_errors() -> String {
+    serde_json::to_string(&LOGIC_KERNEL.lock().unwrap().errors).unwrap_or("[]".to_string())
+}
+
+/// Why the most recent logic run was aborted (`kind`: gasExhausted, timeout, queueOverflow,
+/// depthExceeded, valueTooLarge, ...), or null if it finished.
+#[wasm_bindgen]
+pub fn get_execution_error() -> String {
+    serde_json::to_string(&LOGIC_KERNEL.lock().unwrap().error).unwrap_or("null".to_string())
+}
+
+/// Replaces the kernel's execution limits (`maxSteps`, `nodeCosts`, `maxValueBytes`, `maxDurationMs`, ...).
+/// Missing fields keep their defaults.
+#[wasm_bindgen]
+pub fn set_execution_limits(limits_json: &str) -> Result<(), JsValue> {
+    let limits: plugins::metering::ExecutionLimits = serde_json::from_str(limits_json)
+        .map_err(|e| JsValue::from_str(&format!("Invalid limits: {}", e)))?;
+    LOGIC_KERNEL.lock().unwrap().limits = limits;
+    Ok(())
+}
+
+/// Registers a plugin at runtime. Logic node types it adds in `on_register` become available immediately.
+pub fn register_plugin(plugin: Box<dyn sdk::OmniosPlugin>) {
+    let nodes = PLUGIN_REGISTRY.lock().unwrap().register(plugin);
+    LOGIC_KERNEL.lock().unwrap().nodes.extend(nodes);
+}
+
+/// Every logic node type the kernel can run (`nodeType`, `inputs`, `outputs`, `fields`), for the logic editor's palette.
+#[wasm_bindgen]
+pub fn get_node_types() -> String {
+    serde_json::to_string(&LOGIC_KERNEL.lock().unwrap().nodes.specs()).unwrap_or("[]".to_string())
+}
+
+/// Static checks for a blueprint (the given JSON, or a registered blueprint id), against the project's
+/// blueprints and global variables. Returns findings: `severity`, `code`, `message`, `nodeIds`, `connectionIds`.
+#[wasm_bindgen]
+pub fn validate_blueprint(blueprint: &str) -> Result<String, JsValue> {
+    let mut ctx = plugins::validator::ValidationContext::default();
+    let registered = {
+        let kernel = LOGIC_KERNEL.lock().unwrap();
+        ctx.nodes = kernel.nodes.clone();
+        ctx.blueprints.extend(kernel.blueprints.keys().cloned());
+        kernel.blueprints.get(blueprint).map(|bp| (**bp).clone())
+    };
+    if let Some(state) = PROJECT_STATE.lock().unwrap().as_ref() {
+        ctx.blueprints.extend(state.blueprints.keys().cloned());
+        ctx.globals.extend(state.global_variables.values().map(|v| (v.name.clone(), v.r#type.clone())));
+    }
+
+    let bp = match registered {
+        Some(bp) => bp,
+        None => serde_json::from_str::<UnifiedBlueprint>(blueprint)
+            .map_err(|e| JsValue::from_str(&format!("Invalid blueprint: {}", e)))?,
+    };
+    let findings = plugins::validator::validate(&bp, &ctx);
+    serde_json::to_string(&findings).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Structured trace of the last logic execution (and its resumptions): one event per node with
+/// the port taken, variables before/after and steps used.
+#[wasm_bindgen]
+pub fn get_logic_trace() -> String {
+    serde_json::to_string(&LOGIC_KERNEL.lock().unwrap().trace).unwrap_or("{}".to_string())
+}
+
+/// With debugging on, a run halts before any node with a breakpoint until `replay_logic` carries
+/// it on. Turning it off lets a paused run finish.
+#[wasm_bindgen]
+pub fn set_logic_replay(enabled: bool) {
+    with_logic(|kernel| kernel.set_debugging(enabled));
+}
+
+/// Replaces the breakpoints (JSON array of node ids).
+#[wasm_bindgen]
+pub fn set_logic_breakpoints(node_ids: &str) -> Result<(), JsValue> {
+    let ids: Vec<String> = serde_json::from_str(node_ids)
+        .map_err(|e| JsValue::from_str(&format!("Invalid breakpoints: {}", e)))?;
+    LOGIC_KERNEL.lock().unwrap().debugger.breakpoints = ids.into_iter().collect();
+    Ok(())
+}
+
+/// `stepInto`, `stepOver`, `stepOut` or `continue` the paused run. Returns the debug state
+/// (`paused`, `blueprintId`, `nodeId`, `depth`, `variables`); `nodeId` is the node about to run.
+#[wasm_bindgen]
+pub fn replay_logic(command: &str) -> Result<String, JsValue> {
+    let command = plugins::debugger::DebugCommand::parse(command)
+        .ok_or_else(|| JsValue::from_str(&format!("Unknown debug command: {}", command)))?;
+    let state = with_logic(|kernel| kernel.debug(command));
+    serde_json::to_string(&state).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn get_replay_state() -> String {
+    serde_json::to_string(&LOGIC_KERNEL.lock().unwrap().debug_state()).unwrap_or("{}".to_string())
+}
+
+/// Side effects queued by logic since the last call (`id`, `blueprintId`, `nodeId`, `type`, params).
+/// The host performs them: navigate, showAlert, httpRequest, setElementStyle, playAnimation, emitEvent.
+#[wasm_bindgen]
+pub fn drain_effects() -> String {
+    let effects = LOGIC_KERNEL.lock().unwrap().drain_effects();
+    serde_json::to_string(&effects).unwrap_or("[]".to_string())
+}
+
+/// Continues an execution suspended at a wait/await_effect node. `result_json` lands in the node's `resultVar`.
+#[wasm_bindgen]
+pub fn resume_execution(execution_id: &str, result_json: &str) -> Result<(), JsValue> {
+    let result: serde_json::Value = serde_json::from_str(result_json).unwrap_or(serde_json::Value::Null);
+    with_logic(|kernel| kernel.resume(execution_id, result)).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Reports an effect's result; resumes the execution awaiting it. Returns whether one was waiting.
+#[wasm_bindgen]
+pub fn resume_effect(effect_id: &str, result_json: &str) -> Result<bool, JsValue> {
+    let result: serde_json::Value = serde_json::from_str(result_json).unwrap_or(serde_json::Value::Null);
+    with_logic(|kernel| kernel.resume_effect(effect_id, result)).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Suspended executions (`clock`, `pending` records). Save this with the project to keep flows alive across reloads.
+#[wasm_bindgen]
+pub fn get_pending_executions() -> String {
+    serde_json::to_string(&LOGIC_KERNEL.lock().unwrap().executions).unwrap_or("{}".to_string())
+}
+
+#[wasm_bindgen]
+pub fn restore_pending_executions(json: &str) -> Result<(), JsValue> {
+    let saved: plugins::executions::ExecutionTable = serde_json::from_str(json)
+        .map_err(|e| JsValue::from_str(&format!("Invalid executions: {}", e)))?;
+    LOGIC_KERNEL.lock().unwrap().executions.restore(saved);
     Ok(())
 }
 
@@ -1101,6 +1676,27 @@ pub fn get_group_bounds(ids_json: &str) -> String {
 
 #[wasm_bindgen]
 pub fn find_snap_targets(element_id: &str, x: f32, y: f32, width: f32, height: f32) -> String {
-    let result = plugins::interaction::InteractionPlugin::find_snap_targets(element_id, x, y, width, height, 5.0);
+    let config = plugins::interaction::SNAP_CONFIG.lock().unwrap().clone();
+    let result = plugins::interaction::InteractionPlugin::find_snap_targets_with(element_id, x, y, width, height, &config);
     serde_json::to_string(&result).unwrap_or("{}".to_string())
 }
+
+/// Partial updates are merged, e.g. `{ "gridSize": 8, "baseline": 4 }` or `{ "columns": { "count": 12, "gutter": 24, "margin": 40 } }`.
+#[wasm_bindgen]
+pub fn set_snap_config(json: &str) -> Result<(), JsValue> {
+    let mut config = plugins::interaction::SNAP_CONFIG.lock().unwrap();
+    let mut merged = serde_json::to_value(&*config).unwrap_or(serde_json::Value::Null);
+    let updates: serde_json::Value = serde_json::from_str(json)
+        .map_err(|e| JsValue::from_str(&format!("Snap Config Parse Error: {}", e)))?;
+    if let (Some(target), Some(updates)) = (merged.as_object_mut(), updates.as_object()) {
+        for (k, v) in updates { target.insert(k.clone(), v.clone()); }
+    }
+    *config = serde_json::from_value(merged)
+        .map_err(|e| JsValue::from_str(&format!("Snap Config Error: {}", e)))?;
+    Ok(())
+}
+
+#[wasm_bindgen]
+pub fn get_snap_config() -> String {
+    serde_json::to_string(&*plugins::interaction::SNAP_CONFIG.lock().unwrap()).unwrap_or("{}".to_string())
+}
@@ -0,0 +1,704 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::ElementStyles;
+
+// --- EASING CURVES ---
+
+/// Timing function for duration-based tweens.
+/// Named CSS / framer-motion easings are normalised to their cubic-bezier form.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Easing {
+    Linear,
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+        if let Some(args) = name.strip_prefix("cubic-bezier(").and_then(|s| s.strip_suffix(')')) {
+            let pts: Vec<f32> = args.split(',').filter_map(|p| p.trim().parse::<f32>().ok()).collect();
+            if pts.len() == 4 {
+                return Some(Easing::CubicBezier(pts[0], pts[1], pts[2], pts[3]));
+            }
+            return None;
+        }
+
+        match name {
+            "linear" => Some(Easing::Linear),
+            "ease" => Some(Easing::CubicBezier(0.25, 0.1, 0.25, 1.0)),
+            "ease-in" | "easeIn" => Some(Easing::CubicBezier(0.42, 0.0, 1.0, 1.0)),
+            "ease-out" | "easeOut" => Some(Easing::CubicBezier(0.0, 0.0, 0.58, 1.0)),
+            "ease-in-out" | "easeInOut" => Some(Easing::CubicBezier(0.42, 0.0, 0.58, 1.0)),
+            "backIn" => Some(Easing::CubicBezier(0.36, 0.0, 0.66, -0.56)),
+            "backOut" => Some(Easing::CubicBezier(0.34, 1.56, 0.64, 1.0)),
+            _ => None,
+        }
+    }
+
+    /// Accepts a name (`"easeOut"`, `"cubic-bezier(...)"`) or a framer-style `[x1, y1, x2, y2]` array.
+    pub fn from_json(v: &serde_json::Value) -> Option<Self> {
+        if let Some(name) = v.as_str() {
+            return Self::from_name(name);
+        }
+        let pts: Vec<f32> = v.as_array()?.iter().filter_map(|p| p.as_f64()).map(|p| p as f32).collect();
+        if pts.len() == 4 {
+            Some(Easing::CubicBezier(pts[0], pts[1], pts[2], pts[3]))
+        } else {
+            None
+        }
+    }
+
+    /// Maps linear progress `t` (0..1) to eased progress.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                if t == 0.0 || t == 1.0 { return t; }
+                let s = solve_bezier_x(x1, x2, t);
+                sample_bezier(y1, y2, s)
+            }
+        }
+    }
+}
+
+// Bezier with fixed endpoints (0,0) and (1,1), evaluated per axis.
+fn sample_bezier(p1: f32, p2: f32, s: f32) -> f32 {
+    let c = 3.0 * p1;
+    let b = 3.0 * (p2 - p1) - c;
+    let a = 1.0 - c - b;
+    ((a * s + b) * s + c) * s
+}
+
+fn sample_bezier_derivative(p1: f32, p2: f32, s: f32) -> f32 {
+    let c = 3.0 * p1;
+    let b = 3.0 * (p2 - p1) - c;
+    let a = 1.0 - c - b;
+    (3.0 * a * s + 2.0 * b) * s + c
+}
+
+fn solve_bezier_x(x1: f32, x2: f32, x: f32) -> f32 {
+    // Newton-Raphson first (fast path), bisection if the slope is too flat.
+    let mut s = x;
+    for _ in 0..8 {
+        let err = sample_bezier(x1, x2, s) - x;
+        if err.abs() < 1e-6 { return s; }
+        let d = sample_bezier_derivative(x1, x2, s);
+        if d.abs() < 1e-6 { break; }
+        s -= err / d;
+    }
+
+    let (mut lo, mut hi) = (0.0f32, 1.0f32);
+    s = x;
+    for _ in 0..32 {
+        let v = sample_bezier(x1, x2, s);
+        if (v - x).abs() < 1e-6 { break; }
+        if v < x { lo = s; } else { hi = s; }
+        s = (lo + hi) / 2.0;
+    }
+    s
+}
+
+// --- TRANSITION CONFIG ---
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TransitionKind {
+    Spring { stiffness: f32, damping: f32, mass: f32 },
+    Tween { duration: f32, easing: Easing },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransitionConfig {
+    pub kind: TransitionKind,
+    pub delay: f32, // seconds
+    /// A spring is asleep once it is within `rest_delta` of its target and slower than `rest_speed` (units/s).
+    #[serde(default = "default_rest_threshold")]
+    pub rest_delta: f32,
+    #[serde(default = "default_rest_threshold")]
+    pub rest_speed: f32,
+}
+
+fn default_rest_threshold() -> f32 { 0.01 }
+
+impl Default for TransitionConfig {
+    fn default() -> Self {
+        // Neutral Spring (matches framer-motion defaults roughly)
+        Self {
+            kind: TransitionKind::Spring { stiffness: 170.0, damping: 26.0, mass: 1.0 },
+            delay: 0.0,
+            rest_delta: default_rest_threshold(),
+            rest_speed: default_rest_threshold(),
+        }
+    }
+}
+
+impl TransitionConfig {
+    /// Zero-length tween: the value snaps on the next frame.
+    pub fn instant() -> Self {
+        Self { kind: TransitionKind::Tween { duration: 0.0, easing: Easing::Linear }, ..Self::default() }
+    }
+}
+
+const CONFIG_KEYS: [&str; 9] = ["type", "stiffness", "damping", "mass", "duration", "ease", "delay", "restDelta", "restSpeed"];
+
+impl TransitionConfig {
+    /// Parses a framer-motion style object:
+    /// `{ "type": "spring", "stiffness": 300, "damping": 20 }` or `{ "duration": 0.3, "ease": "easeOut", "delay": 0.1 }`.
+    pub fn from_json(v: &serde_json::Value) -> Option<Self> {
+        let obj = v.as_object()?;
+        if !CONFIG_KEYS.iter().any(|k| obj.contains_key(*k)) {
+            return None;
+        }
+
+        let num = |key: &str| obj.get(key).and_then(|v| v.as_f64()).map(|v| v as f32);
+        let is_spring = match obj.get("type").and_then(|v| v.as_str()) {
+            Some("spring") => true,
+            Some(_) => false,
+            None => num("stiffness").is_some() || num("damping").is_some() || num("mass").is_some(),
+        };
+
+        let kind = if is_spring {
+            TransitionKind::Spring {
+                stiffness: num("stiffness").unwrap_or(170.0),
+                damping: num("damping").unwrap_or(26.0),
+                mass: num("mass").unwrap_or(1.0).max(0.001),
+            }
+        } else {
+            TransitionKind::Tween {
+                duration: num("duration").unwrap_or(0.3).max(0.0),
+                easing: obj.get("ease").and_then(Easing::from_json).unwrap_or(Easing::CubicBezier(0.25, 0.1, 0.25, 1.0)),
+            }
+        };
+
+        Some(Self {
+            kind,
+            delay: num("delay").unwrap_or(0.0).max(0.0),
+            rest_delta: num("restDelta").unwrap_or(default_rest_threshold()),
+            rest_speed: num("restSpeed").unwrap_or(default_rest_threshold()),
+        })
+    }
+}
+
+/// Per-element transition declaration: an optional default plus per-property overrides.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TransitionSpec {
+    pub default: Option<TransitionConfig>,
+    pub properties: HashMap<String, TransitionConfig>,
+}
+
+impl TransitionSpec {
+    /// Reads `transition` from the resolved styles first, then from element props.
+    pub fn resolve(styles: &ElementStyles, props: &HashMap<String, serde_json::Value>) -> Option<Self> {
+        styles.get("transition")
+            .or_else(|| props.get("transition"))
+            .and_then(Self::from_json)
+    }
+
+    pub fn from_json(v: &serde_json::Value) -> Option<Self> {
+        if let Some(css) = v.as_str() {
+            return Self::from_css(css);
+        }
+
+        let obj = v.as_object()?;
+        let mut spec = Self { default: TransitionConfig::from_json(v), properties: HashMap::new() };
+        for (key, value) in obj {
+            if CONFIG_KEYS.contains(&key.as_str()) { continue; }
+            if let Some(config) = TransitionConfig::from_json(value) {
+                if key == "default" || key == "all" {
+                    spec.default = Some(config);
+                } else {
+                    spec.properties.insert(key.clone(), config);
+                }
+            }
+        }
+
+        if spec.default.is_none() && spec.properties.is_empty() { None } else { Some(spec) }
+    }
+
+    /// Parses a CSS `transition` shorthand, e.g. `"opacity 300ms ease-in 50ms, border-radius 0.2s"`.
+    /// Like CSS, a segment with a token that is not a time, an easing or a single property name is dropped.
+    pub fn from_css(css: &str) -> Option<Self> {
+        let mut spec = Self::default();
+
+        'segments: for segment in split_top_level(css) {
+            let mut property = None;
+            let mut durations = Vec::new();
+            let mut easing = None;
+
+            for token in split_whitespace_top_level(&segment) {
+                if let Some(secs) = parse_time(&token) {
+                    durations.push(secs);
+                } else if let Some(e) = Easing::from_name(&token) {
+                    easing = Some(e);
+                } else if property.is_none() && is_property_name(&token) {
+                    property = Some(kebab_to_camel(&token));
+                } else {
+                    continue 'segments;
+                }
+            }
+            let property = property.unwrap_or_else(|| "all".to_string());
+
+            let config = TransitionConfig {
+                kind: TransitionKind::Tween {
+                    duration: durations.first().copied().unwrap_or(0.0),
+                    easing: easing.unwrap_or(Easing::CubicBezier(0.25, 0.1, 0.25, 1.0)),
+                },
+                delay: durations.get(1).copied().unwrap_or(0.0),
+                ..TransitionConfig::default()
+            };
+
+            if property == "all" {
+                spec.default = Some(config);
+            } else {
+                spec.properties.insert(property, config);
+            }
+        }
+
+        if spec.default.is_none() && spec.properties.is_empty() { None } else { Some(spec) }
+    }
+
+    /// Geometry keys also match their CSS counterparts (`x` -> `left`/`transform`, `y` -> `top`/`transform`).
+    pub fn for_property(&self, prop: &str) -> Option<TransitionConfig> {
+        let aliases: &[&str] = match prop {
+            "x" => &["x", "left", "transform"],
+            "y" => &["y", "top", "transform"],
+            _ => &[],
+        };
+
+        self.properties.get(prop)
+            .or_else(|| aliases.iter().find_map(|a| self.properties.get(*a)))
+            .copied()
+            .or(self.default)
+    }
+}
+
+fn split_top_level(s: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for ch in s.chars() {
+        match ch {
+            '(' => { depth += 1; current.push(ch); },
+            ')' => { depth -= 1; current.push(ch); },
+            ',' if depth == 0 => { parts.push(current.trim().to_string()); current.clear(); },
+            _ => current.push(ch),
+        }
+    }
+    if !current.trim().is_empty() { parts.push(current.trim().to_string()); }
+    parts
+}
+
+fn split_whitespace_top_level(s: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for ch in s.chars() {
+        match ch {
+            '(' => { depth += 1; current.push(ch); },
+            ')' => { depth -= 1; current.push(ch); },
+            c if c.is_whitespace() && depth == 0 => {
+                if !current.is_empty() { parts.push(current.clone()); current.clear(); }
+            },
+            _ => current.push(ch),
+        }
+    }
+    if !current.is_empty() { parts.push(current); }
+    parts
+}
+
+fn parse_time(token: &str) -> Option<f32> {
+    if let Some(ms) = token.strip_suffix("ms") {
+        ms.parse::<f32>().ok().map(|v| v / 1000.0)
+    } else if let Some(s) = token.strip_suffix('s') {
+        s.parse::<f32>().ok()
+    } else {
+        None
+    }
+}
+
+/// Letters and hyphens (`opacity`, `border-radius`, `-webkit-filter`, `all`).
+fn is_property_name(token: &str) -> bool {
+    token.chars().any(|c| c.is_ascii_alphabetic()) && token.chars().all(|c| c.is_ascii_alphabetic() || c == '-')
+}
+
+fn kebab_to_camel(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut upper = false;
+    for ch in s.chars() {
+        if ch == '-' {
+            upper = true;
+        } else if upper {
+            out.extend(ch.to_uppercase());
+            upper = false;
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+// --- ANIMATED VALUES ---
+
+/// Style keys driven by Taffy. Their animated counterparts are the x/y/width/height layout channels.
+const LAYOUT_PROPERTIES: [&str; 21] = [
+    "width", "height", "minWidth", "minHeight", "maxWidth", "maxHeight",
+    "left", "top", "right", "bottom", "padding", "margin", "gap",
+    "flexGrow", "flexShrink", "flexBasis", "order", "zIndex",
+    "x", "y", "transition",
+];
+
+pub fn is_layout_property(key: &str) -> bool {
+    LAYOUT_PROPERTIES.contains(&key)
+}
+
+/// Layout channels. Presence values for these are offsets from the layout box, not absolute values.
+pub fn is_geometry(key: &str) -> bool {
+    matches!(key, "x" | "y" | "width" | "height")
+}
+
+/// CSS initial value used when an element does not set the property itself.
+pub fn initial_value(key: &str) -> f32 {
+    match key {
+        "opacity" | "scale" | "scaleX" | "scaleY" => 1.0,
+        _ => 0.0,
+    }
+}
+
+/// Numeric value of a style: plain numbers or strings like `"12px"`, `"45deg"`, `"0.5"`.
+pub fn parse_numeric(v: &serde_json::Value) -> Option<f32> {
+    if let Some(n) = v.as_f64() {
+        return Some(n as f32);
+    }
+    let s = v.as_str()?.trim();
+    let num = ["px", "deg", "%", "rem", "em"].iter()
+        .find_map(|unit| s.strip_suffix(unit))
+        .unwrap_or(s);
+    num.trim().parse::<f32>().ok()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AnimationValue {
+    pub current: f32,
+    pub target: f32,
+    pub velocity: f32,
+    #[serde(default)]
+    pub from: f32,
+    #[serde(default)]
+    pub elapsed: f32, // seconds since the last retarget, including delay
+    #[serde(default)]
+    pub transition: TransitionConfig,
+    /// Settled values are skipped by `step` until they are retargeted.
+    #[serde(default)]
+    pub sleeping: bool,
+}
+
+impl AnimationValue {
+    pub fn at_rest(value: f32) -> Self {
+        Self {
+            current: value,
+            target: value,
+            velocity: 0.0,
+            from: value,
+            elapsed: 0.0,
+            transition: TransitionConfig::default(),
+            sleeping: true,
+        }
+    }
+
+    /// Points the value at a new target. Tweens restart from the current value; springs keep their velocity.
+    /// Returns `Start` when a sleeping value wakes up and `Interrupt` when a running one is redirected.
+    pub fn retarget(&mut self, target: f32, transition: TransitionConfig) -> Option<AnimationEventKind> {
+        let target_changed = (target - self.target).abs() > f32::EPSILON;
+        if target_changed || transition != self.transition {
+            self.from = self.current;
+            self.elapsed = 0.0;
+        }
+        self.target = target;
+        self.transition = transition;
+
+        if !target_changed { return None; }
+        let kind = if self.sleeping { AnimationEventKind::Start } else { AnimationEventKind::Interrupt };
+        self.sleeping = false;
+        Some(kind)
+    }
+
+    /// Advances one frame. Returns true on the frame the value settles.
+    pub fn step(&mut self, dt: f32) -> bool {
+        if self.sleeping { return false; }
+
+        self.elapsed += dt;
+        let active = self.elapsed - self.transition.delay;
+        if active <= 0.0 { return false; }
+
+        match self.transition.kind {
+            TransitionKind::Spring { stiffness, damping, mass } => {
+                // Only integrate the part of this frame that falls after the delay
+                let step_dt = dt.min(active);
+                // Spring equation: F = -kx - cv
+                let force = -stiffness * (self.current - self.target);
+                let damping_force = -damping * self.velocity;
+                let acceleration = (force + damping_force) / mass;
+
+                self.velocity += acceleration * step_dt;
+                self.current += self.velocity * step_dt;
+
+                if (self.current - self.target).abs() < self.transition.rest_delta
+                    && self.velocity.abs() < self.transition.rest_speed {
+                    self.settle();
+                    return true;
+                }
+            },
+            TransitionKind::Tween { duration, easing } => {
+                let t = if duration <= 0.0 { 1.0 } else { (active / duration).min(1.0) };
+                let previous = self.current;
+                self.current = self.from + (self.target - self.from) * easing.apply(t);
+                self.velocity = if dt > 0.0 { (self.current - previous) / dt } else { 0.0 };
+
+                if t >= 1.0 {
+                    self.settle();
+                    return true;
+                }
+            },
+        }
+        false
+    }
+
+    /// Moves the displayed value without touching the target (FLIP invert, enter offsets).
+    /// Returns `Start` if this wakes a sleeping value.
+    pub fn jump_to(&mut self, current: f32, transition: TransitionConfig) -> Option<AnimationEventKind> {
+        self.current = current;
+        self.from = current;
+        self.elapsed = 0.0;
+        self.transition = transition;
+        if (self.current - self.target).abs() <= f32::EPSILON { return None; }
+
+        let was_sleeping = self.sleeping;
+        self.sleeping = false;
+        if was_sleeping { Some(AnimationEventKind::Start) } else { None }
+    }
+
+    fn settle(&mut self) {
+        self.current = self.target;
+        self.velocity = 0.0;
+        self.sleeping = true;
+    }
+}
+
+// --- PRESENCE (ENTER / EXIT) ---
+
+/// `enter` / `exit` declaration, read from styles or props:
+/// `{ "opacity": 0, "y": 16, "transition": { "duration": 0.2 } }`.
+/// Enter values are where a new element starts; exit values are where a removed one ends.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PresenceConfig {
+    pub values: HashMap<String, f32>,
+    pub transition: Option<TransitionConfig>,
+}
+
+impl PresenceConfig {
+    pub fn resolve(key: &str, styles: &ElementStyles, props: &HashMap<String, serde_json::Value>) -> Option<Self> {
+        styles.get(key)
+            .or_else(|| props.get(key))
+            .and_then(Self::from_json)
+    }
+
+    pub fn from_json(v: &serde_json::Value) -> Option<Self> {
+        let obj = v.as_object()?;
+        let mut config = Self::default();
+        for (key, value) in obj {
+            if key == "transition" {
+                config.transition = TransitionConfig::from_json(value);
+            } else if let Some(n) = parse_numeric(value) {
+                config.values.insert(key.clone(), n);
+            }
+        }
+        if config.values.is_empty() { None } else { Some(config) }
+    }
+}
+
+/// Snapshot of a removed element that keeps animating towards its `exit` values.
+/// Geometry channels are offsets from `x`/`y`/`width`/`height`, captured at removal.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExitGhost {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub values: HashMap<String, AnimationValue>,
+}
+
+impl ExitGhost {
+    /// `current` holds the element's last displayed style values, used as exit start points.
+    pub fn new(
+        id: &str,
+        parent_id: Option<String>,
+        bounds: (f32, f32, f32, f32),
+        current: &HashMap<String, f32>,
+        exit: &PresenceConfig,
+        spec: Option<&TransitionSpec>,
+    ) -> Self {
+        let mut values = HashMap::new();
+        for (key, end) in &exit.values {
+            let start = if is_geometry(key) { 0.0 } else { current.get(key).copied().unwrap_or_else(|| initial_value(key)) };
+            let transition = exit.transition
+                .or_else(|| spec.and_then(|s| s.for_property(key)))
+                .unwrap_or_default();
+            let mut val = AnimationValue::at_rest(start);
+            val.retarget(*end, transition);
+            values.insert(key.clone(), val);
+        }
+
+        let (x, y, width, height) = bounds;
+        Self { id: id.to_string(), parent_id, x, y, width, height, values }
+    }
+
+    /// Returns true once every channel has settled and the ghost can be dropped.
+    pub fn step(&mut self, dt: f32) -> bool {
+        for val in self.values.values_mut() {
+            val.step(dt);
+        }
+        self.values.values().all(|v| v.sleeping)
+    }
+
+    /// Drawable state: absolute box plus the remaining style channels.
+    pub fn snapshot(&self) -> serde_json::Value {
+        let channel = |key: &str| self.values.get(key).map(|v| v.current).unwrap_or(0.0);
+        let styles: HashMap<&str, f32> = self.values.iter()
+            .filter(|(k, _)| !is_geometry(k))
+            .map(|(k, v)| (k.as_str(), v.current))
+            .collect();
+
+        serde_json::json!({
+            "id": self.id,
+            "parentId": self.parent_id,
+            "x": self.x + channel("x"),
+            "y": self.y + channel("y"),
+            "width": self.width + channel("width"),
+            "height": self.height + channel("height"),
+            "styles": styles,
+        })
+    }
+}
+
+// --- LIFECYCLE EVENTS ---
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum AnimationEventKind {
+    Start,
+    Complete,
+    Interrupt,
+}
+
+impl AnimationEventKind {
+    /// Logic trigger fired on the element's blueprint.
+    pub fn trigger_name(&self) -> &'static str {
+        match self {
+            AnimationEventKind::Start => "on_animation_start",
+            AnimationEventKind::Complete => "on_animation_end",
+            AnimationEventKind::Interrupt => "on_animation_interrupt",
+        }
+    }
+}
+
+/// Element-level event: the element started moving, came fully to rest, or was redirected mid-flight.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AnimationEvent {
+    pub element_id: String,
+    pub kind: AnimationEventKind,
+    pub properties: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_css_transition_parsing() {
+        let spec = TransitionSpec::from_css("opacity 300ms ease-in 50ms, border-radius 0.2s cubic-bezier(0.1, 0.7, 1.0, 0.1)").unwrap();
+
+        let opacity = spec.for_property("opacity").unwrap();
+        assert_eq!(opacity.delay, 0.05);
+        assert_eq!(opacity.kind, TransitionKind::Tween { duration: 0.3, easing: Easing::CubicBezier(0.42, 0.0, 1.0, 1.0) });
+
+        assert!(spec.for_property("borderRadius").is_some());
+        assert!(spec.for_property("x").is_none());
+    }
+
+    #[test]
+    fn test_malformed_css_transition_segments_are_dropped() {
+        // A unitless time, a misspelled easing and trailing junk don't turn into property names
+        let spec = TransitionSpec::from_css("opacity 0.5, transform 1s eas-in, all 1s 3x, width 200ms linear").unwrap();
+        assert_eq!(spec.default, None);
+        assert_eq!(spec.properties.keys().collect::<Vec<_>>(), vec!["width"]);
+
+        assert!(TransitionSpec::from_css("opacity transform 1s").is_none());
+        assert!(TransitionSpec::from_css("0.5").is_none());
+    }
+
+    #[test]
+    fn test_tween_reaches_target_after_delay() {
+        let mut val = AnimationValue::at_rest(0.0);
+        val.retarget(1.0, TransitionConfig {
+            kind: TransitionKind::Tween { duration: 0.5, easing: Easing::Linear },
+            delay: 0.25,
+            ..TransitionConfig::default()
+        });
+
+        val.step(0.25);
+        assert_eq!(val.current, 0.0);
+        val.step(0.25);
+        assert!((val.current - 0.5).abs() < 1e-4);
+        assert!(val.step(0.25));
+        assert_eq!(val.current, 1.0);
+        assert!(val.sleeping);
+    }
+
+    #[test]
+    fn test_spring_settles_and_reports_interrupts() {
+        let mut val = AnimationValue::at_rest(0.0);
+        assert_eq!(val.retarget(100.0, TransitionConfig::default()), Some(AnimationEventKind::Start));
+
+        val.step(1.0 / 60.0);
+        assert_eq!(val.retarget(50.0, TransitionConfig::default()), Some(AnimationEventKind::Interrupt));
+
+        let settled_frames = (0..600).filter(|_| val.step(1.0 / 60.0)).count();
+        assert_eq!(settled_frames, 1);
+        assert!(val.sleeping);
+        assert_eq!(val.current, 50.0);
+    }
+
+    #[test]
+    fn test_exit_ghost_fades_out_and_finishes() {
+        let exit = PresenceConfig::from_json(&serde_json::json!({
+            "opacity": 0,
+            "y": 20,
+            "transition": { "duration": 0.2, "ease": "linear" }
+        })).unwrap();
+        let current = HashMap::from([("opacity".to_string(), 0.8)]);
+        let mut ghost = ExitGhost::new("card", None, (10.0, 10.0, 100.0, 50.0), &current, &exit, None);
+
+        assert!(!ghost.step(0.1));
+        assert!((ghost.snapshot()["styles"]["opacity"].as_f64().unwrap() - 0.4).abs() < 1e-4);
+        assert!(ghost.step(0.1));
+        assert_eq!(ghost.snapshot()["y"], 30.0);
+    }
+
+    #[test]
+    fn test_bezier_endpoints_and_monotonicity() {
+        let ease = Easing::from_name("ease-in-out").unwrap();
+        assert_eq!(ease.apply(0.0), 0.0);
+        assert_eq!(ease.apply(1.0), 1.0);
+        assert!((ease.apply(0.5) - 0.5).abs() < 1e-3);
+        assert!(ease.apply(0.25) < ease.apply(0.75));
+    }
+}
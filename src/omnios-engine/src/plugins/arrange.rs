@@ -0,0 +1,390 @@
+use crate::{HyperCommand, PROJECT_STATE};
+use crate::plugins::interaction::Rect;
+use crate::plugins::resize::px;
+use crate::plugins::spatial_index::SPATIAL_INDEX;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+// --- ALIGN / DISTRIBUTE / TIDY ---
+// Freedom (and absolute) children are moved through their `left`/`top` insets. Flex children
+// cannot be placed individually, so their container gets the equivalent justify/align/gap change.
+
+pub const DEFAULT_TIDY_GAP: f32 = 16.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AlignMode {
+    Left,
+    Center,
+    Right,
+    Top,
+    Middle,
+    Bottom,
+}
+
+impl AlignMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "left" => Some(AlignMode::Left),
+            "center" => Some(AlignMode::Center),
+            "right" => Some(AlignMode::Right),
+            "top" => Some(AlignMode::Top),
+            "middle" => Some(AlignMode::Middle),
+            "bottom" => Some(AlignMode::Bottom),
+            _ => None,
+        }
+    }
+
+    fn horizontal(self) -> bool {
+        matches!(self, AlignMode::Left | AlignMode::Center | AlignMode::Right)
+    }
+
+    /// Flex keyword for this alignment (start, center or end).
+    fn flex_keyword(self) -> &'static str {
+        match self {
+            AlignMode::Left | AlignMode::Top => "flex-start",
+            AlignMode::Center | AlignMode::Middle => "center",
+            AlignMode::Right | AlignMode::Bottom => "flex-end",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DistributeMode {
+    Spacing, // Equal gaps between neighbours
+    Centers, // Equal distance between centers
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArrangeOp {
+    Align(AlignMode),
+    Distribute { horizontal: bool, mode: DistributeMode },
+    Tidy { columns: usize, gap: f32 },
+}
+
+#[derive(Debug, Clone)]
+pub struct ArrangeItem {
+    pub id: String,
+    pub bounds: Rect,
+    pub parent_id: Option<String>,
+    pub parent: Option<Rect>,
+    pub positioned: bool,   // Freedom/absolute: moved through insets
+    pub parent_row: bool,   // Parent flex direction is row (or row-reverse)
+}
+
+fn width(r: &Rect) -> f32 { r.max_x - r.min_x }
+fn height(r: &Rect) -> f32 { r.max_y - r.min_y }
+
+fn group_bounds(rects: &[Rect]) -> Option<Rect> {
+    let first = *rects.first()?;
+    Some(rects.iter().fold(first, |g, r| Rect {
+        min_x: g.min_x.min(r.min_x),
+        min_y: g.min_y.min(r.min_y),
+        max_x: g.max_x.max(r.max_x),
+        max_y: g.max_y.max(r.max_y),
+    }))
+}
+
+/// New top-left corners aligning every rect to `reference`.
+pub fn align_positions(rects: &[Rect], reference: Rect, mode: AlignMode) -> Vec<(f32, f32)> {
+    rects.iter().map(|r| {
+        let (w, h) = (width(r), height(r));
+        match mode {
+            AlignMode::Left => (reference.min_x, r.min_y),
+            AlignMode::Center => ((reference.min_x + reference.max_x - w) / 2.0, r.min_y),
+            AlignMode::Right => (reference.max_x - w, r.min_y),
+            AlignMode::Top => (r.min_x, reference.min_y),
+            AlignMode::Middle => (r.min_x, (reference.min_y + reference.max_y - h) / 2.0),
+            AlignMode::Bottom => (r.min_x, reference.max_y - h),
+        }
+    }).collect()
+}
+
+/// The outermost rects along the axis stay put; the rest are spread between them.
+pub fn distribute_positions(rects: &[Rect], horizontal: bool, mode: DistributeMode) -> Vec<(f32, f32)> {
+    let mut positions: Vec<(f32, f32)> = rects.iter().map(|r| (r.min_x, r.min_y)).collect();
+    if rects.len() < 3 { return positions; }
+
+    let span = |r: &Rect| if horizontal { (r.min_x, r.max_x) } else { (r.min_y, r.max_y) };
+    let mut order: Vec<usize> = (0..rects.len()).collect();
+    order.sort_by(|a, b| {
+        let (ca, cb) = (span(&rects[*a]), span(&rects[*b]));
+        (ca.0 + ca.1).partial_cmp(&(cb.0 + cb.1)).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let first = span(&rects[order[0]]);
+    let last = span(&rects[*order.last().unwrap()]);
+    let steps = (order.len() - 1) as f32;
+
+    let starts: Vec<f32> = match mode {
+        DistributeMode::Spacing => {
+            let sizes: f32 = order.iter().map(|i| { let s = span(&rects[*i]); s.1 - s.0 }).sum();
+            let gap = (last.1 - first.0 - sizes) / steps;
+            let mut cursor = first.0;
+            order.iter().map(|i| {
+                let s = span(&rects[*i]);
+                let start = cursor;
+                cursor += (s.1 - s.0) + gap;
+                start
+            }).collect()
+        }
+        DistributeMode::Centers => {
+            let (c0, c1) = ((first.0 + first.1) / 2.0, (last.0 + last.1) / 2.0);
+            order.iter().enumerate().map(|(k, i)| {
+                let s = span(&rects[*i]);
+                c0 + (c1 - c0) * k as f32 / steps - (s.1 - s.0) / 2.0
+            }).collect()
+        }
+    };
+
+    for (k, i) in order.iter().enumerate() {
+        if horizontal { positions[*i].0 = starts[k]; } else { positions[*i].1 = starts[k]; }
+    }
+    positions
+}
+
+/// Reading order: rows are clustered by vertical overlap, then sorted left to right.
+fn reading_order(rects: &[Rect]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..rects.len()).collect();
+    order.sort_by(|a, b| rects[*a].min_y.partial_cmp(&rects[*b].min_y).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut rows: Vec<Vec<usize>> = Vec::new();
+    for i in order {
+        let center = (rects[i].min_y + rects[i].max_y) / 2.0;
+        match rows.last_mut() {
+            Some(row) if center < rects[row[0]].max_y => row.push(i),
+            _ => rows.push(vec![i]),
+        }
+    }
+    rows.into_iter().flat_map(|mut row| {
+        row.sort_by(|a, b| rects[*a].min_x.partial_cmp(&rects[*b].min_x).unwrap_or(std::cmp::Ordering::Equal));
+        row
+    }).collect()
+}
+
+/// Packs rects into a grid (`columns` = 0 picks a square-ish grid) starting at their top-left corner.
+pub fn tidy_positions(rects: &[Rect], columns: usize, gap: f32) -> Vec<(f32, f32)> {
+    let origin = match group_bounds(rects) { Some(g) => (g.min_x, g.min_y), None => return Vec::new() };
+    let columns = if columns == 0 { (rects.len() as f32).sqrt().ceil() as usize } else { columns }.max(1);
+    let order = reading_order(rects);
+
+    let rows = order.len().div_ceil(columns);
+    let mut col_w = vec![0.0f32; columns];
+    let mut row_h = vec![0.0f32; rows];
+    for (k, i) in order.iter().enumerate() {
+        col_w[k % columns] = col_w[k % columns].max(width(&rects[*i]));
+        row_h[k / columns] = row_h[k / columns].max(height(&rects[*i]));
+    }
+
+    let mut positions = vec![(0.0, 0.0); rects.len()];
+    for (k, i) in order.iter().enumerate() {
+        let x = origin.0 + col_w[..k % columns].iter().map(|w| w + gap).sum::<f32>();
+        let y = origin.1 + row_h[..k / columns].iter().map(|h| h + gap).sum::<f32>();
+        positions[*i] = (x, y);
+    }
+    positions
+}
+
+/// Container changes equivalent to `op` for flex children laid out along a row or a column.
+pub fn flex_updates(op: ArrangeOp, children: &[Rect], row: bool) -> serde_json::Map<String, serde_json::Value> {
+    let mut updates = serde_json::Map::new();
+    let main_gap = |rects: &[Rect]| {
+        let span = |r: &Rect| if row { (r.min_x, r.max_x) } else { (r.min_y, r.max_y) };
+        let mut spans: Vec<(f32, f32)> = rects.iter().map(span).collect();
+        spans.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        let gaps: Vec<f32> = spans.windows(2).map(|w| (w[1].0 - w[0].1).max(0.0)).collect();
+        if gaps.is_empty() { None } else { Some(gaps.iter().sum::<f32>() / gaps.len() as f32) }
+    };
+
+    match op {
+        ArrangeOp::Align(mode) => {
+            // Along the main axis the whole line moves (justify), across it every item does (align)
+            let key = if mode.horizontal() == row { "justifyContent" } else { "alignItems" };
+            updates.insert(key.to_string(), mode.flex_keyword().into());
+        }
+        ArrangeOp::Distribute { horizontal, .. } => {
+            if horizontal == row {
+                if let Some(gap) = main_gap(children) {
+                    updates.insert("gap".to_string(), px(gap));
+                    updates.insert("justifyContent".to_string(), "flex-start".into());
+                }
+            }
+        }
+        ArrangeOp::Tidy { gap, .. } => {
+            updates.insert("flexDirection".to_string(), "row".into());
+            updates.insert("flexWrap".to_string(), "wrap".into());
+            updates.insert("gap".to_string(), px(gap));
+        }
+    }
+    updates
+}
+
+/// Commands for `op`: insets for positioned items, one container update per flex parent.
+pub fn plan_arrange(items: &[ArrangeItem], op: ArrangeOp, view_mode: &str) -> Vec<HyperCommand> {
+    let mut commands = Vec::new();
+
+    let positioned: Vec<&ArrangeItem> = items.iter().filter(|i| i.positioned).collect();
+    let rects: Vec<Rect> = positioned.iter().map(|i| i.bounds).collect();
+    let positions = match op {
+        ArrangeOp::Align(mode) => {
+            // A single element aligns inside its parent, a group to its own bounds
+            let reference = if rects.len() == 1 { positioned[0].parent.or_else(|| group_bounds(&rects)) } else { group_bounds(&rects) };
+            reference.map(|r| align_positions(&rects, r, mode)).unwrap_or_default()
+        }
+        ArrangeOp::Distribute { horizontal, mode } => distribute_positions(&rects, horizontal, mode),
+        ArrangeOp::Tidy { columns, gap } => tidy_positions(&rects, columns, gap),
+    };
+
+    for (item, (x, y)) in positioned.iter().zip(positions) {
+        let parent = match item.parent { Some(p) => p, None => continue };
+        let mut updates = serde_json::Map::new();
+        if (x - item.bounds.min_x).abs() > 0.01 { updates.insert("left".to_string(), px(x - parent.min_x)); }
+        if (y - item.bounds.min_y).abs() > 0.01 { updates.insert("top".to_string(), px(y - parent.min_y)); }
+        if !updates.is_empty() {
+            commands.push(HyperCommand::update_style(&item.id, serde_json::Value::Object(updates), view_mode));
+        }
+    }
+
+    // Flex children, grouped by container in first-seen order
+    let mut containers: Vec<(String, bool, Vec<Rect>)> = Vec::new();
+    for item in items.iter().filter(|i| !i.positioned) {
+        let parent_id = match &item.parent_id { Some(p) => p, None => continue };
+        match containers.iter_mut().find(|(id, _, _)| id == parent_id) {
+            Some((_, _, rects)) => rects.push(item.bounds),
+            None => containers.push((parent_id.clone(), item.parent_row, vec![item.bounds])),
+        }
+    }
+    for (parent_id, row, rects) in containers {
+        let updates = flex_updates(op, &rects, row);
+        if !updates.is_empty() {
+            commands.push(HyperCommand::update_style(&parent_id, serde_json::Value::Object(updates), view_mode));
+        }
+    }
+
+    commands
+}
+
+/// Reads items from the project and spatial index. An empty id list uses the current selection.
+fn collect_items(ids: Vec<String>) -> (Vec<ArrangeItem>, String) {
+    let ids = if ids.is_empty() { crate::plugins::selection::SELECTION.lock().unwrap().ids.clone() } else { ids };
+
+    let (specs, view_mode) = {
+        let state_guard = PROJECT_STATE.lock().unwrap();
+        let state = match state_guard.as_ref() { Some(s) => s, None => return (Vec::new(), String::new()) };
+        let mut parent_rows: HashMap<String, bool> = HashMap::new();
+        let specs: Vec<(String, Option<String>, bool, bool)> = ids.iter().filter_map(|id| {
+            let el = state.elements.get(id)?;
+            let styles = crate::resolve_final_styles(el, &state.view_mode);
+            let positioned = el.layout_mode.as_deref() == Some("freedom")
+                || styles.get("position").and_then(|v| v.as_str()) == Some("absolute");
+            let row = el.parent_id.as_ref().map(|pid| *parent_rows.entry(pid.clone()).or_insert_with(|| {
+                let direction = state.elements.get(pid)
+                    .map(|p| crate::resolve_final_styles(p, &state.view_mode))
+                    .and_then(|s| s.get("flexDirection").and_then(|v| v.as_str()).map(|d| d.to_string()));
+                !matches!(direction.as_deref(), Some("column") | Some("column-reverse"))
+            })).unwrap_or(true);
+            Some((id.clone(), el.parent_id.clone(), positioned, row))
+        }).collect();
+        (specs, state.view_mode.clone())
+    };
+
+    let index = SPATIAL_INDEX.lock().unwrap();
+    let items = specs.into_iter().filter_map(|(id, parent_id, positioned, parent_row)| {
+        let bounds = Rect::from(&index.query_one(&id)?);
+        let parent = parent_id.as_ref().and_then(|pid| index.query_one(pid)).map(|b| Rect::from(&b));
+        Some(ArrangeItem { id, bounds, parent_id, parent, positioned, parent_row })
+    }).collect();
+    (items, view_mode)
+}
+
+fn arrange(ids_json: &str, op: ArrangeOp) -> String {
+    let ids: Vec<String> = serde_json::from_str(ids_json).unwrap_or_default();
+    let (items, view_mode) = collect_items(ids);
+    serde_json::to_string(&plan_arrange(&items, op, &view_mode)).unwrap_or("[]".to_string())
+}
+
+// --- WASM Exports ---
+// Each returns a JSON array of HyperCommands; `ids_json` = "[]" uses the current selection.
+
+/// `mode`: left | center | right | top | middle | bottom.
+#[wasm_bindgen]
+pub fn align_elements(ids_json: &str, mode: &str) -> String {
+    match AlignMode::from_name(mode) {
+        Some(m) => arrange(ids_json, ArrangeOp::Align(m)),
+        None => "[]".to_string(),
+    }
+}
+
+/// `axis`: horizontal | vertical. `mode`: spacing | centers.
+#[wasm_bindgen]
+pub fn distribute_elements(ids_json: &str, axis: &str, mode: &str) -> String {
+    let mode = if mode == "centers" { DistributeMode::Centers } else { DistributeMode::Spacing };
+    arrange(ids_json, ArrangeOp::Distribute { horizontal: axis != "vertical", mode })
+}
+
+/// `columns` = 0 picks a square-ish grid; a negative `gap` uses the default.
+#[wasm_bindgen]
+pub fn tidy_elements(ids_json: &str, columns: u32, gap: f32) -> String {
+    let gap = if gap < 0.0 { DEFAULT_TIDY_GAP } else { gap };
+    arrange(ids_json, ArrangeOp::Tidy { columns: columns as usize, gap })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_align_and_distribute() {
+        let rects = [Rect::from_xywh(10.0, 0.0, 20.0, 10.0), Rect::from_xywh(50.0, 30.0, 40.0, 10.0), Rect::from_xywh(200.0, 5.0, 10.0, 10.0)];
+        let group = group_bounds(&rects).unwrap();
+
+        assert_eq!(align_positions(&rects, group, AlignMode::Right)[0], (190.0, 0.0));
+        assert_eq!(align_positions(&rects, group, AlignMode::Middle)[1], (50.0, 15.0));
+
+        // Span 10..210 holds 70px of boxes: two gaps of 65
+        let spaced = distribute_positions(&rects, true, DistributeMode::Spacing);
+        assert_eq!(spaced.iter().map(|p| p.0).collect::<Vec<_>>(), vec![10.0, 95.0, 200.0]);
+
+        // Centers 20 and 205: the middle center lands on 112.5
+        let centered = distribute_positions(&rects, true, DistributeMode::Centers);
+        assert_eq!(centered[1].0, 92.5);
+    }
+
+    #[test]
+    fn test_tidy_packs_in_reading_order() {
+        let rects = [Rect::from_xywh(300.0, 0.0, 50.0, 50.0), Rect::from_xywh(0.0, 0.0, 100.0, 40.0), Rect::from_xywh(10.0, 200.0, 30.0, 30.0), Rect::from_xywh(150.0, 10.0, 20.0, 60.0)];
+        let positions = tidy_positions(&rects, 2, 10.0);
+        // Reading order: [1, 3, 0] on the first row, [2] below; columns are 100 and 30 wide
+        assert_eq!(positions[1], (0.0, 0.0));
+        assert_eq!(positions[3], (110.0, 0.0));
+        assert_eq!(positions[0], (0.0, 70.0));
+        assert_eq!(positions[2], (110.0, 70.0));
+    }
+
+    #[test]
+    fn test_plan_mixes_insets_and_flex_suggestions() {
+        let parent = Rect::from_xywh(100.0, 100.0, 500.0, 500.0);
+        let item = |id: &str, bounds: Rect, positioned: bool, parent_id: &str| ArrangeItem {
+            id: id.to_string(), bounds, parent_id: Some(parent_id.to_string()), parent: Some(parent), positioned, parent_row: true,
+        };
+        let items = vec![
+            item("a", Rect::from_xywh(120.0, 150.0, 50.0, 50.0), true, "canvas"),
+            item("b", Rect::from_xywh(300.0, 110.0, 50.0, 50.0), true, "canvas"),
+            item("c", Rect::from_xywh(100.0, 300.0, 50.0, 50.0), false, "row"),
+            item("d", Rect::from_xywh(170.0, 300.0, 50.0, 50.0), false, "row"),
+        ];
+
+        let commands = plan_arrange(&items, ArrangeOp::Align(AlignMode::Top), "desktop");
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].target_id, "a");
+        assert_eq!(commands[0].payload["updates"]["top"], "10px");
+        assert_eq!(commands[1].target_id, "row");
+        assert_eq!(commands[1].payload["updates"]["alignItems"], "flex-start");
+
+        let commands = plan_arrange(&items, ArrangeOp::Distribute { horizontal: true, mode: DistributeMode::Spacing }, "desktop");
+        let row = commands.iter().find(|c| c.target_id == "row").unwrap();
+        assert_eq!(row.payload["updates"]["gap"], "20px");
+    }
+}
@@ -0,0 +1,279 @@
+use crate::plugins::interaction::Rect;
+use crate::plugins::spatial_index::SPATIAL_INDEX;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use wasm_bindgen::prelude::*;
+
+// --- CAMERA ---
+// World = layout space (what SPATIAL_INDEX stores). Screen = canvas pixels the pointer reports.
+// screen = (world - origin) * zoom, so `x`/`y` is the world point shown at the canvas top-left.
+
+lazy_static! {
+    pub static ref CAMERA: Mutex<Camera> = Mutex::new(Camera::default());
+}
+
+pub const MIN_ZOOM: f32 = 0.05;
+pub const MAX_ZOOM: f32 = 16.0;
+pub const ZOOM_STEP: f32 = 1.25;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Camera {
+    pub x: f32,
+    pub y: f32,
+    pub zoom: f32,
+    pub viewport_width: f32,  // 0 until the host reports the canvas size
+    pub viewport_height: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self { x: 0.0, y: 0.0, zoom: 1.0, viewport_width: 0.0, viewport_height: 0.0 }
+    }
+}
+
+impl Camera {
+    pub fn screen_to_world(&self, sx: f32, sy: f32) -> (f32, f32) {
+        (sx / self.zoom + self.x, sy / self.zoom + self.y)
+    }
+
+    pub fn world_to_screen(&self, wx: f32, wy: f32) -> (f32, f32) {
+        ((wx - self.x) * self.zoom, (wy - self.y) * self.zoom)
+    }
+
+    /// Converts a screen rectangle (x, y, width, height) into world space.
+    pub fn screen_rect_to_world(&self, x: f32, y: f32, width: f32, height: f32) -> (f32, f32, f32, f32) {
+        let (wx, wy) = self.screen_to_world(x, y);
+        (wx, wy, width / self.zoom, height / self.zoom)
+    }
+
+    /// A screen-space distance (e.g. a snap threshold in pixels) measured in world units.
+    pub fn screen_distance(&self, d: f32) -> f32 {
+        d / self.zoom
+    }
+
+    /// World rectangle currently on screen, if the viewport size is known.
+    pub fn visible_rect(&self) -> Option<Rect> {
+        if self.viewport_width <= 0.0 || self.viewport_height <= 0.0 { return None; }
+        Some(Rect {
+            min_x: self.x,
+            min_y: self.y,
+            max_x: self.x + self.viewport_width / self.zoom,
+            max_y: self.y + self.viewport_height / self.zoom,
+        })
+    }
+
+    /// Pans by a screen-space delta (dragging the canvas right moves the view left).
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.x -= dx / self.zoom;
+        self.y -= dy / self.zoom;
+    }
+
+    /// Zooms by `factor`, keeping the world point under the screen point (sx, sy) in place.
+    pub fn zoom_at(&mut self, factor: f32, sx: f32, sy: f32) {
+        if !(factor.is_finite() && factor > 0.0) { return; }
+        let (wx, wy) = self.screen_to_world(sx, sy);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.x = wx - sx / self.zoom;
+        self.y = wy - sy / self.zoom;
+    }
+
+    pub fn zoom_at_center(&mut self, factor: f32) {
+        self.zoom_at(factor, self.viewport_width / 2.0, self.viewport_height / 2.0);
+    }
+
+    /// Fits `rect` inside the viewport with `padding` screen pixels on every side, centered.
+    pub fn fit(&mut self, rect: &Rect, padding: f32) {
+        let (w, h) = (rect.max_x - rect.min_x, rect.max_y - rect.min_y);
+        if self.viewport_width > 0.0 && self.viewport_height > 0.0 && w > 0.0 && h > 0.0 {
+            let avail_w = (self.viewport_width - padding * 2.0).max(1.0);
+            let avail_h = (self.viewport_height - padding * 2.0).max(1.0);
+            self.zoom = (avail_w / w).min(avail_h / h).clamp(MIN_ZOOM, MAX_ZOOM);
+        }
+        let (cx, cy) = ((rect.min_x + rect.max_x) / 2.0, (rect.min_y + rect.max_y) / 2.0);
+        self.x = cx - self.viewport_width / 2.0 / self.zoom;
+        self.y = cy - self.viewport_height / 2.0 / self.zoom;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Camera { viewport_width: self.viewport_width, viewport_height: self.viewport_height, ..Camera::default() };
+    }
+
+    /// Handles the camera shortcuts from ShortcutsPlugin. Returns false for other actions.
+    pub fn apply_action(&mut self, action: &str) -> bool {
+        match action {
+            "ZoomIn" => self.zoom_at_center(ZOOM_STEP),
+            "ZoomOut" => self.zoom_at_center(1.0 / ZOOM_STEP),
+            "ResetView" => self.reset(),
+            _ => return false,
+        }
+        true
+    }
+}
+
+fn bounds_of(ids: &[String]) -> Option<Rect> {
+    let index = SPATIAL_INDEX.lock().unwrap();
+    ids.iter()
+        .filter_map(|id| index.query_one(id))
+        .map(|b| Rect::from(&b))
+        .reduce(|g, r| Rect {
+            min_x: g.min_x.min(r.min_x),
+            min_y: g.min_y.min(r.min_y),
+            max_x: g.max_x.max(r.max_x),
+            max_y: g.max_y.max(r.max_y),
+        })
+}
+
+fn camera_json() -> String {
+    serde_json::to_string(&*CAMERA.lock().unwrap()).unwrap_or("{}".to_string())
+}
+
+// --- WASM Exports ---
+// Each camera operation returns the resulting camera as JSON.
+
+#[wasm_bindgen]
+pub fn set_viewport_size(width: f32, height: f32) -> String {
+    {
+        let mut camera = CAMERA.lock().unwrap();
+        camera.viewport_width = width.max(0.0);
+        camera.viewport_height = height.max(0.0);
+    }
+    camera_json()
+}
+
+#[wasm_bindgen]
+pub fn get_camera() -> String {
+    camera_json()
+}
+
+#[wasm_bindgen]
+pub fn set_camera(x: f32, y: f32, zoom: f32) -> String {
+    {
+        let mut camera = CAMERA.lock().unwrap();
+        camera.x = x;
+        camera.y = y;
+        if zoom.is_finite() && zoom > 0.0 { camera.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM); }
+    }
+    camera_json()
+}
+
+#[wasm_bindgen]
+pub fn pan_camera(dx: f32, dy: f32) -> String {
+    CAMERA.lock().unwrap().pan(dx, dy);
+    camera_json()
+}
+
+/// Wheel/pinch zoom: `factor` > 1 zooms in around the screen point.
+#[wasm_bindgen]
+pub fn zoom_camera_at(factor: f32, screen_x: f32, screen_y: f32) -> String {
+    CAMERA.lock().unwrap().zoom_at(factor, screen_x, screen_y);
+    camera_json()
+}
+
+#[wasm_bindgen]
+pub fn reset_camera() -> String {
+    CAMERA.lock().unwrap().reset();
+    camera_json()
+}
+
+#[wasm_bindgen]
+pub fn fit_to_selection(padding: f32) -> String {
+    let ids = crate::plugins::selection::SELECTION.lock().unwrap().ids.clone();
+    if let Some(rect) = bounds_of(&ids) {
+        CAMERA.lock().unwrap().fit(&rect, padding);
+    }
+    camera_json()
+}
+
+/// Fits the active page (or every top-level element when no page is active).
+#[wasm_bindgen]
+pub fn fit_to_page(padding: f32) -> String {
+    let ids: Vec<String> = {
+        let state_guard = crate::PROJECT_STATE.lock().unwrap();
+        match state_guard.as_ref() {
+            Some(state) => match state.active_page_id.as_ref().filter(|p| state.elements.contains_key(*p)) {
+                Some(page) => vec![page.clone()],
+                None => state.elements.values().filter(|el| el.parent_id.is_none()).map(|el| el.id.clone()).collect(),
+            },
+            None => Vec::new(),
+        }
+    };
+    if let Some(rect) = bounds_of(&ids) {
+        CAMERA.lock().unwrap().fit(&rect, padding);
+    }
+    camera_json()
+}
+
+#[wasm_bindgen]
+pub fn screen_to_world(x: f32, y: f32) -> Vec<f32> {
+    let (wx, wy) = CAMERA.lock().unwrap().screen_to_world(x, y);
+    vec![wx, wy]
+}
+
+#[wasm_bindgen]
+pub fn world_to_screen(x: f32, y: f32) -> Vec<f32> {
+    let (sx, sy) = CAMERA.lock().unwrap().world_to_screen(x, y);
+    vec![sx, sy]
+}
+
+/// Ids of elements intersecting the viewport (grown by `margin` screen pixels), for culling.
+#[wasm_bindgen]
+pub fn visible_elements(margin: f32) -> String {
+    let visible = {
+        let camera = CAMERA.lock().unwrap();
+        camera.visible_rect().map(|r| (r, camera.screen_distance(margin)))
+    };
+    let ids: Vec<String> = {
+        let index = SPATIAL_INDEX.lock().unwrap();
+        match visible {
+            Some((r, m)) => index.query_intersecting(r.min_x - m, r.min_y - m, r.max_x - r.min_x + m * 2.0, r.max_y - r.min_y + m * 2.0),
+            // Unknown viewport: nothing can be culled
+            None => index.iter_all().map(|(id, _)| id.clone()).collect(),
+        }
+    };
+    serde_json::to_string(&ids).unwrap_or("[]".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport() -> Camera {
+        Camera { viewport_width: 800.0, viewport_height: 600.0, ..Camera::default() }
+    }
+
+    #[test]
+    fn test_zoom_keeps_point_under_cursor() {
+        let mut camera = viewport();
+        camera.pan(-100.0, -50.0);
+        let before = camera.screen_to_world(200.0, 150.0);
+        camera.zoom_at(2.0, 200.0, 150.0);
+        let after = camera.screen_to_world(200.0, 150.0);
+        assert!((before.0 - after.0).abs() < 1e-4 && (before.1 - after.1).abs() < 1e-4);
+        assert_eq!(camera.zoom, 2.0);
+
+        // Round trip
+        let (sx, sy) = camera.world_to_screen(after.0, after.1);
+        assert!((sx - 200.0).abs() < 1e-3 && (sy - 150.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_fit_centers_rect_and_visible_rect() {
+        let mut camera = viewport();
+        let rect = Rect::from_xywh(1000.0, 1000.0, 400.0, 200.0);
+        camera.fit(&rect, 100.0);
+        // 600x400 available: width is the limit (600 / 400 = 1.5)
+        assert_eq!(camera.zoom, 1.5);
+        let (cx, cy) = camera.world_to_screen(1200.0, 1100.0);
+        assert!((cx - 400.0).abs() < 1e-3 && (cy - 300.0).abs() < 1e-3);
+
+        let visible = camera.visible_rect().unwrap();
+        assert!(visible.min_x < rect.min_x && visible.max_x > rect.max_x);
+
+        assert!(camera.apply_action("ResetView"));
+        assert_eq!((camera.x, camera.y, camera.zoom), (0.0, 0.0, 1.0));
+        assert_eq!(camera.viewport_width, 800.0);
+        assert!(!camera.apply_action("Undo"));
+    }
+}
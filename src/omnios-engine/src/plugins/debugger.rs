@@ -0,0 +1,104 @@
+use crate::plugins::executions::Frame;
+use crate::plugins::trace::VariableSnapshot;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+// --- LOGIC DEBUGGER ---
+// With debugging on, the kernel halts before running a node that has a breakpoint. Its work
+// stack (frames and their queues, the halted node back in front), execution variables and gas
+// go into a PausedRun, the way a wait suspends into an ExecutionRecord. Step and continue
+// commands put them back and drive on until the next stop, so effects and writes after the
+// pause haven't happened yet. The editor highlights `nodeId` and shows `variables`.
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DebugCommand {
+    StepInto, // Next node, entering loop bodies and calls
+    StepOver, // Next node at this depth or shallower
+    StepOut,  // Next node shallower than this one
+    Continue, // Next breakpoint
+}
+
+impl DebugCommand {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "stepInto" | "step_into" => Some(DebugCommand::StepInto),
+            "stepOver" | "step_over" | "step" => Some(DebugCommand::StepOver),
+            "stepOut" | "step_out" => Some(DebugCommand::StepOut),
+            "continue" => Some(DebugCommand::Continue),
+            _ => None,
+        }
+    }
+}
+
+/// A run halted before `node_id`. Holds everything the kernel needs to carry on.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PausedRun {
+    pub blueprint_id: String,
+    pub node_id: String,
+    pub depth: usize, // Frames above the root: loop bodies, try bodies and calls
+    pub frames: Vec<Frame>,
+    pub variables: HashMap<String, Value>, // Execution scope
+    pub spent: u64,                        // Gas used so far; the time budget restarts on resume
+    pub last_effect: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DebugState {
+    pub paused: bool,
+    pub blueprint_id: Option<String>,
+    pub node_id: Option<String>, // The node about to run
+    pub depth: usize,
+    pub variables: Option<VariableSnapshot>, // Live variables at the pause
+}
+
+#[derive(Debug, Default)]
+pub struct Debugger {
+    pub enabled: bool,
+    pub breakpoints: HashSet<String>, // Node ids
+    session: Option<PausedRun>,
+    step: Option<(DebugCommand, usize)>, // Armed while a command drives the paused run
+    resuming: bool, // The halted node runs first on resume instead of pausing again
+}
+
+impl Debugger {
+    /// Whether the kernel should halt before `node_id`. Only one run can be paused at a time;
+    /// other runs (timers, effects) go past breakpoints meanwhile.
+    pub fn should_pause(&mut self, node_id: &str, depth: usize) -> bool {
+        if !self.enabled || self.session.is_some() { return false; }
+        if std::mem::take(&mut self.resuming) { return false; }
+        let stepped = match self.step {
+            Some((DebugCommand::StepInto, _)) => true,
+            Some((DebugCommand::StepOver, from)) => depth <= from,
+            Some((DebugCommand::StepOut, from)) => depth < from,
+            Some((DebugCommand::Continue, _)) | None => false,
+        };
+        stepped || self.breakpoints.contains(node_id)
+    }
+
+    pub fn pause(&mut self, run: PausedRun) {
+        log::info!("[LogicDebugger] Paused at {}/{} (depth {})", run.blueprint_id, run.node_id, run.depth);
+        self.session = Some(run);
+    }
+
+    /// Takes the paused run back for `command` to drive. None when nothing is paused.
+    pub fn resume(&mut self, command: DebugCommand) -> Option<PausedRun> {
+        let run = self.session.take()?;
+        self.step = Some((command, run.depth));
+        self.resuming = true;
+        Some(run)
+    }
+
+    /// The command's drive is over: paused again, finished, suspended or aborted.
+    pub fn disarm(&mut self) {
+        self.step = None;
+        self.resuming = false;
+    }
+
+    pub fn session(&self) -> Option<&PausedRun> {
+        self.session.as_ref()
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{DesignerElement, PROJECT_STATE};
+use crate::plugins::interaction::Rect;
+use crate::plugins::selection::{ancestors, is_selectable};
+use crate::plugins::spatial_index::SPATIAL_INDEX;
+use serde::Serialize;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+// --- DROP TARGET RESOLUTION ---
+// Finds where a dragged element would land: the deepest container under the pointer, the
+// insertion index among its flow children, and a line for the UI to draw at that slot.
+
+/// Element types that accept children even while empty.
+pub const CONTAINER_TYPES: &[&str] = &[
+    "box", "container", "section", "grid", "2-col", "3-col", "repeater", "form", "slot",
+    "navbar", "header", "footer", "auth-wall", "card", "accordion", "tabs", "tab", "page",
+];
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FlowKind {
+    Row,
+    RowReverse,
+    Column,
+    ColumnReverse,
+    Wrap, // Row that wraps onto several lines
+    Grid,
+    Freedom, // Dragged elements are positioned: no slot, only a local offset
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct IndicatorLine {
+    pub x1: f32,
+    pub y1: f32,
+    pub x2: f32,
+    pub y2: f32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DropTarget {
+    pub parent_id: String,
+    /// Position in the parent's `children` once the dragged elements are taken out of it.
+    pub index: usize,
+    pub layout: FlowKind,
+    pub indicator: Option<IndicatorLine>,
+    pub container: [f32; 4], // x, y, width, height
+    pub local_x: f32,        // Pointer relative to the container (for freedom drops)
+    pub local_y: f32,
+}
+
+pub fn is_container(el: &DesignerElement) -> bool {
+    el.children.is_some() || CONTAINER_TYPES.contains(&el.r#type.as_str())
+}
+
+fn flow_kind(styles: &crate::ElementStyles) -> FlowKind {
+    let get = |key: &str| styles.get(key).and_then(|v| v.as_str());
+    if get("display") == Some("grid") { return FlowKind::Grid; }
+    match get("flexDirection") {
+        Some("column") => FlowKind::Column,
+        Some("column-reverse") => FlowKind::ColumnReverse,
+        Some("row-reverse") => FlowKind::RowReverse,
+        _ if matches!(get("flexWrap"), Some("wrap") | Some("wrap-reverse")) => FlowKind::Wrap,
+        _ => FlowKind::Row,
+    }
+}
+
+/// Insertion slot among `children` (flow children in document order) for a pointer at (x, y).
+pub fn insertion_slot(container: &Rect, children: &[Rect], kind: FlowKind, x: f32, y: f32) -> (usize, Option<IndicatorLine>) {
+    let before = |b: &Rect| {
+        let (cx, cy) = ((b.min_x + b.max_x) / 2.0, (b.min_y + b.max_y) / 2.0);
+        match kind {
+            FlowKind::Row => x > cx,
+            FlowKind::RowReverse => x < cx,
+            FlowKind::Column => y > cy,
+            FlowKind::ColumnReverse => y < cy,
+            // Multi-line flows go line by line, then left to right within the pointer's line
+            FlowKind::Wrap | FlowKind::Grid => y > b.max_y || (y >= b.min_y && x > cx),
+            FlowKind::Freedom => true,
+        }
+    };
+    let index = children.iter().position(|b| !before(b)).unwrap_or(children.len());
+    if kind == FlowKind::Freedom { return (children.len(), None); }
+
+    let prev = index.checked_sub(1).and_then(|i| children.get(i));
+    let next = children.get(index);
+    let vertical_line = !matches!(kind, FlowKind::Column | FlowKind::ColumnReverse);
+
+    let line = if vertical_line {
+        // Between two items on the same line, otherwise against the single neighbour
+        let (at, span) = match (prev, next) {
+            (Some(p), Some(n)) if p.max_y > n.min_y && n.max_y > p.min_y => {
+                let reversed = kind == FlowKind::RowReverse;
+                let at = if reversed { (p.min_x + n.max_x) / 2.0 } else { (p.max_x + n.min_x) / 2.0 };
+                (at, (p.min_y.min(n.min_y), p.max_y.max(n.max_y)))
+            }
+            (_, Some(n)) => (if kind == FlowKind::RowReverse { n.max_x } else { n.min_x }, (n.min_y, n.max_y)),
+            (Some(p), None) => (if kind == FlowKind::RowReverse { p.min_x } else { p.max_x }, (p.min_y, p.max_y)),
+            (None, None) => (container.min_x, (container.min_y, container.max_y)),
+        };
+        IndicatorLine { x1: at, y1: span.0, x2: at, y2: span.1 }
+    } else {
+        let reversed = kind == FlowKind::ColumnReverse;
+        let (at, span) = match (prev, next) {
+            (Some(p), Some(n)) => {
+                let at = if reversed { (p.min_y + n.max_y) / 2.0 } else { (p.max_y + n.min_y) / 2.0 };
+                (at, (p.min_x.min(n.min_x), p.max_x.max(n.max_x)))
+            }
+            (None, Some(n)) => (if reversed { n.max_y } else { n.min_y }, (n.min_x, n.max_x)),
+            (Some(p), None) => (if reversed { p.min_y } else { p.max_y }, (p.min_x, p.max_x)),
+            (None, None) => (container.min_y, (container.min_x, container.max_x)),
+        };
+        IndicatorLine { x1: span.0, y1: at, x2: span.1, y2: at }
+    };
+    (index, Some(line))
+}
+
+/// Deepest container under the point that accepts a drop of `dragged`.
+pub fn find_container(
+    elements: &HashMap<String, DesignerElement>,
+    hits: &[(String, Rect)],
+    dragged: &[String],
+) -> Option<(String, Rect)> {
+    hits.iter()
+        .filter(|(id, _)| elements.get(id).map(is_container).unwrap_or(false))
+        .filter(|(id, _)| is_selectable(elements, id))
+        .filter_map(|(id, r)| {
+            // Nothing can be dropped into itself or its own subtree
+            let lineage = ancestors(elements, id);
+            if dragged.contains(id) || lineage.iter().any(|a| dragged.contains(a)) { return None; }
+            Some((lineage.len(), (r.max_x - r.min_x) * (r.max_y - r.min_y), id, r))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0).then(b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal)))
+        .map(|(_, _, id, r)| (id.clone(), *r))
+}
+
+pub fn resolve(x: f32, y: f32, dragged: &[String]) -> Option<DropTarget> {
+    let hits: Vec<(String, Rect)> = {
+        let index = SPATIAL_INDEX.lock().unwrap();
+        index.query_intersecting(x, y, 0.0, 0.0).into_iter()
+            .filter_map(|id| index.query_one(&id).map(|b| (id, Rect::from(&b))))
+            .collect()
+    };
+
+    let (parent_id, container, kind, flow_ids, remaining_len) = {
+        let state_guard = PROJECT_STATE.lock().unwrap();
+        let state = state_guard.as_ref()?;
+        let (parent_id, container) = find_container(&state.elements, &hits, dragged)?;
+        let parent = state.elements.get(&parent_id)?;
+
+        let positioned = |el: &DesignerElement| {
+            el.layout_mode.as_deref() == Some("freedom")
+                || crate::resolve_final_styles(el, &state.view_mode).get("position").and_then(|v| v.as_str()) == Some("absolute")
+        };
+        let all_positioned = !dragged.is_empty() && dragged.iter().all(|id| state.elements.get(id).map(positioned).unwrap_or(false));
+        let kind = if all_positioned { FlowKind::Freedom } else { flow_kind(&crate::resolve_final_styles(parent, &state.view_mode)) };
+
+        // Indices count every remaining child; only flow children take part in the slot search
+        let remaining: Vec<String> = parent.children.clone().unwrap_or_default().into_iter().filter(|c| !dragged.contains(c)).collect();
+        let remaining_len = remaining.len();
+        let flow_ids: Vec<(usize, String)> = remaining.into_iter().enumerate()
+            .filter(|(_, id)| state.elements.get(id).map(|el| !positioned(el)).unwrap_or(false))
+            .collect();
+        (parent_id, container, kind, flow_ids, remaining_len)
+    };
+
+    let (indices, rects): (Vec<usize>, Vec<Rect>) = {
+        let index = SPATIAL_INDEX.lock().unwrap();
+        flow_ids.iter().filter_map(|(i, id)| index.query_one(id).map(|b| (*i, Rect::from(&b)))).unzip()
+    };
+    let (slot, indicator) = insertion_slot(&container, &rects, kind, x, y);
+    // Before the flow child at `slot`, else right after the last one (freedom drops append)
+    let index = match (kind, indices.get(slot), indices.last()) {
+        (FlowKind::Freedom, _, _) => remaining_len,
+        (_, Some(i), _) => *i,
+        (_, None, Some(last)) => last + 1,
+        (_, None, None) => remaining_len,
+    };
+
+    Some(DropTarget {
+        parent_id,
+        index,
+        layout: kind,
+        indicator,
+        container: [container.min_x, container.min_y, container.max_x - container.min_x, container.max_y - container.min_y],
+        local_x: x - container.min_x,
+        local_y: y - container.min_y,
+    })
+}
+
+// --- WASM Exports ---
+
+/// Returns `{ parentId, index, layout, indicator, container, localX, localY }` or "null".
+#[wasm_bindgen]
+pub fn resolve_drop_target(x: f32, y: f32, dragged_ids_json: &str) -> String {
+    let dragged: Vec<String> = serde_json::from_str(dragged_ids_json).unwrap_or_default();
+    match resolve(x, y, &dragged) {
+        Some(target) => serde_json::to_string(&target).unwrap_or("null".to_string()),
+        None => "null".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_along_row_and_column() {
+        let container = Rect::from_xywh(0.0, 0.0, 400.0, 100.0);
+        let row = [Rect::from_xywh(0.0, 0.0, 100.0, 50.0), Rect::from_xywh(120.0, 0.0, 100.0, 50.0)];
+
+        let (index, line) = insertion_slot(&container, &row, FlowKind::Row, 160.0, 20.0);
+        assert_eq!(index, 1);
+        assert_eq!(line, Some(IndicatorLine { x1: 110.0, y1: 0.0, x2: 110.0, y2: 50.0 }));
+
+        let (index, line) = insertion_slot(&container, &row, FlowKind::Row, 300.0, 20.0);
+        assert_eq!(index, 2);
+        assert_eq!(line.unwrap().x1, 220.0);
+
+        let column = [Rect::from_xywh(0.0, 0.0, 200.0, 40.0), Rect::from_xywh(0.0, 60.0, 200.0, 40.0)];
+        let (index, line) = insertion_slot(&container, &column, FlowKind::Column, 50.0, 10.0);
+        assert_eq!(index, 0);
+        assert_eq!(line, Some(IndicatorLine { x1: 0.0, y1: 0.0, x2: 200.0, y2: 0.0 }));
+    }
+
+    #[test]
+    fn test_slot_in_grid_goes_line_by_line() {
+        let container = Rect::from_xywh(0.0, 0.0, 300.0, 300.0);
+        let cells = [Rect::from_xywh(0.0, 0.0, 100.0, 100.0), Rect::from_xywh(110.0, 0.0, 100.0, 100.0), Rect::from_xywh(0.0, 110.0, 100.0, 100.0)];
+
+        // Second line, right of the first cell
+        let (index, _) = insertion_slot(&container, &cells, FlowKind::Grid, 80.0, 150.0);
+        assert_eq!(index, 3);
+        let (index, _) = insertion_slot(&container, &cells, FlowKind::Grid, 20.0, 150.0);
+        assert_eq!(index, 2);
+    }
+
+    #[test]
+    fn test_container_excludes_dragged_subtree_and_locked() {
+        let raw = serde_json::json!([
+            { "id": "root", "type": "page", "parentId": null, "children": ["outer", "locked"] },
+            { "id": "outer", "type": "container", "parentId": "root", "children": ["inner"] },
+            { "id": "inner", "type": "box", "parentId": "outer" },
+            { "id": "locked", "type": "box", "parentId": "root", "locked": true },
+            { "id": "label", "type": "text", "parentId": "inner" }
+        ]);
+        let list: Vec<DesignerElement> = serde_json::from_value(raw).unwrap();
+        let elements: HashMap<String, DesignerElement> = list.into_iter().map(|el| (el.id.clone(), el)).collect();
+        let hits = vec![
+            ("root".to_string(), Rect::from_xywh(0.0, 0.0, 1000.0, 1000.0)),
+            ("outer".to_string(), Rect::from_xywh(0.0, 0.0, 500.0, 500.0)),
+            ("inner".to_string(), Rect::from_xywh(0.0, 0.0, 100.0, 100.0)),
+            ("label".to_string(), Rect::from_xywh(0.0, 0.0, 50.0, 20.0)),
+        ];
+
+        // Text is not a container, so the deepest container is the box around it
+        assert_eq!(find_container(&elements, &hits, &[]).unwrap().0, "inner");
+        // Dragging `outer` rules out itself and everything inside it
+        assert_eq!(find_container(&elements, &hits, &["outer".to_string()]).unwrap().0, "root");
+        let locked_hits = vec![("locked".to_string(), Rect::from_xywh(600.0, 0.0, 100.0, 100.0)), ("root".to_string(), Rect::from_xywh(0.0, 0.0, 1000.0, 1000.0))];
+        assert_eq!(find_container(&elements, &locked_hits, &[]).unwrap().0, "root");
+    }
+}
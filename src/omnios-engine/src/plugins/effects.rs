@@ -0,0 +1,476 @@
+use crate::plugins::expression::{self, ExprError, Scope};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+// --- SIDE EFFECTS ---
+// Logic never touches the outside world itself. Effect nodes (navigate, alert, api_request, ...)
+// become typed effects with resolved parameters, queued on the kernel. The browser host drains
+// them through `drain_effects`; the CLI and server hand them to an EffectHandler (mock or native).
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Effect {
+    #[serde(rename_all = "camelCase")]
+    Navigate { url: String, new_tab: bool },
+    #[serde(rename_all = "camelCase")]
+    ShowAlert { message: String, level: String },
+    #[serde(rename_all = "camelCase")]
+    HttpRequest {
+        method: String,
+        url: String,
+        headers: HashMap<String, String>,
+        body: Option<Value>,
+        result_var: Option<String>, // Where the host should store the response
+    },
+    #[serde(rename_all = "camelCase")]
+    SetElementStyle { element_id: String, styles: Map<String, Value> },
+    #[serde(rename_all = "camelCase")]
+    PlayAnimation { element_id: String, animation: String },
+    #[serde(rename_all = "camelCase")]
+    EmitEvent { name: String, payload: Value },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedEffect {
+    pub id: String,
+    pub blueprint_id: String,
+    pub node_id: String,
+    #[serde(flatten)]
+    pub effect: Effect,
+}
+
+/// Replaces `{{ expr }}` placeholders. A string that is exactly one placeholder keeps the
+/// value's type (`"{{ cart.items }}"` stays an array); otherwise results are spliced in as text.
+pub fn resolve_template(src: &str, scope: &dyn Scope) -> Result<Value, ExprError> {
+    let trimmed = src.trim();
+    if trimmed.starts_with("{{") && trimmed.ends_with("}}") && trimmed[2..].find("{{").is_none() {
+        return expression::eval_str(&trimmed[2..trimmed.len() - 2], scope);
+    }
+
+    let mut out = String::new();
+    let mut rest = src;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start..].find("}}") {
+            Some(e) => start + e,
+            None => break, // Unclosed: leave the text as written
+        };
+        out.push_str(&rest[..start]);
+        out.push_str(&expression::to_text(&expression::eval_str(&rest[start + 2..end], scope)?));
+        rest = &rest[end + 2..];
+    }
+    out.push_str(rest);
+    Ok(Value::String(out))
+}
+
+/// Resolves templates in every string inside a node's data.
+pub fn resolve_value(value: &Value, scope: &dyn Scope) -> Result<Value, ExprError> {
+    match value {
+        Value::String(s) if s.contains("{{") => resolve_template(s, scope),
+        Value::Array(items) => Ok(Value::Array(items.iter().map(|v| resolve_value(v, scope)).collect::<Result<_, _>>()?)),
+        Value::Object(map) => {
+            let mut out = Map::new();
+            for (k, v) in map {
+                out.insert(k.clone(), resolve_value(v, scope)?);
+            }
+            Ok(Value::Object(out))
+        }
+        other => Ok(other.clone()),
+    }
+}
+
+fn text(data: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|k| data.get(*k)).filter(|v| !v.is_null()).map(expression::to_text)
+}
+
+fn required(data: &Value, keys: &[&str], node_type: &str) -> Result<String, String> {
+    text(data, keys).filter(|s| !s.is_empty()).ok_or_else(|| format!("{} needs '{}'", node_type, keys[0]))
+}
+
+/// Builds the effect for an effect node from its (resolved) data. None for node types that are not effects.
+/// `payload` supplies the element for element effects that don't name one.
+pub fn effect_from_node(node_type: &str, data: &Value, payload: &Value) -> Option<Result<Effect, String>> {
+    let element = |data: &Value| text(data, &["elementId", "targetId"])
+        .or_else(|| payload.get("elementId").and_then(|v| v.as_str()).map(String::from))
+        .ok_or_else(|| format!("{} needs 'elementId'", node_type));
+
+    let effect = match node_type {
+        "navigate" => required(data, &["url", "path"], node_type).map(|url| Effect::Navigate {
+            url,
+            new_tab: data.get("newTab").and_then(|v| v.as_bool()).unwrap_or(false),
+        }),
+        "alert" | "show_alert" => Ok(Effect::ShowAlert {
+            message: text(data, &["message"]).unwrap_or_else(|| "Alert".to_string()),
+            level: text(data, &["level"]).unwrap_or_else(|| "info".to_string()),
+        }),
+        "api_request" | "http_request" => required(data, &["url"], node_type).map(|url| Effect::HttpRequest {
+            method: text(data, &["method"]).unwrap_or_else(|| "GET".to_string()).to_uppercase(),
+            url,
+            headers: data.get("headers").and_then(|h| h.as_object())
+                .map(|h| h.iter().map(|(k, v)| (k.clone(), expression::to_text(v))).collect())
+                .unwrap_or_default(),
+            body: data.get("body").cloned().filter(|b| !b.is_null()),
+            result_var: text(data, &["resultVar", "outputVar"]),
+        }),
+        "set_style" | "set_element_style" => element(data).and_then(|element_id| {
+            let styles = data.get("styles").and_then(|s| s.as_object()).cloned()
+                .ok_or_else(|| format!("{} needs 'styles'", node_type))?;
+            Ok(Effect::SetElementStyle { element_id, styles })
+        }),
+        "play_animation" => element(data).map(|element_id| Effect::PlayAnimation {
+            element_id,
+            animation: text(data, &["animation"]).unwrap_or_else(|| "enter".to_string()),
+        }),
+        "emit_event" => required(data, &["name", "event"], node_type).map(|name| Effect::EmitEvent {
+            name,
+            payload: data.get("payload").cloned().unwrap_or(Value::Null),
+        }),
+        _ => return None,
+    };
+    Some(effect)
+}
+
+// --- Handlers (CLI / server) ---
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectOutcome {
+    pub effect_id: String,
+    pub ok: bool,
+    pub result: Value,
+    pub error: Option<String>,
+}
+
+impl EffectOutcome {
+    pub fn ok(effect: &QueuedEffect, result: Value) -> Self {
+        Self { effect_id: effect.id.clone(), ok: true, result, error: None }
+    }
+
+    pub fn failed(effect: &QueuedEffect, error: impl Into<String>) -> Self {
+        Self { effect_id: effect.id.clone(), ok: false, result: Value::Null, error: Some(error.into()) }
+    }
+}
+
+/// Performs effects outside the browser.
+pub trait EffectHandler {
+    fn handle(&mut self, effect: &QueuedEffect) -> EffectOutcome;
+
+    fn handle_all(&mut self, effects: &[QueuedEffect]) -> Vec<EffectOutcome> {
+        effects.iter().map(|e| self.handle(e)).collect()
+    }
+}
+
+/// Records effects without performing them. HTTP requests answer from `responses` (by URL), else null.
+#[derive(Default, Debug)]
+pub struct MockEffects {
+    pub responses: HashMap<String, Value>,
+    pub handled: Vec<QueuedEffect>,
+}
+
+impl EffectHandler for MockEffects {
+    fn handle(&mut self, effect: &QueuedEffect) -> EffectOutcome {
+        self.handled.push(effect.clone());
+        let result = match &effect.effect {
+            Effect::HttpRequest { url, .. } => self.responses.get(url).cloned().unwrap_or(Value::Null),
+            _ => Value::Null,
+        };
+        EffectOutcome::ok(effect, result)
+    }
+}
+
+/// Headless execution: HTTP requests are sent (https too with the `native-http` feature), UI effects
+/// are only logged.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default, Debug)]
+pub struct NativeEffects {
+    pub allowed_hosts: Option<Vec<String>>, // None: any host (CLI). Servers pass the hosts flows may call
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl EffectHandler for NativeEffects {
+    fn handle(&mut self, effect: &QueuedEffect) -> EffectOutcome {
+        match &effect.effect {
+            Effect::HttpRequest { method, url, headers, body, .. } => {
+                if let Some(allowed) = &self.allowed_hosts {
+                    match native_http::host_of(url) {
+                        Some(host) if allowed.iter().any(|a| a.eq_ignore_ascii_case(host)) => {}
+                        _ => return EffectOutcome::failed(effect, format!("host of {} is not allowed", url)),
+                    }
+                }
+                match native_http::send(method, url, headers, body.as_ref()) {
+                    Ok(response) => EffectOutcome::ok(effect, response),
+                    Err(e) => EffectOutcome::failed(effect, e),
+                }
+            }
+            other => {
+                // No UI here: the host gets the effect back and reports it
+                log::info!("[NativeEffects] {}", serde_json::to_string(other).unwrap_or_default());
+                EffectOutcome::ok(effect, Value::Null)
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native_http {
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::io::Read;
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+    /// Larger responses fail instead of being buffered.
+    pub const MAX_RESPONSE_BYTES: usize = 4 << 20;
+
+    /// `tchar`s (RFC 9110): all a method or header name may contain.
+    fn is_token(s: &str) -> bool {
+        !s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+    }
+
+    /// Method, header names and values come from templates over the trigger payload: anything that
+    /// could end a line early (CR/LF) or isn't a token is rejected rather than written to the wire.
+    pub fn check_request(method: &str, headers: &HashMap<String, String>) -> Result<(), String> {
+        if !is_token(method) {
+            return Err(format!("invalid HTTP method {:?}", method));
+        }
+        for (name, value) in headers {
+            if !is_token(name) {
+                return Err(format!("invalid header name {:?}", name));
+            }
+            if value.bytes().any(|b| (b < 0x20 && b != b'\t') || b == 0x7f) {
+                return Err(format!("invalid value for header '{}'", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Host of an http(s) URL, without port. None for other schemes and URLs with credentials.
+    pub fn host_of(url: &str) -> Option<&str> {
+        let rest = url.strip_prefix("http://").or_else(|| url.strip_prefix("https://"))?;
+        let authority = &rest[..rest.find(['/', '?', '#']).unwrap_or(rest.len())];
+        if authority.contains('@') {
+            return None;
+        }
+        let host = match authority.strip_prefix('[') {
+            Some(v6) => &v6[..v6.find(']')?],
+            None => authority.split(':').next().unwrap_or(""),
+        };
+        if host.is_empty() { None } else { Some(host) }
+    }
+
+    fn body_text(body: Option<&Value>) -> Option<String> {
+        body.map(|b| match b {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+    }
+
+    fn read_capped(reader: impl Read) -> Result<Vec<u8>, String> {
+        let mut raw = Vec::new();
+        reader.take(MAX_RESPONSE_BYTES as u64 + 1).read_to_end(&mut raw).map_err(|e| e.to_string())?;
+        if raw.len() > MAX_RESPONSE_BYTES {
+            return Err(format!("response is larger than {} bytes", MAX_RESPONSE_BYTES));
+        }
+        Ok(raw)
+    }
+
+    /// `{ status, body }`; JSON bodies are parsed.
+    fn response(status: u16, body: &[u8]) -> Value {
+        let text = String::from_utf8_lossy(body);
+        let body = serde_json::from_str(&text).unwrap_or_else(|_| Value::String(text.into_owned()));
+        serde_json::json!({ "status": status, "body": body })
+    }
+
+    /// Sends the request with attohttpc (http and https). Redirects are not followed, so a request
+    /// can't be bounced to a host the caller didn't name.
+    #[cfg(feature = "native-http")]
+    pub fn send(method: &str, url: &str, headers: &HashMap<String, String>, body: Option<&Value>) -> Result<Value, String> {
+        use attohttpc::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+
+        check_request(method, headers)?;
+        let method = attohttpc::Method::from_bytes(method.as_bytes()).map_err(|e| e.to_string())?;
+        let mut request = attohttpc::RequestBuilder::try_new(method, url).map_err(|e| e.to_string())?
+            .timeout(TIMEOUT)
+            .follow_redirects(false);
+        for (name, value) in headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| e.to_string())?;
+            let value = HeaderValue::from_str(value).map_err(|e| e.to_string())?;
+            request = request.try_header(name, value).map_err(|e| e.to_string())?;
+        }
+        let sent = match body_text(body) {
+            Some(text) => {
+                if !headers.keys().any(|k| k.eq_ignore_ascii_case("content-type")) {
+                    request = request.try_header(CONTENT_TYPE, "application/json").map_err(|e| e.to_string())?;
+                }
+                request.text(text).send()
+            }
+            None => request.send(),
+        };
+        let (status, _, reader) = sent.map_err(|e| e.to_string())?.split();
+        Ok(response(status.as_u16(), &read_capped(reader)?))
+    }
+
+    /// Minimal HTTP/1.1 client over a TCP socket (plain http only) for builds without `native-http`.
+    #[cfg(not(feature = "native-http"))]
+    pub fn send(method: &str, url: &str, headers: &HashMap<String, String>, body: Option<&Value>) -> Result<Value, String> {
+        use std::io::Write;
+        use std::net::TcpStream;
+
+        check_request(method, headers)?;
+        let rest = url.strip_prefix("http://")
+            .ok_or_else(|| format!("only http:// URLs can be requested without the native-http feature (got {}); mock the request instead", url))?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        if authority.is_empty() || !authority.bytes().all(|b| b.is_ascii_alphanumeric() || b"-.:[]".contains(&b)) {
+            return Err(format!("invalid host in {}", url));
+        }
+        let address = if authority.contains(':') { authority.to_string() } else { format!("{}:80", authority) };
+
+        let mut stream = TcpStream::connect(&address).map_err(|e| format!("connect {}: {}", address, e))?;
+        stream.set_read_timeout(Some(TIMEOUT)).ok();
+        stream.set_write_timeout(Some(TIMEOUT)).ok();
+
+        let payload = body_text(body);
+        let mut request = format!("{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n", method, encode_path(path), authority);
+        for (k, v) in headers {
+            request.push_str(&format!("{}: {}\r\n", k, v));
+        }
+        if let Some(p) = &payload {
+            if !headers.keys().any(|k| k.eq_ignore_ascii_case("content-type")) {
+                request.push_str("Content-Type: application/json\r\n");
+            }
+            request.push_str(&format!("Content-Length: {}\r\n", p.len()));
+        }
+        request.push_str("\r\n");
+        if let Some(p) = &payload { request.push_str(p); }
+
+        stream.write_all(request.as_bytes()).map_err(|e| e.to_string())?;
+        let raw = read_capped(stream)?;
+
+        let split = find(&raw, b"\r\n\r\n").ok_or("malformed HTTP response")?;
+        let head = String::from_utf8_lossy(&raw[..split]);
+        let status = head.split_whitespace().nth(1).and_then(|s| s.parse::<u16>().ok())
+            .ok_or("malformed HTTP response")?;
+        let chunked = head.lines().any(|line| {
+            let (name, value) = line.split_once(':').unwrap_or((line, ""));
+            name.trim().eq_ignore_ascii_case("transfer-encoding") && value.to_ascii_lowercase().contains("chunked")
+        });
+        let body = &raw[split + 4..];
+        let body = if chunked { dechunk(body)? } else { body.to_vec() };
+        Ok(response(status, &body))
+    }
+
+    #[cfg(not(feature = "native-http"))]
+    /// Percent-encodes what may not appear raw in a request target (spaces, quotes, non-ASCII, ...).
+    /// Existing `%XX` escapes are kept.
+    pub fn encode_path(path: &str) -> String {
+        let mut out = String::with_capacity(path.len());
+        for b in path.bytes() {
+            if b.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@/?%".contains(&b) {
+                out.push(b as char);
+            } else {
+                out.push_str(&format!("%{:02X}", b));
+            }
+        }
+        out
+    }
+
+    #[cfg(not(feature = "native-http"))]
+    /// Decodes a `Transfer-Encoding: chunked` body.
+    pub fn dechunk(mut data: &[u8]) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        loop {
+            let line = find(data, b"\r\n").ok_or("malformed chunked body")?;
+            let size = std::str::from_utf8(&data[..line]).ok()
+                .and_then(|s| usize::from_str_radix(s.split(';').next().unwrap_or("").trim(), 16).ok())
+                .ok_or("malformed chunk size")?;
+            data = &data[line + 2..];
+            if size == 0 {
+                return Ok(out);
+            }
+            if data.len() < size {
+                return Err("truncated chunked body".to_string());
+            }
+            out.extend_from_slice(&data[..size]);
+            data = data.get(size + 2..).unwrap_or_default();
+        }
+    }
+
+    #[cfg(not(feature = "native-http"))]
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        haystack.windows(needle.len()).position(|w| w == needle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_templates_and_effect_nodes() {
+        let scope: HashMap<String, Value> = HashMap::from([
+            ("user".to_string(), json!({ "id": 7, "name": "Ada" })),
+            ("items".to_string(), json!([1, 2])),
+        ]);
+        assert_eq!(resolve_template("/users/{{ user.id }}/profile", &scope).unwrap(), json!("/users/7/profile"));
+        assert_eq!(resolve_template("{{ items }}", &scope).unwrap(), json!([1, 2]));
+
+        let data = resolve_value(&json!({ "url": "/u/{{ user.id }}", "newTab": true }), &scope).unwrap();
+        let effect = effect_from_node("navigate", &data, &Value::Null).unwrap().unwrap();
+        assert_eq!(effect, Effect::Navigate { url: "/u/7".to_string(), new_tab: true });
+
+        // Element effects fall back to the element that fired the trigger
+        let effect = effect_from_node("play_animation", &json!({}), &json!({ "elementId": "card" })).unwrap().unwrap();
+        let queued = QueuedEffect { id: "effect-1".to_string(), blueprint_id: "bp".to_string(), node_id: "n".to_string(), effect };
+        assert_eq!(serde_json::to_value(&queued).unwrap(), json!({
+            "id": "effect-1", "blueprintId": "bp", "nodeId": "n", "type": "playAnimation", "elementId": "card", "animation": "enter"
+        }));
+
+        assert!(effect_from_node("api_request", &json!({}), &Value::Null).unwrap().is_err());
+        assert!(effect_from_node("set_var", &json!({}), &Value::Null).is_none());
+
+        let mut mock = MockEffects::default();
+        mock.responses.insert("/api/me".to_string(), json!({ "ok": true }));
+        let request = QueuedEffect {
+            id: "effect-2".to_string(),
+            blueprint_id: "bp".to_string(),
+            node_id: "n".to_string(),
+            effect: effect_from_node("api_request", &json!({ "url": "/api/me" }), &Value::Null).unwrap().unwrap(),
+        };
+        assert_eq!(mock.handle(&request).result, json!({ "ok": true }));
+        assert_eq!(mock.handled.len(), 1);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_native_requests_are_sanitized() {
+        let injected = HashMap::from([("X-Id".to_string(), "1\r\nHost: internal".to_string())]);
+        assert!(native_http::check_request("GET", &injected).unwrap_err().contains("X-Id"));
+        assert!(native_http::check_request("GET / HTTP/1.1\r\n", &HashMap::new()).is_err());
+        assert!(native_http::check_request("GET", &HashMap::from([("Bad Name".to_string(), "x".to_string())])).is_err());
+        assert!(native_http::check_request("POST", &HashMap::from([("Content-Type".to_string(), "application/json".to_string())])).is_ok());
+
+        assert_eq!(native_http::host_of("https://api.example.com:8443/v1?q=1"), Some("api.example.com"));
+        assert_eq!(native_http::host_of("http://[::1]:80/"), Some("::1"));
+        assert_eq!(native_http::host_of("http://api.example.com@10.0.0.1/"), None);
+        let mut native = NativeEffects { allowed_hosts: Some(vec!["api.example.com".to_string()]) };
+        let internal = QueuedEffect {
+            id: "effect-3".to_string(),
+            blueprint_id: "bp".to_string(),
+            node_id: "n".to_string(),
+            effect: effect_from_node("api_request", &json!({ "url": "http://169.254.169.254/latest" }), &Value::Null).unwrap().unwrap(),
+        };
+        assert!(native.handle(&internal).error.unwrap().contains("not allowed"));
+
+        #[cfg(not(feature = "native-http"))]
+        {
+            assert_eq!(native_http::encode_path("/a b/\"x\"?q=\u{fc}%20"), "/a%20b/%22x%22?q=%C3%BC%20");
+            assert_eq!(native_http::dechunk(b"4\r\nWiki\r\n5;ext=1\r\npedia\r\n0\r\n\r\n").unwrap(), b"Wikipedia");
+            assert!(native_http::dechunk(b"ff\r\nshort").is_err());
+        }
+    }
+}
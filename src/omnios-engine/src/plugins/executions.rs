@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+// --- SUSPENDED EXECUTIONS ---
+// A wait/delay/await_effect node stops its branch and saves everything needed to carry on:
+// the node to continue from, the execution-scope variables and the trigger payload.
+// Inside a loop body, a try body or a called blueprint the record also takes the kernel's
+// frames above the root, so the loop carries on with its next iteration, the try still
+// catches and the call still returns to its caller once the wait is over.
+// Records are plain data, so pending flows survive a save/reload of the host.
+// They resume when the host calls `resume` or when the logic clock passes their deadline.
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum WaitFor {
+    /// Timer only; resumed by the clock.
+    Delay,
+    /// A queued effect's result (HTTP response, confirmation, ...); resumed by the host.
+    #[serde(rename_all = "camelCase")]
+    Effect { effect_id: Option<String> },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionRecord {
+    pub id: String,
+    pub blueprint_id: String,
+    pub node_id: String, // The waiting node; execution continues from its outgoing connections
+    pub payload: Value,
+    pub variables: HashMap<String, Value>, // Execution scope at the time of suspension
+    pub wait: WaitFor,
+    pub deadline: Option<f64>, // Logic clock (seconds). For effects this is the timeout
+    pub result_var: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub frames: Vec<Frame>, // Work stack (root first) when suspended inside a scope; empty at the top level
+}
+
+/// One level of the kernel's work stack. The root frame runs the triggered flow; loop bodies,
+/// `try` bodies and blueprint calls each push a frame, which runs to completion before the
+/// frame below carries on.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Frame {
+    pub blueprint_id: String,    // Whose nodes are queued
+    pub node_id: Option<String>, // The loop/try/call node that opened it, in the frame below. None for the root
+    pub payload: Value,
+    pub queue: VecDeque<String>, // Nodes still to run, breadth-first
+    pub scope: Scope,
+    #[serde(skip)]
+    pub traced: Option<(usize, u64)>, // Open trace event of the opening node, and the gas spent before it
+}
+
+impl Frame {
+    pub fn root(blueprint_id: &str, payload: Value, queue: Vec<String>) -> Self {
+        Self { blueprint_id: blueprint_id.to_string(), node_id: None, payload, queue: queue.into(), scope: Scope::Root, traced: None }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Scope {
+    Root,
+    /// A for_each/while body, run once per iteration.
+    Loop { iteration: Iteration },
+    /// A `try` body. A failure in it continues on the node's `catch` port.
+    Try,
+    /// A called blueprint. The caller's execution scope is put back when it returns.
+    #[serde(rename_all = "camelCase")]
+    Call { caller_variables: HashMap<String, Value> },
+}
+
+/// What decides a loop's next iteration.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Iteration {
+    /// for_each: `items[next]` goes into `item_var`, its index into `index_var`.
+    #[serde(rename_all = "camelCase")]
+    Items { items: Vec<Value>, next: usize, item_var: String, index_var: String },
+    /// while: runs while `condition` is truthy.
+    While { condition: String },
+}
+
+/// How a suspended execution was woken. Decides which port it continues on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wake {
+    Host,
+    Deadline,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionTable {
+    pub clock: f64,
+    next_id: u64,
+    pending: BTreeMap<String, ExecutionRecord>,
+}
+
+impl ExecutionTable {
+    pub fn next_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("exec-{}", self.next_id)
+    }
+
+    pub fn suspend(&mut self, record: ExecutionRecord) {
+        self.pending.insert(record.id.clone(), record);
+    }
+
+    pub fn take(&mut self, id: &str) -> Option<ExecutionRecord> {
+        self.pending.remove(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ExecutionRecord> {
+        self.pending.get(id)
+    }
+
+    pub fn pending(&self) -> impl Iterator<Item = &ExecutionRecord> {
+        self.pending.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// The execution waiting on `effect_id`, if any.
+    pub fn waiting_on(&self, effect_id: &str) -> Option<String> {
+        self.pending.values()
+            .find(|r| matches!(&r.wait, WaitFor::Effect { effect_id: Some(e) } if e == effect_id))
+            .map(|r| r.id.clone())
+    }
+
+    pub fn next_deadline(&self) -> Option<f64> {
+        self.pending.values().filter_map(|r| r.deadline).min_by(|a, b| a.total_cmp(b))
+    }
+
+    /// Advances the clock and removes the records whose deadline has passed, earliest first.
+    pub fn advance(&mut self, dt: f64) -> Vec<ExecutionRecord> {
+        if dt.is_finite() && dt > 0.0 { self.clock += dt; }
+        let now = self.clock;
+        let due: Vec<String> = self.pending.values()
+            .filter(|r| r.deadline.is_some_and(|d| d <= now))
+            .map(|r| r.id.clone())
+            .collect();
+        let mut records: Vec<ExecutionRecord> = due.iter().filter_map(|id| self.pending.remove(id)).collect();
+        records.sort_by(|a, b| a.deadline.unwrap_or(now).total_cmp(&b.deadline.unwrap_or(now)));
+        records
+    }
+
+    /// Replaces the pending set (e.g. restored from a saved project). The clock keeps running
+    /// from its current value, so deadlines are shifted to stay the same distance away.
+    pub fn restore(&mut self, saved: ExecutionTable) {
+        let shift = self.clock - saved.clock;
+        self.next_id = self.next_id.max(saved.next_id);
+        self.pending = saved.pending.into_iter()
+            .map(|(id, mut r)| {
+                r.deadline = r.deadline.map(|d| d + shift);
+                (id, r)
+            })
+            .collect();
+    }
+}
+
+/// Reads a duration in seconds from node data: `duration` (ms, as in the TS engine) or `seconds`.
+pub fn duration_secs(data: &Value, key_ms: &str, key_secs: &str) -> Option<f64> {
+    data.get(key_secs).and_then(|v| v.as_f64())
+        .or_else(|| data.get(key_ms).and_then(|v| v.as_f64()).map(|ms| ms / 1000.0))
+        .filter(|s| s.is_finite() && *s >= 0.0)
+}
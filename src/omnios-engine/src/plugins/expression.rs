@@ -0,0 +1,824 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+// --- EXPRESSIONS ---
+// Small sandboxed language for logic nodes: `cart.total > 100 && user.role == "vip"`, `count + 1`.
+// No assignment, no loops, no user functions: only the helpers below, over values a Scope provides.
+//
+//   literals   1, 2.5, "a", 'b', true, false, null, [1, 2], { key: value }
+//   access     a.b, a?.b (null-safe), a[0], a["key"], text.length
+//   operators  ! - * / % + - < <= > >= == != && || ?? cond ? a : b
+//   helpers    see `call`
+
+/// Longest accepted source; expressions live in node data, not in files.
+pub const MAX_SOURCE_LEN: usize = 4096;
+/// Deepest accepted nesting (parentheses, operators, access chains).
+pub const MAX_DEPTH: usize = 64;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ExprError {
+    pub message: String,
+    pub position: Option<usize>, // Char offset in the source, for parse errors
+}
+
+impl ExprError {
+    fn at(position: usize, message: impl Into<String>) -> Self {
+        Self { message: message.into(), position: Some(position) }
+    }
+
+    fn eval(message: impl Into<String>) -> Self {
+        Self { message: message.into(), position: None }
+    }
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.position {
+            Some(p) => write!(f, "{} (at {})", self.message, p),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Where identifiers come from.
+pub trait Scope {
+    fn lookup(&self, name: &str) -> Option<Value>;
+}
+
+impl Scope for HashMap<String, Value> {
+    fn lookup(&self, name: &str) -> Option<Value> {
+        self.get(name).cloned()
+    }
+}
+
+impl Scope for Map<String, Value> {
+    fn lookup(&self, name: &str) -> Option<Value> {
+        self.get(name).cloned()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Ident(String),
+    Array(Vec<Expr>),
+    Object(Vec<(String, Expr)>),
+    Member { object: Box<Expr>, property: Box<Expr>, optional: bool },
+    Call { name: String, args: Vec<Expr> },
+    Unary { op: &'static str, operand: Box<Expr> },
+    Binary { op: &'static str, left: Box<Expr>, right: Box<Expr> },
+    Conditional { test: Box<Expr>, then: Box<Expr>, otherwise: Box<Expr> },
+}
+
+// --- Lexer ---
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Str(String),
+    Ident(String),
+    Punct(&'static str),
+    End,
+}
+
+const PUNCTUATION: [&str; 27] = [
+    "&&", "||", "==", "!=", "<=", ">=", "??", "?.",
+    "+", "-", "*", "/", "%", "<", ">", "!", "(", ")", "[", "]", "{", "}", ",", ".", ":", "?", "=",
+];
+
+fn tokenize(src: &str) -> Result<Vec<(Token, usize)>, ExprError> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') { i += 1; }
+            let text: String = chars[start..i].iter().collect();
+            let n = text.parse::<f64>().map_err(|_| ExprError::at(start, format!("invalid number '{}'", text)))?;
+            tokens.push((Token::Num(n), start));
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$') { i += 1; }
+            tokens.push((Token::Ident(chars[start..i].iter().collect()), start));
+        } else if c == '"' || c == '\'' {
+            i += 1;
+            let mut text = String::new();
+            loop {
+                match chars.get(i) {
+                    None => return Err(ExprError::at(start, "unterminated string")),
+                    Some(&q) if q == c => { i += 1; break; }
+                    Some('\\') => {
+                        let escaped = chars.get(i + 1).ok_or_else(|| ExprError::at(i, "unterminated string"))?;
+                        text.push(match escaped { 'n' => '\n', 't' => '\t', other => *other });
+                        i += 2;
+                    }
+                    Some(&ch) => { text.push(ch); i += 1; }
+                }
+            }
+            tokens.push((Token::Str(text), start));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            // `a ?.5 : b` is a ternary, not optional access
+            let optional_digit = rest == "?." && chars.get(i + 2).is_some_and(|d| d.is_ascii_digit());
+            let punct = PUNCTUATION.iter()
+                .find(|p| rest.starts_with(**p) && !(optional_digit && **p == "?."))
+                .ok_or_else(|| ExprError::at(start, format!("unexpected character '{}'", c)))?;
+            if *punct == "=" {
+                return Err(ExprError::at(start, "assignment is not allowed, use '==' to compare"));
+            }
+            i += punct.chars().count();
+            tokens.push((Token::Punct(punct), start));
+        }
+    }
+    tokens.push((Token::End, chars.len()));
+    Ok(tokens)
+}
+
+// --- Parser ---
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    depth: usize,
+}
+
+/// Binary operators by precedence, loosest first.
+const LEVELS: [&[&str]; 7] = [
+    &["??"],
+    &["||"],
+    &["&&"],
+    &["==", "!="],
+    &["<", "<=", ">", ">="],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn position(&self) -> usize {
+        self.tokens[self.pos].1
+    }
+
+    fn advance(&mut self) -> Token {
+        let t = self.tokens[self.pos].0.clone();
+        if self.pos < self.tokens.len() - 1 { self.pos += 1; }
+        t
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Token::Punct(p) if *p == punct) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), ExprError> {
+        if self.eat(punct) { Ok(()) } else { Err(ExprError::at(self.position(), format!("expected '{}'", punct))) }
+    }
+
+    fn enter(&mut self) -> Result<(), ExprError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH { Err(ExprError::at(self.position(), "expression is nested too deeply")) } else { Ok(()) }
+    }
+
+    fn expression(&mut self) -> Result<Expr, ExprError> {
+        self.enter()?;
+        let test = self.binary(0)?;
+        let result = if self.eat("?") {
+            let then = self.expression()?;
+            self.expect(":")?;
+            let otherwise = self.expression()?;
+            Expr::Conditional { test: Box::new(test), then: Box::new(then), otherwise: Box::new(otherwise) }
+        } else {
+            test
+        };
+        self.depth -= 1;
+        Ok(result)
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, ExprError> {
+        if level == LEVELS.len() { return self.unary(); }
+        let mut left = self.binary(level + 1)?;
+        // Each operator nests the chain so far one level deeper (`a + b + c` is `(a + b) + c`)
+        let mut links = 0;
+        loop {
+            let op = match self.peek() {
+                Token::Punct(p) if LEVELS[level].contains(p) => *p,
+                _ => break,
+            };
+            self.advance();
+            self.enter()?;
+            links += 1;
+            let right = self.binary(level + 1)?;
+            left = Expr::Binary { op, left: Box::new(left), right: Box::new(right) };
+        }
+        self.depth -= links;
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ExprError> {
+        for op in ["!", "-"] {
+            if self.eat(op) {
+                self.enter()?;
+                let operand = self.unary()?;
+                self.depth -= 1;
+                return Ok(Expr::Unary { op, operand: Box::new(operand) });
+            }
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, ExprError> {
+        let mut expr = self.primary()?;
+        let start_depth = self.depth;
+        loop {
+            // Every access nests the chain one level deeper, like binary operators
+            if !matches!(self.peek(), Token::Punct(".") | Token::Punct("?.") | Token::Punct("[")) { break; }
+            self.enter()?;
+            let optional = if self.eat(".") {
+                false
+            } else if self.eat("?.") {
+                true
+            } else if self.eat("[") {
+                let index = self.expression()?;
+                self.expect("]")?;
+                expr = Expr::Member { object: Box::new(expr), property: Box::new(index), optional: false };
+                continue;
+            } else {
+                break;
+            };
+
+            // `a?.[0]` indexes null-safely
+            if optional && self.eat("[") {
+                let index = self.expression()?;
+                self.expect("]")?;
+                expr = Expr::Member { object: Box::new(expr), property: Box::new(index), optional };
+                continue;
+            }
+            let at = self.position();
+            let name = match self.advance() {
+                Token::Ident(name) => name,
+                _ => return Err(ExprError::at(at, "expected a property name")),
+            };
+            expr = Expr::Member { object: Box::new(expr), property: Box::new(Expr::Literal(Value::String(name))), optional };
+        }
+        self.depth = start_depth;
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ExprError> {
+        let at = self.position();
+        match self.advance() {
+            Token::Num(n) => Ok(Expr::Literal(number(n))),
+            Token::Str(s) => Ok(Expr::Literal(Value::String(s))),
+            Token::Ident(name) => {
+                let literal = match name.as_str() {
+                    "true" => Some(Value::Bool(true)),
+                    "false" => Some(Value::Bool(false)),
+                    "null" => Some(Value::Null),
+                    _ => None,
+                };
+                if let Some(v) = literal {
+                    Ok(Expr::Literal(v))
+                } else if self.eat("(") {
+                    let args = self.list(")")?;
+                    Ok(Expr::Call { name, args })
+                } else {
+                    Ok(Expr::Ident(name))
+                }
+            }
+            Token::Punct("(") => {
+                let inner = self.expression()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Token::Punct("[") => Ok(Expr::Array(self.list("]")?)),
+            Token::Punct("{") => {
+                let mut pairs = Vec::new();
+                while !self.eat("}") {
+                    let key_at = self.position();
+                    let key = match self.advance() {
+                        Token::Ident(k) | Token::Str(k) => k,
+                        _ => return Err(ExprError::at(key_at, "expected an object key")),
+                    };
+                    self.expect(":")?;
+                    pairs.push((key, self.expression()?));
+                    if !self.eat(",") {
+                        self.expect("}")?;
+                        break;
+                    }
+                }
+                Ok(Expr::Object(pairs))
+            }
+            Token::End => Err(ExprError::at(at, "unexpected end of expression")),
+            other => Err(ExprError::at(at, format!("unexpected {}", describe(&other)))),
+        }
+    }
+
+    fn list(&mut self, close: &str) -> Result<Vec<Expr>, ExprError> {
+        let mut items = Vec::new();
+        if self.eat(close) { return Ok(items); }
+        loop {
+            items.push(self.expression()?);
+            if self.eat(close) { return Ok(items); }
+            self.expect(",")?;
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Num(n) => format!("number {}", n),
+        Token::Str(s) => format!("string \"{}\"", s),
+        Token::Ident(i) => format!("'{}'", i),
+        Token::Punct(p) => format!("'{}'", p),
+        Token::End => "end of expression".to_string(),
+    }
+}
+
+pub fn parse(src: &str) -> Result<Expr, ExprError> {
+    if src.chars().count() > MAX_SOURCE_LEN {
+        return Err(ExprError::at(MAX_SOURCE_LEN, format!("expression is longer than {} characters", MAX_SOURCE_LEN)));
+    }
+    let mut parser = Parser { tokens: tokenize(src)?, pos: 0, depth: 0 };
+    let expr = parser.expression()?;
+    match parser.peek() {
+        Token::End => Ok(expr),
+        other => Err(ExprError::at(parser.position(), format!("unexpected {}", describe(other)))),
+    }
+}
+
+// --- Evaluation ---
+
+/// Integral results stay JSON integers, so `count + 1` stores 2 rather than 2.0.
+pub fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < 9_007_199_254_740_992.0 {
+        Value::from(n as i64)
+    } else {
+        serde_json::Number::from_f64(n).map(Value::Number).unwrap_or(Value::Null)
+    }
+}
+
+pub fn truthy(v: &Value) -> bool {
+    match v {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|f| f != 0.0 && !f.is_nan()),
+        Value::String(s) => !s.is_empty(),
+        _ => true,
+    }
+}
+
+/// Numbers compare by value (1 == 1.0), everything else structurally.
+pub fn loose_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        _ => a == b,
+    }
+}
+
+pub fn type_name(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Text form used for string concatenation and `string()`.
+pub fn to_text(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        Value::Null => "null".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn num(v: &Value, context: &str) -> Result<f64, ExprError> {
+    v.as_f64().ok_or_else(|| ExprError::eval(format!("{} expects a number, got {}", context, type_name(v))))
+}
+
+fn compare(op: &str, a: &Value, b: &Value) -> Result<bool, ExprError> {
+    let ord = match (a, b) {
+        (Value::Number(_), Value::Number(_)) => num(a, op)?.partial_cmp(&num(b, op)?),
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        _ => return Err(ExprError::eval(format!("cannot compare {} {} {}", type_name(a), op, type_name(b)))),
+    };
+    let ord = match ord { Some(o) => o, None => return Ok(false) }; // NaN
+    Ok(match op {
+        "<" => ord.is_lt(),
+        "<=" => ord.is_le(),
+        ">" => ord.is_gt(),
+        _ => ord.is_ge(),
+    })
+}
+
+fn arithmetic(op: &str, a: Value, b: Value) -> Result<Value, ExprError> {
+    if op == "+" {
+        match (&a, &b) {
+            (Value::String(_), _) | (_, Value::String(_)) => return Ok(Value::String(to_text(&a) + &to_text(&b))),
+            (Value::Array(x), Value::Array(y)) => return Ok(Value::Array(x.iter().chain(y).cloned().collect())),
+            _ => {}
+        }
+    }
+    let (x, y) = (num(&a, op)?, num(&b, op)?);
+    Ok(number(match op {
+        "+" => x + y,
+        "-" => x - y,
+        "*" => x * y,
+        "/" | "%" if y == 0.0 => return Err(ExprError::eval("division by zero")),
+        "/" => x / y,
+        _ => x % y,
+    }))
+}
+
+fn member(object: &Value, property: &Value, optional: bool) -> Result<Value, ExprError> {
+    match (object, property) {
+        (Value::Null, _) if optional => Ok(Value::Null),
+        (Value::Null, p) => Err(ExprError::eval(format!("cannot read {} of null (use ?. for optional access)", to_text(p)))),
+        (Value::Object(map), Value::String(key)) => Ok(map.get(key).cloned().unwrap_or(Value::Null)),
+        (Value::Array(items), Value::String(key)) if key == "length" => Ok(Value::from(items.len())),
+        (Value::String(s), Value::String(key)) if key == "length" => Ok(Value::from(s.chars().count())),
+        (Value::Array(items), Value::Number(n)) => Ok(n.as_f64()
+            .filter(|i| *i >= 0.0 && i.fract() == 0.0)
+            .and_then(|i| items.get(i as usize).cloned())
+            .unwrap_or(Value::Null)),
+        (Value::String(s), Value::Number(n)) => Ok(n.as_f64()
+            .filter(|i| *i >= 0.0 && i.fract() == 0.0)
+            .and_then(|i| s.chars().nth(i as usize))
+            .map(|c| Value::String(c.to_string()))
+            .unwrap_or(Value::Null)),
+        (o, p) => Err(ExprError::eval(format!("cannot read {} of {}", to_text(p), type_name(o)))),
+    }
+}
+
+pub fn evaluate(expr: &Expr, scope: &dyn Scope) -> Result<Value, ExprError> {
+    match expr {
+        Expr::Literal(v) => Ok(v.clone()),
+        Expr::Ident(name) => scope.lookup(name).ok_or_else(|| ExprError::eval(format!("unknown variable '{}'", name))),
+        Expr::Array(items) => Ok(Value::Array(items.iter().map(|e| evaluate(e, scope)).collect::<Result<_, _>>()?)),
+        Expr::Object(pairs) => {
+            let mut map = Map::new();
+            for (k, e) in pairs {
+                map.insert(k.clone(), evaluate(e, scope)?);
+            }
+            Ok(Value::Object(map))
+        }
+        Expr::Member { .. } => Ok(access_chain(expr, scope)?.unwrap_or(Value::Null)),
+        Expr::Call { name, args } => {
+            let values = args.iter().map(|e| evaluate(e, scope)).collect::<Result<Vec<_>, _>>()?;
+            call(name, &values)
+        }
+        Expr::Unary { op, operand } => {
+            let v = evaluate(operand, scope)?;
+            match *op {
+                "!" => Ok(Value::Bool(!truthy(&v))),
+                _ => Ok(number(-num(&v, "-")?)),
+            }
+        }
+        Expr::Binary { op: "??", left, right } => {
+            // An undefined variable on the left falls back too: `count ?? 0`
+            let l = match left.as_ref() {
+                Expr::Ident(name) => scope.lookup(name).unwrap_or(Value::Null),
+                other => evaluate(other, scope)?,
+            };
+            if l.is_null() { evaluate(right, scope) } else { Ok(l) }
+        }
+        Expr::Binary { op, left, right } => {
+            let l = evaluate(left, scope)?;
+            match *op {
+                // Short-circuiting operators return the deciding operand, like JS
+                "&&" => if truthy(&l) { evaluate(right, scope) } else { Ok(l) },
+                "||" => if truthy(&l) { Ok(l) } else { evaluate(right, scope) },
+                _ => {
+                    let r = evaluate(right, scope)?;
+                    match *op {
+                        "==" => Ok(Value::Bool(loose_eq(&l, &r))),
+                        "!=" => Ok(Value::Bool(!loose_eq(&l, &r))),
+                        "<" | "<=" | ">" | ">=" => Ok(Value::Bool(compare(op, &l, &r)?)),
+                        _ => arithmetic(op, l, r),
+                    }
+                }
+            }
+        }
+        Expr::Conditional { test, then, otherwise } => {
+            if truthy(&evaluate(test, scope)?) { evaluate(then, scope) } else { evaluate(otherwise, scope) }
+        }
+    }
+}
+
+/// Evaluates `a.b?.c.d`. None means an optional step met null, which skips the rest of the chain (like JS).
+fn access_chain(expr: &Expr, scope: &dyn Scope) -> Result<Option<Value>, ExprError> {
+    let (object, property, optional) = match expr {
+        Expr::Member { object, property, optional } => (object, property, *optional),
+        other => return evaluate(other, scope).map(Some),
+    };
+    let target = match (object.as_ref(), optional) {
+        // A missing root is null under optional access: `user?.name` with no `user`
+        (Expr::Ident(name), true) => scope.lookup(name).unwrap_or(Value::Null),
+        _ => match access_chain(object, scope)? {
+            Some(v) => v,
+            None => return Ok(None),
+        },
+    };
+    if target.is_null() && optional { return Ok(None); }
+    member(&target, &evaluate(property, scope)?, optional).map(Some)
+}
+
+/// Parses and evaluates in one go.
+pub fn eval_str(src: &str, scope: &dyn Scope) -> Result<Value, ExprError> {
+    evaluate(&parse(src)?, scope)
+}
+
+fn arity(name: &str, args: &[Value], min: usize, max: usize) -> Result<(), ExprError> {
+    if args.len() < min || args.len() > max {
+        let expected = if min == max { min.to_string() } else { format!("{}-{}", min, max) };
+        return Err(ExprError::eval(format!("{}() takes {} argument(s), got {}", name, expected, args.len())));
+    }
+    Ok(())
+}
+
+fn text<'a>(name: &str, v: &'a Value) -> Result<&'a str, ExprError> {
+    v.as_str().ok_or_else(|| ExprError::eval(format!("{}() expects a string, got {}", name, type_name(v))))
+}
+
+fn array<'a>(name: &str, v: &'a Value) -> Result<&'a Vec<Value>, ExprError> {
+    v.as_array().ok_or_else(|| ExprError::eval(format!("{}() expects an array, got {}", name, type_name(v))))
+}
+
+/// Resolves a possibly negative index against a length, clamped to 0..=len.
+fn clamp_index(i: f64, len: usize) -> usize {
+    let i = if i < 0.0 { len as f64 + i } else { i };
+    i.max(0.0).min(len as f64) as usize
+}
+
+/// Built-in helpers. Anything not listed here is an error: expressions cannot reach the host.
+fn call(name: &str, args: &[Value]) -> Result<Value, ExprError> {
+    let arg = |i: usize| args.get(i).unwrap_or(&Value::Null);
+    match name {
+        // Strings
+        "upper" | "lower" | "trim" => {
+            arity(name, args, 1, 1)?;
+            let s = text(name, arg(0))?;
+            Ok(Value::String(match name { "upper" => s.to_uppercase(), "lower" => s.to_lowercase(), _ => s.trim().to_string() }))
+        }
+        "startsWith" | "endsWith" => {
+            arity(name, args, 2, 2)?;
+            let (s, affix) = (text(name, arg(0))?, text(name, arg(1))?);
+            Ok(Value::Bool(if name == "startsWith" { s.starts_with(affix) } else { s.ends_with(affix) }))
+        }
+        "split" => {
+            arity(name, args, 2, 2)?;
+            Ok(Value::Array(text(name, arg(0))?.split(text(name, arg(1))?).map(|p| Value::String(p.to_string())).collect()))
+        }
+        "replace" => {
+            arity(name, args, 3, 3)?;
+            Ok(Value::String(text(name, arg(0))?.replace(text(name, arg(1))?, text(name, arg(2))?)))
+        }
+        "join" => {
+            arity(name, args, 1, 2)?;
+            let sep = if args.len() > 1 { text(name, arg(1))? } else { "," };
+            Ok(Value::String(array(name, arg(0))?.iter().map(to_text).collect::<Vec<_>>().join(sep)))
+        }
+
+        // Strings and arrays
+        "len" => {
+            arity(name, args, 1, 1)?;
+            match arg(0) {
+                Value::String(s) => Ok(Value::from(s.chars().count())),
+                Value::Array(a) => Ok(Value::from(a.len())),
+                Value::Object(o) => Ok(Value::from(o.len())),
+                Value::Null => Ok(Value::from(0)),
+                other => Err(ExprError::eval(format!("len() expects a string, array or object, got {}", type_name(other)))),
+            }
+        }
+        "contains" | "indexOf" => {
+            arity(name, args, 2, 2)?;
+            let index = match arg(0) {
+                Value::String(s) => s.find(text(name, arg(1))?).map(|b| s[..b].chars().count()),
+                Value::Array(a) => a.iter().position(|v| loose_eq(v, arg(1))),
+                Value::Null => None,
+                other => return Err(ExprError::eval(format!("{}() expects a string or array, got {}", name, type_name(other)))),
+            };
+            Ok(if name == "contains" { Value::Bool(index.is_some()) } else { Value::from(index.map_or(-1, |i| i as i64)) })
+        }
+        "slice" => {
+            arity(name, args, 2, 3)?;
+            let start = num(arg(1), "slice()")?;
+            match arg(0) {
+                Value::String(s) => {
+                    let chars: Vec<char> = s.chars().collect();
+                    let end = if args.len() > 2 { clamp_index(num(arg(2), "slice()")?, chars.len()) } else { chars.len() };
+                    let start = clamp_index(start, chars.len()).min(end);
+                    Ok(Value::String(chars[start..end].iter().collect()))
+                }
+                Value::Array(a) => {
+                    let end = if args.len() > 2 { clamp_index(num(arg(2), "slice()")?, a.len()) } else { a.len() };
+                    let start = clamp_index(start, a.len()).min(end);
+                    Ok(Value::Array(a[start..end].to_vec()))
+                }
+                other => Err(ExprError::eval(format!("slice() expects a string or array, got {}", type_name(other)))),
+            }
+        }
+        "reverse" => {
+            arity(name, args, 1, 1)?;
+            match arg(0) {
+                Value::String(s) => Ok(Value::String(s.chars().rev().collect())),
+                Value::Array(a) => Ok(Value::Array(a.iter().rev().cloned().collect())),
+                other => Err(ExprError::eval(format!("reverse() expects a string or array, got {}", type_name(other)))),
+            }
+        }
+
+        // Arrays
+        "first" | "last" => {
+            arity(name, args, 1, 1)?;
+            let a = array(name, arg(0))?;
+            Ok((if name == "first" { a.first() } else { a.last() }).cloned().unwrap_or(Value::Null))
+        }
+        "sum" => {
+            arity(name, args, 1, 1)?;
+            let total = array(name, arg(0))?.iter().map(|v| num(v, "sum()")).sum::<Result<f64, _>>()?;
+            Ok(number(total))
+        }
+        "unique" => {
+            arity(name, args, 1, 1)?;
+            let mut out: Vec<Value> = Vec::new();
+            for v in array(name, arg(0))? {
+                if !out.iter().any(|o| loose_eq(o, v)) { out.push(v.clone()); }
+            }
+            Ok(Value::Array(out))
+        }
+        "sort" => {
+            arity(name, args, 1, 1)?;
+            let mut items = array(name, arg(0))?.clone();
+            let mut failed = None;
+            items.sort_by(|a, b| match (a, b) {
+                (Value::Number(x), Value::Number(y)) => x.as_f64().partial_cmp(&y.as_f64()).unwrap_or(std::cmp::Ordering::Equal),
+                (Value::String(x), Value::String(y)) => x.cmp(y),
+                _ => {
+                    failed = Some(format!("sort() cannot order {} and {}", type_name(a), type_name(b)));
+                    std::cmp::Ordering::Equal
+                }
+            });
+            match failed { Some(m) => Err(ExprError::eval(m)), None => Ok(Value::Array(items)) }
+        }
+
+        // Objects
+        "keys" | "values" => {
+            arity(name, args, 1, 1)?;
+            let map = arg(0).as_object().ok_or_else(|| ExprError::eval(format!("{}() expects an object, got {}", name, type_name(arg(0)))))?;
+            Ok(Value::Array(if name == "keys" { map.keys().map(|k| Value::String(k.clone())).collect() } else { map.values().cloned().collect() }))
+        }
+
+        // Numbers
+        "abs" | "floor" | "ceil" => {
+            arity(name, args, 1, 1)?;
+            let n = num(arg(0), name)?;
+            Ok(number(match name { "abs" => n.abs(), "floor" => n.floor(), _ => n.ceil() }))
+        }
+        "round" => {
+            arity(name, args, 1, 2)?;
+            let digits = if args.len() > 1 { num(arg(1), "round()")?.clamp(0.0, 10.0) } else { 0.0 };
+            let factor = 10f64.powi(digits as i32);
+            Ok(number((num(arg(0), "round()")? * factor).round() / factor))
+        }
+        "min" | "max" => {
+            // min(a, b, ...) or min(array)
+            let items: &[Value] = match args {
+                [Value::Array(a)] => a,
+                _ => args,
+            };
+            let nums = items.iter().map(|v| num(v, name)).collect::<Result<Vec<f64>, _>>()?;
+            let pick = if name == "min" { nums.iter().cloned().reduce(f64::min) } else { nums.iter().cloned().reduce(f64::max) };
+            Ok(pick.map(number).unwrap_or(Value::Null))
+        }
+
+        // Conversions
+        "number" => {
+            arity(name, args, 1, 1)?;
+            match arg(0) {
+                Value::Number(_) => Ok(arg(0).clone()),
+                Value::String(s) => Ok(s.trim().trim_end_matches("px").parse::<f64>().map(number).unwrap_or(Value::Null)),
+                Value::Bool(b) => Ok(Value::from(*b as i64)),
+                _ => Ok(Value::Null),
+            }
+        }
+        "string" => {
+            arity(name, args, 1, 1)?;
+            Ok(Value::String(to_text(arg(0))))
+        }
+        "boolean" => {
+            arity(name, args, 1, 1)?;
+            Ok(Value::Bool(truthy(arg(0))))
+        }
+        "isNull" => {
+            arity(name, args, 1, 1)?;
+            Ok(Value::Bool(arg(0).is_null()))
+        }
+        "typeOf" => {
+            arity(name, args, 1, 1)?;
+            Ok(Value::String(type_name(arg(0)).to_string()))
+        }
+        _ => Err(ExprError::eval(format!("unknown function '{}'", name))),
+    }
+}
+
+// --- WASM Exports ---
+
+/// Editor preview: evaluates `src` against a JSON object of variables.
+/// Returns `{ "value": ... }` or `{ "error": { "message", "position" } }`.
+#[wasm_bindgen]
+pub fn evaluate_expression(src: &str, scope_json: &str) -> String {
+    let scope: Map<String, Value> = serde_json::from_str(scope_json).unwrap_or_default();
+    let result = match eval_str(src, &scope) {
+        Ok(value) => serde_json::json!({ "value": value }),
+        Err(e) => serde_json::json!({ "error": e }),
+    };
+    result.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn scope() -> HashMap<String, Value> {
+        HashMap::from([
+            ("cart".to_string(), json!({ "total": 120.5, "items": [{ "sku": "a", "qty": 2 }, { "sku": "b", "qty": 1 }] })),
+            ("user".to_string(), json!({ "role": "vip", "name": "Ada" })),
+            ("count".to_string(), json!(1)),
+            ("empty".to_string(), Value::Null),
+        ])
+    }
+
+    fn eval(src: &str) -> Value {
+        eval_str(src, &scope()).unwrap_or_else(|e| panic!("{}: {}", src, e))
+    }
+
+    #[test]
+    fn test_operators_and_access() {
+        assert_eq!(eval("cart.total > 100 && user.role == \"vip\""), json!(true));
+        assert_eq!(eval("count + 1"), json!(2));
+        assert_eq!(eval("2 + 3 * 4 - 10 / 4"), json!(11.5));
+        assert_eq!(eval("1 == 1.0 && !(2 < 1)"), json!(true));
+        assert_eq!(eval("'Hi ' + user.name"), json!("Hi Ada"));
+        assert_eq!(eval("cart.items[1].sku"), json!("b"));
+        assert_eq!(eval("cart.items.length"), json!(2));
+        assert_eq!(eval("empty?.deep.path"), json!(null));
+        assert_eq!(eval("missing?.x ?? 'fallback'"), json!("fallback"));
+        assert_eq!(eval("count > 0 ? 'some' : 'none'"), json!("some"));
+        assert_eq!(eval("user.role || 'guest'"), json!("vip"));
+        assert_eq!(eval("undefinedCount ?? 0"), json!(0));
+        assert_eq!(eval("{ total: cart.total, tags: [1, 2] }.tags[0]"), json!(1));
+    }
+
+    #[test]
+    fn test_helpers() {
+        assert_eq!(eval("upper(user.name) + len('abc')"), json!("ADA3"));
+        assert_eq!(eval("contains(['a', 'b'], 'b') && startsWith(user.name, 'A')"), json!(true));
+        assert_eq!(eval("join(split('a-b-c', '-'), '+')"), json!("a+b+c"));
+        assert_eq!(eval("slice('hello', -3)"), json!("llo"));
+        assert_eq!(eval("max([3, 9, 4])"), json!(9));
+        assert_eq!(eval("round(cart.total / 3, 2)"), json!(40.17));
+        assert_eq!(eval("number('12px') + 1"), json!(13));
+        assert_eq!(eval("sort(unique([3, 1, 3, 2]))"), json!([1, 2, 3]));
+    }
+
+    #[test]
+    fn test_errors_are_reported() {
+        let err = parse("cart.total >").unwrap_err();
+        assert_eq!(err.position, Some(12));
+        assert!(parse("count = 2").unwrap_err().message.contains("assignment"));
+        assert!(parse(&"(".repeat(200)).unwrap_err().message.contains("nested"));
+        // Left-associative chains nest too: a maximum-length one is rejected instead of overflowing the stack
+        let chain = vec!["1"; MAX_SOURCE_LEN / 2].join("+");
+        assert!(parse(&chain).unwrap_err().message.contains("nested"));
+        assert!(parse(&format!("a{}", ".b".repeat(MAX_SOURCE_LEN / 2 - 1))).unwrap_err().message.contains("nested"));
+        assert_eq!(eval(&vec!["1"; 32].join(" + ")), json!(32));
+
+        let err = eval_str("empty.name", &scope()).unwrap_err();
+        assert!(err.message.contains("of null"));
+        assert!(eval_str("nope + 1", &scope()).unwrap_err().message.contains("unknown variable 'nope'"));
+        assert!(eval_str("system('rm')", &scope()).unwrap_err().message.contains("unknown function"));
+        assert!(eval_str("1 / 0", &scope()).unwrap_err().message.contains("division by zero"));
+        assert!(eval_str("user < 1", &scope()).unwrap_err().message.contains("cannot compare"));
+    }
+}
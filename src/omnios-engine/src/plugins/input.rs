@@ -123,6 +123,15 @@ impl ShortcutsPlugin {
         self.register("delete", false, false, false, "Delete");
         self.register("backspace", false, false, false, "Delete");
         self.register("escape", false, false, false, "Escape");
+
+        // Canvas navigation (resolved engine-side by `perform_action`)
+        for (key, dir) in [("arrowleft", "Left"), ("arrowright", "Right"), ("arrowup", "Up"), ("arrowdown", "Down")] {
+            self.register(key, false, false, false, &format!("Nudge{}", dir));
+            self.register(key, false, true, false, &format!("Nudge{}Large", dir));
+        }
+        self.register("tab", false, false, false, "SelectNext");
+        self.register("tab", false, true, false, "SelectPrevious");
+        self.register("enter", false, false, false, "SelectChild");
     }
 
     pub fn resolve(&self, key: &str, ctrl: bool, shift: bool, alt: bool, meta: bool) -> Option<String> {
@@ -157,8 +166,10 @@ impl OmniosPlugin for ShortcutsPlugin {
 
 #[wasm_bindgen]
 pub fn handle_key_event(key: &str, ctrl: bool, shift: bool, alt: bool, meta: bool) -> Option<JsValue> {
-    let registry = SHORTCUT_REGISTRY.lock().unwrap();
-    if let Some(action_name) = registry.resolve(key, ctrl, shift, alt, meta) {
+    let action = SHORTCUT_REGISTRY.lock().unwrap().resolve(key, ctrl, shift, alt, meta);
+    if let Some(action_name) = action {
+        // Camera actions are applied here; the host still receives the action name
+        crate::plugins::camera::CAMERA.lock().unwrap().apply_action(&action_name);
         // Return JsValue (String) directly
         serde_wasm_bindgen::to_value(&action_name).ok()
     } else {
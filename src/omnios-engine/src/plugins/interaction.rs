@@ -1,5 +1,8 @@
 use crate::PROJECT_STATE;
-use crate::plugins::spatial_index::SPATIAL_INDEX;
+use crate::plugins::camera::{Camera, CAMERA};
+use crate::plugins::spatial_index::{ElementBounds, SPATIAL_INDEX};
+use lazy_static::lazy_static;
+use std::collections::HashSet;
 use std::sync::Mutex;
 
 #[derive(Debug, Clone)]
@@ -96,94 +99,365 @@ impl InteractionPlugin {
         height: f32,
         threshold: f32,
     ) -> SnapResult {
+        let config = SnapConfig { threshold, ..SNAP_CONFIG.lock().unwrap().clone() };
+        Self::find_snap_targets_with(element_id, x, y, width, height, &config)
+    }
+
+    pub fn find_snap_targets_with(
+        element_id: &str,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        config: &SnapConfig,
+    ) -> SnapResult {
+        // Descendants move with the element, so they are never snap targets
+        let (parent_id, excluded) = {
+            let state_guard = PROJECT_STATE.lock().unwrap();
+            match &*state_guard {
+                Some(state) => {
+                    let parent_id = state.elements.get(element_id).and_then(|el| el.parent_id.clone());
+                    let mut excluded: HashSet<String> = HashSet::new();
+                    let mut stack = vec![element_id.to_string()];
+                    while let Some(id) = stack.pop() {
+                        if let Some(children) = state.elements.get(&id).and_then(|el| el.children.as_ref()) {
+                            stack.extend(children.iter().cloned());
+                        }
+                        excluded.insert(id);
+                    }
+                    (parent_id, excluded)
+                }
+                None => (None, HashSet::from([element_id.to_string()])),
+            }
+        };
+
+        // Threshold and radius are screen pixels; only what is on screen (or nearby) is a candidate
+        let (config, visible) = {
+            let camera = CAMERA.lock().unwrap();
+            (config.in_world(&camera), camera.visible_rect())
+        };
+        let r = config.search_radius;
+        let area = match visible {
+            Some(v) => Rect { min_x: v.min_x.min(x), min_y: v.min_y.min(y), max_x: v.max_x.max(x + width), max_y: v.max_y.max(y + height) },
+            None => Rect { min_x: x - r, min_y: y - r, max_x: x + width + r, max_y: y + height + r },
+        };
+
         let spatial_guard = SPATIAL_INDEX.lock().unwrap();
-        // Ideally we would use spatial_guard.query_area to limit search, 
-        // but for now we'll iterate to ensure we catch all edges.
-        
-        let mut snapped_x = None;
-        let mut snapped_y = None;
-        let mut guides = Vec::new();
-
-        let mut best_dist_x = threshold;
-        let mut best_dist_y = threshold;
-
-        // Edges of the moving element
-        let l = x;
-        let c_x = x + width / 2.0;
-        let r = x + width;
-
-        let t = y;
-        let m_y = y + height / 2.0;
-        let b = y + height;
-
-        // Iterate all elements (Naive O(N) but fast enough for <1000 items in WASM)
-        for (other_id, bounds) in spatial_guard.iter_all() {
-            if other_id == element_id { continue; } // Don't snap to self
-
-            let other_w = bounds.max_x - bounds.min_x;
-            let other_h = bounds.max_y - bounds.min_y;
-
-            let ol = bounds.min_x;
-            let oc_x = bounds.min_x + other_w / 2.0;
-            let or = bounds.max_x;
-
-            let ot = bounds.min_y;
-            let om_y = bounds.min_y + other_h / 2.0;
-            let ob = bounds.max_y;
-
-            // --- X Axis Snapping ---
-            // Left to Left
-            if (l - ol).abs() < best_dist_x { best_dist_x = (l - ol).abs(); snapped_x = Some(ol); }
-            // Left to Right
-            if (l - or).abs() < best_dist_x { best_dist_x = (l - or).abs(); snapped_x = Some(or); }
-            // Right to Right
-            if (r - or).abs() < best_dist_x { best_dist_x = (r - or).abs(); snapped_x = Some(or - width); }
-            // Right to Left
-            if (r - ol).abs() < best_dist_x { best_dist_x = (r - ol).abs(); snapped_x = Some(ol - width); }
-            // Center to Center
-            if (c_x - oc_x).abs() < best_dist_x { best_dist_x = (c_x - oc_x).abs(); snapped_x = Some(oc_x - width / 2.0); }
-
-            // --- Y Axis Snapping ---
-            // Top to Top
-            if (t - ot).abs() < best_dist_y { best_dist_y = (t - ot).abs(); snapped_y = Some(ot); }
-            // Top to Bottom
-            if (t - ob).abs() < best_dist_y { best_dist_y = (t - ob).abs(); snapped_y = Some(ob); }
-            // Bottom to Bottom
-            if (b - ob).abs() < best_dist_y { best_dist_y = (b - ob).abs(); snapped_y = Some(ob - height); }
-            // Bottom to Top
-            if (b - ot).abs() < best_dist_y { best_dist_y = (b - ot).abs(); snapped_y = Some(ot - height); }
-            // Middle to Middle
-            if (m_y - om_y).abs() < best_dist_y { best_dist_y = (m_y - om_y).abs(); snapped_y = Some(om_y - height / 2.0); }
+        let others: Vec<Rect> = spatial_guard.query_intersecting(area.min_x, area.min_y, area.max_x - area.min_x, area.max_y - area.min_y)
+            .iter()
+            .filter(|id| !excluded.contains(*id))
+            .filter_map(|id| spatial_guard.query_one(id))
+            .map(|b| Rect::from(&b))
+            .collect();
+        let parent = parent_id.and_then(|pid| spatial_guard.query_one(&pid)).map(|b| Rect::from(&b));
+
+        compute_snap(Rect::from_xywh(x, y, width, height), &others, parent, &config)
+    }
+}
+
+// --- SNAPPING ---
+
+lazy_static! {
+    pub static ref SNAP_CONFIG: Mutex<SnapConfig> = Mutex::new(SnapConfig::default());
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SnapConfig {
+    pub threshold: f32,
+    pub search_radius: f32,   // Neighbourhood queried around the moving element
+    pub objects: bool,        // Edge and center alignment with other elements
+    pub spacing: bool,        // Equal gaps between neighbours
+    pub grid_size: Option<f32>,
+    pub columns: Option<ColumnGrid>, // Layout grid inside the parent
+    pub baseline: Option<f32>,       // Baseline grid, measured from the parent's top
+    pub pixel_grid: bool,     // Round the result to whole pixels
+}
+
+impl SnapConfig {
+    /// `threshold` and `search_radius` are screen pixels: the same config in world units at the camera's zoom.
+    pub fn in_world(&self, camera: &Camera) -> SnapConfig {
+        SnapConfig {
+            threshold: camera.screen_distance(self.threshold),
+            search_radius: camera.screen_distance(self.search_radius),
+            ..self.clone()
+        }
+    }
+}
+
+impl Default for SnapConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 5.0,
+            search_radius: 800.0,
+            objects: true,
+            spacing: true,
+            grid_size: None,
+            columns: None,
+            baseline: None,
+            pixel_grid: true,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnGrid {
+    pub count: u32,
+    #[serde(default)]
+    pub gutter: f32,
+    #[serde(default)]
+    pub margin: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl Rect {
+    pub fn from_xywh(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Rect { min_x: x, min_y: y, max_x: x + w, max_y: y + h }
+    }
+}
+
+impl From<&ElementBounds> for Rect {
+    fn from(b: &ElementBounds) -> Self {
+        Rect { min_x: b.min_x, min_y: b.min_y, max_x: b.max_x, max_y: b.max_y }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Axis { X, Y }
+
+impl Axis {
+    /// Extent along the snapping axis.
+    fn span(self, r: &Rect) -> (f32, f32) {
+        match self { Axis::X => (r.min_x, r.max_x), Axis::Y => (r.min_y, r.max_y) }
+    }
+
+    /// Extent across the snapping axis (where guide lines run).
+    fn cross(self, r: &Rect) -> (f32, f32) {
+        match self { Axis::X => (r.min_y, r.max_y), Axis::Y => (r.min_x, r.max_x) }
+    }
+
+    /// Alignment lines for X snaps are vertical.
+    fn line(self) -> &'static str {
+        match self { Axis::X => "vertical", Axis::Y => "horizontal" }
+    }
+
+    /// Gap measurements for X snaps are drawn as horizontal segments.
+    fn measure(self) -> &'static str {
+        match self { Axis::X => "horizontal", Axis::Y => "vertical" }
+    }
+}
+
+const TIE_EPSILON: f32 = 0.01;
+
+/// Best snap found so far on one axis; equally good snaps contribute their guides together.
+struct AxisSnap {
+    delta: f32,
+    dist: f32,
+    guides: Vec<SnapGuide>,
+}
+
+impl AxisSnap {
+    fn new(threshold: f32) -> Self {
+        Self { delta: 0.0, dist: threshold, guides: Vec::new() }
+    }
+
+    fn offer(&mut self, delta: f32, guides: Vec<SnapGuide>) {
+        let dist = delta.abs();
+        if dist < self.dist - TIE_EPSILON || (self.guides.is_empty() && dist < self.dist) {
+            self.delta = delta;
+            self.dist = dist;
+            self.guides = guides;
+        } else if !self.guides.is_empty() && (delta - self.delta).abs() <= TIE_EPSILON {
+            self.guides.extend(guides);
         }
+    }
+}
+
+pub(crate) fn format_distance(value: f32) -> String {
+    if (value - value.round()).abs() < 0.05 {
+        format!("{}", value.round() as i64)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+/// Distance between two extents, 0 when they overlap.
+fn gap_between(a: (f32, f32), b: (f32, f32)) -> f32 {
+    if b.1 <= a.0 { a.0 - b.1 } else if a.1 <= b.0 { b.0 - a.1 } else { 0.0 }
+}
 
-        // Generate Guides
-        if let Some(sx) = snapped_x {
-            guides.push(SnapGuide { orientation: "vertical".to_string(), value: sx, label: "".to_string() });
-             // Also add right edge guide if we snapped right edge
-             if (sx + width - x - width).abs() < 0.1 { // If we snapped strictly by the left edge
-                 // Just left guide
-             } else {
-                 // It's ambiguous which edge triggered, usually UI just draws a line at the snap position 
-                 // or the relevant edge. For MVP, we pass the snapped X position.
-             }
+fn guide(kind: &str, orientation: &str, value: f32, extent: (f32, f32), label: String) -> SnapGuide {
+    SnapGuide { orientation: orientation.to_string(), value, label, kind: kind.to_string(), start: extent.0, end: extent.1 }
+}
+
+/// Measurement segment spanning the gap `from..to` along `axis`, placed in the middle of the shared cross extent.
+fn gap_marker(axis: Axis, from: f32, to: f32, a: &Rect, b: &Rect) -> SnapGuide {
+    let (a0, a1) = axis.cross(a);
+    let (b0, b1) = axis.cross(b);
+    let mid = (a0.max(b0) + a1.min(b1)) / 2.0;
+    guide("spacing", axis.measure(), mid, (from, to), format_distance(to - from))
+}
+
+fn snap_objects(axis: Axis, moving: &Rect, others: &[Rect], snap: &mut AxisSnap) {
+    let (m0, m1) = axis.span(moving);
+    let mc = (m0 + m1) / 2.0;
+    let cross = axis.cross(moving);
+
+    for other in others {
+        let (o0, o1) = axis.span(other);
+        let oc = (o0 + o1) / 2.0;
+        let other_cross = axis.cross(other);
+        let extent = (cross.0.min(other_cross.0), cross.1.max(other_cross.1));
+        let gap = gap_between(cross, other_cross);
+        let label = if gap > 0.0 { format_distance(gap) } else { String::new() };
+
+        for (edge, line) in [(m0, o0), (m0, o1), (m1, o1), (m1, o0)] {
+            snap.offer(line - edge, vec![guide("edge", axis.line(), line, extent, label.clone())]);
         }
-        if let Some(sy) = snapped_y {
-            guides.push(SnapGuide { orientation: "horizontal".to_string(), value: sy, label: "".to_string() });
+        snap.offer(oc - mc, vec![guide("center", axis.line(), oc, extent, label.clone())]);
+    }
+}
+
+/// Equal spacing: centers the element between its two neighbours, or repeats a gap that
+/// already exists between neighbours in the same row (X) or column (Y).
+fn snap_spacing(axis: Axis, moving: &Rect, others: &[Rect], snap: &mut AxisSnap) {
+    let (m0, m1) = axis.span(moving);
+    let size = m1 - m0;
+    let mc = (m0 + m1) / 2.0;
+    let cross = axis.cross(moving);
+
+    let mut row: Vec<&Rect> = others.iter()
+        .filter(|o| {
+            let (c0, c1) = axis.cross(o);
+            c0 < cross.1 && c1 > cross.0
+        })
+        .collect();
+    row.sort_by(|a, b| axis.span(a).0.partial_cmp(&axis.span(b).0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let before = row.iter().filter(|o| axis.span(o).1 <= mc).max_by(|a, b| {
+        axis.span(a).1.partial_cmp(&axis.span(b).1).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let after = row.iter().filter(|o| axis.span(o).0 >= mc).min_by(|a, b| {
+        axis.span(a).0.partial_cmp(&axis.span(b).0).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    if let (Some(a), Some(b)) = (before, after) {
+        let start = (axis.span(a).1 + axis.span(b).0 - size) / 2.0;
+        if start >= axis.span(a).1 {
+            let placed = moved(axis, moving, start - m0);
+            snap.offer(start - m0, vec![
+                gap_marker(axis, axis.span(a).1, start, a, &placed),
+                gap_marker(axis, start + size, axis.span(b).0, &placed, b),
+            ]);
         }
+    }
 
-        SnapResult {
-            x: snapped_x.unwrap_or(x),
-            y: snapped_y.unwrap_or(y),
-            guides,
+    // Gaps already present between consecutive neighbours
+    for pair in row.windows(2) {
+        let (p, q) = (pair[0], pair[1]);
+        let g = axis.span(q).0 - axis.span(p).1;
+        if g <= 0.0 { continue; }
+        let reference = gap_marker(axis, axis.span(p).1, axis.span(q).0, p, q);
+
+        if let Some(a) = before {
+            let start = axis.span(a).1 + g;
+            let placed = moved(axis, moving, start - m0);
+            snap.offer(start - m0, vec![gap_marker(axis, axis.span(a).1, start, a, &placed), reference.clone()]);
+        }
+        if let Some(b) = after {
+            let start = axis.span(b).0 - g - size;
+            let placed = moved(axis, moving, start - m0);
+            snap.offer(start - m0, vec![gap_marker(axis, start + size, axis.span(b).0, &placed, b), reference.clone()]);
         }
     }
 }
 
-#[derive(serde::Serialize, Debug)]
+fn moved(axis: Axis, r: &Rect, delta: f32) -> Rect {
+    match axis {
+        Axis::X => Rect { min_x: r.min_x + delta, max_x: r.max_x + delta, ..*r },
+        Axis::Y => Rect { min_y: r.min_y + delta, max_y: r.max_y + delta, ..*r },
+    }
+}
+
+/// Snaps either edge to the nearest of `lines` (sorted or not).
+fn snap_lines(axis: Axis, kind: &str, moving: &Rect, lines: &[f32], snap: &mut AxisSnap) {
+    let (m0, m1) = axis.span(moving);
+    let extent = axis.cross(moving);
+    for &line in lines {
+        for edge in [m0, m1] {
+            snap.offer(line - edge, vec![guide(kind, axis.line(), line, extent, String::new())]);
+        }
+    }
+}
+
+/// Multiples of `step` from `origin` that lie near the element's edges.
+pub(crate) fn grid_lines(origin: f32, step: f32, edges: (f32, f32)) -> Vec<f32> {
+    if step <= 0.0 { return Vec::new(); }
+    [edges.0, edges.1].iter()
+        .flat_map(|e| {
+            let k = ((e - origin) / step).round();
+            [origin + (k - 1.0) * step, origin + k * step, origin + (k + 1.0) * step]
+        })
+        .collect()
+}
+
+pub(crate) fn column_lines(parent: &Rect, grid: &ColumnGrid) -> Vec<f32> {
+    if grid.count == 0 { return Vec::new(); }
+    let content = parent.max_x - parent.min_x - grid.margin * 2.0;
+    let col = (content - grid.gutter * (grid.count - 1) as f32) / grid.count as f32;
+    if col <= 0.0 { return Vec::new(); }
+    (0..grid.count)
+        .flat_map(|i| {
+            let left = parent.min_x + grid.margin + i as f32 * (col + grid.gutter);
+            [left, left + col]
+        })
+        .collect()
+}
+
+/// Pure snapping pass: `others` are candidate elements, `parent` anchors column and baseline grids.
+pub fn compute_snap(moving: Rect, others: &[Rect], parent: Option<Rect>, config: &SnapConfig) -> SnapResult {
+    let mut result = SnapResult { x: moving.min_x, y: moving.min_y, guides: Vec::new() };
+
+    for axis in [Axis::X, Axis::Y] {
+        let mut snap = AxisSnap::new(config.threshold);
+        if config.objects { snap_objects(axis, &moving, others, &mut snap); }
+        if config.spacing { snap_spacing(axis, &moving, others, &mut snap); }
+        if let Some(size) = config.grid_size {
+            snap_lines(axis, "grid", &moving, &grid_lines(0.0, size, axis.span(&moving)), &mut snap);
+        }
+        if let (Axis::X, Some(columns), Some(p)) = (axis, &config.columns, &parent) {
+            snap_lines(axis, "column", &moving, &column_lines(p, columns), &mut snap);
+        }
+        if let (Axis::Y, Some(step)) = (axis, config.baseline) {
+            let origin = parent.map(|p| p.min_y).unwrap_or(0.0);
+            snap_lines(axis, "baseline", &moving, &grid_lines(origin, step, axis.span(&moving)), &mut snap);
+        }
+
+        let value = match axis { Axis::X => &mut result.x, Axis::Y => &mut result.y };
+        *value += snap.delta;
+        if config.pixel_grid { *value = value.round(); }
+        result.guides.extend(snap.guides);
+    }
+
+    result
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
 pub struct SnapGuide {
     pub orientation: String, // "vertical" | "horizontal"
     pub value: f32,
-    pub label: String,
+    pub label: String,       // Distance in px, empty for pure alignment
+    pub kind: String,        // "edge" | "center" | "spacing" | "grid" | "column" | "baseline"
+    pub start: f32,          // Extent along the guide
+    pub end: f32,
 }
 
 #[derive(serde::Serialize, Debug)]
@@ -192,3 +466,69 @@ pub struct SnapResult {
     pub y: f32,
     pub guides: Vec<SnapGuide>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn objects_only() -> SnapConfig {
+        SnapConfig { spacing: false, ..SnapConfig::default() }
+    }
+
+    #[test]
+    fn test_edge_snap_carries_distance_label() {
+        let others = [Rect::from_xywh(0.0, 0.0, 100.0, 50.0)];
+        let result = compute_snap(Rect::from_xywh(3.0, 80.0, 40.0, 40.0), &others, None, &objects_only());
+        assert_eq!(result.x, 0.0);
+        assert_eq!(result.y, 80.0);
+        let g = &result.guides[0];
+        assert_eq!((g.kind.as_str(), g.orientation.as_str(), g.value), ("edge", "vertical", 0.0));
+        assert_eq!(g.label, "30"); // 80 - 50 below the other box
+    }
+
+    #[test]
+    fn test_equal_spacing_between_and_repeated() {
+        let config = SnapConfig { objects: false, ..SnapConfig::default() };
+
+        // Centered between two neighbours: gaps of 30 on both sides
+        let others = [Rect::from_xywh(0.0, 0.0, 50.0, 50.0), Rect::from_xywh(150.0, 0.0, 50.0, 50.0)];
+        let result = compute_snap(Rect::from_xywh(78.0, 0.0, 40.0, 50.0), &others, None, &config);
+        assert_eq!(result.x, 80.0);
+        assert_eq!(result.guides.len(), 2);
+        assert!(result.guides.iter().all(|g| g.kind == "spacing" && g.label == "30"));
+
+        // Repeating the 100px gap between A and B after B
+        let others = [Rect::from_xywh(0.0, 0.0, 50.0, 50.0), Rect::from_xywh(150.0, 0.0, 50.0, 50.0)];
+        let result = compute_snap(Rect::from_xywh(297.0, 10.0, 50.0, 30.0), &others, None, &config);
+        assert_eq!(result.x, 300.0);
+        assert!(result.guides.iter().any(|g| g.label == "100" && g.start == 200.0 && g.end == 300.0));
+    }
+
+    #[test]
+    fn test_grid_columns_baseline_and_pixel_rounding() {
+        let none = SnapConfig { objects: false, spacing: false, ..SnapConfig::default() };
+
+        // Either edge may land on the grid: the right edge 23 -> 24 is closest
+        let grid = SnapConfig { grid_size: Some(8.0), ..none.clone() };
+        let result = compute_snap(Rect::from_xywh(13.0, 30.0, 10.0, 10.0), &[], None, &grid);
+        assert_eq!((result.x, result.y), (14.0, 30.0));
+        assert!(result.guides.iter().all(|g| g.kind == "grid"));
+
+        // Columns are 90 wide: 40..130 | 150..240 | ...; baseline lines start at the parent top (10)
+        let layout = SnapConfig {
+            columns: Some(ColumnGrid { count: 4, gutter: 20.0, margin: 40.0 }),
+            baseline: Some(6.0),
+            ..none.clone()
+        };
+        let parent = Rect::from_xywh(0.0, 10.0, 500.0, 500.0);
+        let result = compute_snap(Rect::from_xywh(148.3, 27.4, 60.0, 20.0), &[], Some(parent), &layout);
+        assert_eq!((result.x, result.y), (150.0, 28.0));
+        assert!(result.guides.iter().any(|g| g.kind == "column"));
+        assert!(result.guides.iter().any(|g| g.kind == "baseline"));
+
+        // Without a snap, the position is still rounded to whole pixels
+        let result = compute_snap(Rect::from_xywh(10.4, 20.6, 5.0, 5.0), &[], None, &none);
+        assert_eq!((result.x, result.y), (10.0, 21.0));
+        assert!(result.guides.is_empty());
+    }
+}
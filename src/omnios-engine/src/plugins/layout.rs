@@ -1,5 +1,5 @@
 use crate::ProjectState;
-use crate::plugins::spatial_index::SPATIAL_INDEX;
+use crate::plugins::spatial_index::{ElementBounds, SPATIAL_INDEX};
 use crate::TAFFY;
 use crate::DesignerElement;
 use std::collections::HashMap;
@@ -61,8 +61,7 @@ pub fn compute_project_layout(state: &ProjectState) -> Result<(), String> {
     }
 
     // 4. Update Spatial Index
-    let mut index = SPATIAL_INDEX.lock().unwrap();
-    index.clear();
+    let mut items = Vec::with_capacity(node_map.len());
 
     for (id, node) in &node_map {
         let layout = taffy.layout(*node).map_err(|e| e.to_string())?;
@@ -76,15 +75,12 @@ pub fn compute_project_layout(state: &ProjectState) -> Result<(), String> {
         
         let (x, y) = get_absolute_position(&taffy, *node, &node_map);
         
-        index.insert_or_update(
-            id.clone(),
-            x,
-            y,
-            layout.size.width,
-            layout.size.height
-        );
+        items.push(ElementBounds::new(id.clone(), x, y, layout.size.width, layout.size.height));
     }
 
+    // Full recompute: one bulk load instead of n insertions
+    SPATIAL_INDEX.lock().unwrap().rebuild(items);
+
     Ok(())
 }
 
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use crate::sdk::{OmniosPlugin, PluginContext};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -29,103 +30,1307 @@ pub struct UnifiedBlueprint {
 }
 
 use crate::core::secrets::SecretStore;
+use crate::plugins::debugger::{DebugCommand, DebugState, Debugger, PausedRun};
+use crate::plugins::trace::{Trace, VariableSnapshot};
+use crate::plugins::effects::{Effect, EffectHandler, EffectOutcome, QueuedEffect};
+use crate::plugins::executions::{ExecutionRecord, ExecutionTable, Frame, Iteration, Scope, WaitFor, Wake};
+use crate::plugins::expression;
+use crate::plugins::metering::{value_size, ExecutionError, ExecutionLimits, Meter};
+use crate::plugins::nodes::{Flow, NodeContext, NodeError, NodeRegistry, ERROR_PORT};
+use crate::plugins::variables::{VariableScope, VariableStore};
+
+/// A node that failed (bad expression, type error, failed effect, ...). Handlers on an `error`
+/// port or a `try` node's `catch` see it as the `error` variable.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LogicError {
+    pub blueprint_id: String,
+    pub node_id: String,
+    pub code: String,
+    pub message: String,
+}
+
+/// Identifiers visible to expressions. Bare names resolve through the variable scopes
+/// (execution, blueprint, global); `payload`, `vars` and `globals` name a source explicitly.
+pub(crate) struct KernelScope<'a> {
+    variables: &'a VariableStore,
+    blueprint: &'a UnifiedBlueprint,
+    payload: &'a serde_json::Value,
+}
+
+impl expression::Scope for KernelScope<'_> {
+    fn lookup(&self, name: &str) -> Option<serde_json::Value> {
+        match name {
+            "payload" => Some(self.payload.clone()),
+            "vars" => Some(serde_json::to_value(&self.variables.execution).unwrap_or_default()),
+            "globals" => Some(serde_json::to_value(&self.variables.globals).unwrap_or_default()),
+            _ => self.variables.get(self.blueprint, name).cloned(),
+        }
+    }
+}
 
 pub struct LogicKernel {
-    pub blueprints: HashMap<String, UnifiedBlueprint>,
-    pub runtime_variables: HashMap<String, serde_json::Value>,
+    pub blueprints: HashMap<String, Arc<UnifiedBlueprint>>,
+    pub variables: VariableStore,
     pub secrets: SecretStore,
-    pub max_steps: usize,
-    pub step_count: usize,
+    pub limits: ExecutionLimits,
+    pub errors: Vec<LogicError>, // Unhandled failures from the last `execute` and the resumptions since
+    pub error: Option<ExecutionError>, // Why the last run (execute or resumption) was aborted
+    pub effects: Vec<QueuedEffect>, // Queued until the host drains them
+    pub executions: ExecutionTable, // Flows suspended at wait/delay/await_effect nodes
+    pub trace: Trace, // Nodes run by the last `execute` and the resumptions since
+    pub debugger: Debugger, // Halts runs at breakpoints; step/continue commands carry them on
+    pub nodes: NodeRegistry, // Node type -> handler (built-ins plus plugin nodes)
+    meter: Meter,
+    abort: Option<ExecutionError>, // Set when a limit is hit; stops every queue of the run
+    effect_seq: u64,
+    last_effect: Option<String>,            // Most recent effect of the running flow
+    node_effects: HashMap<String, String>,  // Effect node id -> its latest effect id
+    frames: Vec<Frame>,         // Work stack of the run: root flow, then loop/try/call scopes
+    suspended: Option<ExecutionRecord>, // Set by a waiting node; `drive` files it once the node returns
+    caught: Option<LogicError>, // Failure unwinding to the innermost try or call (Control::Throw)
+    control: Option<Control>,
+    return_value: Option<serde_json::Value>,
+}
+
+/// Pending break/continue/return/throw. While set, no further nodes run: frames are popped
+/// until the loop (break/continue), call (return) or try/call (throw) that consumes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Control {
+    Break,
+    Continue,
+    Return,
+    Throw,
 }
 
+/// How a frame ends. Decides the port its opening node continues on.
+enum Exit {
+    Done,               // Ran out of work (or `break`): the node's default port
+    Caught(LogicError), // A failure reached this try or call
+    Failed(NodeError),  // The opening node itself failed (while condition, result too large)
+    Unwound,            // A return or throw passes through: the branch stops
+}
+
+/// Upper bound on effect/timer rounds in `run_until_idle`, so a looping flow can't hang the CLI or server.
+const MAX_IDLE_ROUNDS: usize = 1000;
+
+/// Ports `default` traversal skips: they fire only for their own purpose.
+const RESERVED_PORTS: [&str; 4] = ["timeout", "body", "catch", ERROR_PORT];
+
 impl LogicKernel {
     pub fn new() -> Self {
         Self {
             blueprints: HashMap::new(),
-            runtime_variables: HashMap::new(),
+            variables: VariableStore::default(),
             secrets: SecretStore::new(),
-            max_steps: 1000, // Default Safety Limit
-            step_count: 0,
+            limits: ExecutionLimits::default(),
+            errors: Vec::new(),
+            error: None,
+            effects: Vec::new(),
+            executions: ExecutionTable::default(),
+            trace: Trace::default(),
+            debugger: Debugger::default(),
+            nodes: NodeRegistry::with_builtins(),
+            meter: Meter::default(),
+            abort: None,
+            effect_seq: 0,
+            last_effect: None,
+            node_effects: HashMap::new(),
+            frames: Vec::new(),
+            suspended: None,
+            caught: None,
+            control: None,
+            return_value: None,
         }
     }
 
     pub fn register_blueprint(&mut self, bp: UnifiedBlueprint) {
-        self.blueprints.insert(bp.id.clone(), bp);
+        self.blueprints.insert(bp.id.clone(), Arc::new(bp));
+    }
+
+    /// Effects queued since the last drain, in execution order.
+    pub fn drain_effects(&mut self) -> Vec<QueuedEffect> {
+        std::mem::take(&mut self.effects)
     }
 
-    pub fn execute(&mut self, blueprint_id: &str, trigger_type: &str, payload: &serde_json::Value) {
-        self.step_count = 0; // Reset Gas Meter
-        
-        if let Some(bp) = self.blueprints.get(blueprint_id).cloned() {
-            // Find start nodes
-            let start_nodes: Vec<&UnifiedNode> = bp.nodes.values()
-                .filter(|n| n.r#type == trigger_type)
-                .collect();
+    /// Runs every `trigger_type` node of the blueprint. Node failures are collected in `errors`;
+    /// hitting an execution limit aborts the whole run and is returned.
+    pub fn execute(&mut self, blueprint_id: &str, trigger_type: &str, payload: &serde_json::Value) -> Result<(), ExecutionError> {
+        self.errors.clear();
+        self.last_effect = None;
+        self.begin_run();
+        self.variables.begin_execution();
+        self.trace.clear();
 
-            for node in start_nodes {
-                self.execute_node(&bp, node, payload);
+        let bp = match self.blueprints.get(blueprint_id).cloned() {
+            Some(bp) => bp,
+            None => {
+                self.abort = Some(ExecutionError::UnknownBlueprint { blueprint_id: blueprint_id.to_string() });
+                return self.finish_run();
             }
+        };
+        let start: Vec<String> = bp.nodes.values()
+            .filter(|n| n.r#type == trigger_type)
+            .map(|n| n.id.clone())
+            .collect();
+        self.frames = vec![Frame::root(blueprint_id, payload.clone(), start)];
+        self.drive();
+        self.finish_run()
+    }
+
+    /// Continues a suspended execution with the host's result (stored in the node's `resultVar`).
+    pub fn resume(&mut self, execution_id: &str, result: serde_json::Value) -> Result<(), ExecutionError> {
+        let record = self.executions.take(execution_id)
+            .ok_or_else(|| ExecutionError::UnknownExecution { execution_id: execution_id.to_string() })?;
+        self.continue_execution(record, result, Wake::Host)
+    }
+
+    /// Resumes the execution awaiting `effect_id`. Returns false if nothing was waiting on it.
+    pub fn resume_effect(&mut self, effect_id: &str, result: serde_json::Value) -> Result<bool, ExecutionError> {
+        match self.executions.waiting_on(effect_id) {
+            Some(id) => self.resume(&id, result).map(|_| true),
+            None => Ok(false),
         }
     }
 
-    fn execute_node(&mut self, bp: &UnifiedBlueprint, node: &UnifiedNode, payload: &serde_json::Value) {
-        if self.step_count >= self.max_steps {
-            log::error!("[LogicKernel] Gas Limit Exceeded ({} steps). Terminating execution loop.", self.max_steps);
-            return;
+    /// Moves the logic clock forward and resumes every execution whose deadline passed.
+    pub fn advance_clock(&mut self, dt: f64) -> usize {
+        let due = self.executions.advance(dt);
+        let count = due.len();
+        for record in due {
+            if let Err(e) = self.continue_execution(record, serde_json::Value::Null, Wake::Deadline) {
+                log::error!("[LogicKernel] {}", e);
+            }
         }
-        self.step_count += 1;
+        count
+    }
 
-        log::info!("[LogicKernel] Executing: {} ({}) [Step: {}]", node.id, node.r#type, self.step_count);
+    /// Headless hosts (CLI, server): performs queued effects, feeds results to awaiting executions
+    /// and fast-forwards the clock through pending timers until nothing is left to do.
+    pub fn run_until_idle(&mut self, handler: &mut dyn EffectHandler) -> (Vec<QueuedEffect>, Vec<EffectOutcome>) {
+        let mut all_effects = Vec::new();
+        let mut all_outcomes = Vec::new();
 
-        let mut next_port = "default".to_string();
+        for _ in 0..MAX_IDLE_ROUNDS {
+            let effects = self.drain_effects();
+            if !effects.is_empty() {
+                let outcomes = handler.handle_all(&effects);
+                for outcome in &outcomes {
+                    let result = match &outcome.error {
+                        Some(e) => serde_json::json!({ "error": e }),
+                        None => outcome.result.clone(),
+                    };
+                    match self.resume_effect(&outcome.effect_id, result) {
+                        // Nothing awaited it, so nothing could handle the failure
+                        Ok(false) => if let Some(message) = &outcome.error {
+                            if let Some(effect) = effects.iter().find(|e| e.id == outcome.effect_id) {
+                                self.errors.push(LogicError {
+                                    blueprint_id: effect.blueprint_id.clone(),
+                                    node_id: effect.node_id.clone(),
+                                    code: "effect_failed".to_string(),
+                                    message: message.clone(),
+                                });
+                            }
+                        },
+                        Ok(true) => {}
+                        Err(e) => log::error!("[LogicKernel] {}", e),
+                    }
+                }
+                all_effects.extend(effects);
+                all_outcomes.extend(outcomes);
+                continue;
+            }
+            match self.executions.next_deadline() {
+                Some(deadline) => {
+                    self.executions.clock = self.executions.clock.max(deadline);
+                    self.advance_clock(0.0);
+                }
+                None => break,
+            }
+        }
+        (all_effects, all_outcomes)
+    }
+
+    fn continue_execution(&mut self, record: ExecutionRecord, result: serde_json::Value, wake: Wake) -> Result<(), ExecutionError> {
+        let stale = |message: String| ExecutionError::StaleExecution { execution_id: record.id.clone(), message };
+        let bp = self.blueprints.get(&record.blueprint_id).cloned()
+            .ok_or_else(|| stale(format!("blueprint '{}' is not registered", record.blueprint_id)))?;
+        let node = bp.nodes.get(&record.node_id).cloned()
+            .ok_or_else(|| stale(format!("node '{}' no longer exists", record.node_id)))?;
+        if let Some(frame) = record.frames.iter().find(|f| !self.blueprints.contains_key(&f.blueprint_id)) {
+            return Err(stale(format!("blueprint '{}' is not registered", frame.blueprint_id)));
+        }
+
+        self.last_effect = None;
+        self.begin_run();
+        self.variables.execution = record.variables;
+        self.frames = if record.frames.is_empty() {
+            vec![Frame::root(&record.blueprint_id, record.payload, Vec::new())]
+        } else {
+            record.frames
+        };
 
-        match node.r#type.as_str() {
-            "set_var" => {
-                if let Some(var_name) = node.data.get("varName").and_then(|v| v.as_str()) {
-                    let value = node.data.get("value").cloned().unwrap_or(serde_json::Value::Null);
-                    self.runtime_variables.insert(var_name.to_string(), value);
+        // An effect that failed (`{ "error": ... }`) fails the awaiting node: its `error` port, else
+        // the try or call it waited in (restored with the frames), else it's unhandled.
+        let failure = match (wake, &record.wait) {
+            (Wake::Host, WaitFor::Effect { .. }) => result.get("error").filter(|e| !e.is_null())
+                .map(|e| e.as_str().map(String::from).unwrap_or_else(|| e.to_string())),
+            _ => None,
+        };
+        let port = match failure {
+            Some(message) => self.fail(&bp, &node, NodeError::new("effect_failed", message)),
+            None => {
+                if let Some(var) = &record.result_var {
+                    if let Err(e) = self.assign(&bp, &node.id, var, result, None) {
+                        log::error!("[LogicKernel] {}", e);
+                        return self.finish_run();
+                    }
                 }
+                // An awaited effect that never answered continues on `timeout`
+                match (wake, &record.wait) {
+                    (Wake::Deadline, WaitFor::Effect { .. }) => Some("timeout".to_string()),
+                    _ => Some("default".to_string()),
+                }
+            }
+        };
+        self.continue_from(&bp, &node, port);
+        self.drive();
+        self.finish_run()
+    }
+
+    /// Carries the paused run on until `command` stops it again (or it finishes, suspends or aborts).
+    pub fn debug(&mut self, command: DebugCommand) -> DebugState {
+        if let Some(run) = self.debugger.resume(command) {
+            self.begin_run();
+            self.meter.spent = run.spent;
+            self.last_effect = run.last_effect;
+            self.variables.execution = run.variables;
+            self.frames = run.frames;
+            self.drive();
+            self.debugger.disarm();
+            let _ = self.finish_run();
+        }
+        self.debug_state()
+    }
+
+    /// Turning debugging off lets a paused run finish.
+    pub fn set_debugging(&mut self, enabled: bool) {
+        self.debugger.enabled = enabled;
+        if !enabled {
+            self.debug(DebugCommand::Continue);
+        }
+    }
+
+    pub fn debug_state(&self) -> DebugState {
+        match self.debugger.session() {
+            Some(run) => DebugState {
+                paused: true,
+                blueprint_id: Some(run.blueprint_id.clone()),
+                node_id: Some(run.node_id.clone()),
+                depth: run.depth,
+                variables: Some(VariableSnapshot {
+                    execution: run.variables.clone(),
+                    blueprint: self.variables.blueprint.get(&run.blueprint_id).cloned().unwrap_or_default(),
+                }),
             },
-            "condition" => {
-                // Simplified condition logic for parity
-                let left = node.data.get("left").unwrap_or(&serde_json::Value::Null);
-                let right = node.data.get("right").unwrap_or(&serde_json::Value::Null);
-                let op = node.data.get("operator").and_then(|v| v.as_str()).unwrap_or("==");
-
-                let result = match op {
-                    "==" => left == right,
-                    "!=" => left != right,
-                    _ => false,
+            None => DebugState { paused: false, blueprint_id: None, node_id: None, depth: 0, variables: None },
+        }
+    }
+
+    /// Fresh gas and time budget for an `execute` or a resumption.
+    fn begin_run(&mut self) {
+        self.meter = Meter::start();
+        self.abort = None;
+        self.frames.clear();
+        self.suspended = None;
+        self.caught = None;
+        self.control = None;
+        self.return_value = None;
+    }
+
+    fn finish_run(&mut self) -> Result<(), ExecutionError> {
+        self.error = self.abort.take();
+        match &self.error {
+            Some(e) => {
+                log::error!("[LogicKernel] Execution aborted: {}", e);
+                Err(e.clone())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Routes a node failure: to the node's `error` port if connected (returns that port), else to
+    /// the innermost `try` or call, else it's recorded as unhandled. The last two stop the branch.
+    fn fail(&mut self, bp: &UnifiedBlueprint, node: &UnifiedNode, error: NodeError) -> Option<String> {
+        let error = LogicError { blueprint_id: bp.id.clone(), node_id: node.id.clone(), code: error.code, message: error.message };
+        if bp.connections.iter().any(|c| c.from_id == node.id && c.port.as_deref() == Some(ERROR_PORT)) {
+            log::info!("[LogicKernel] {} ({}) failed, handled on its error port: {}", node.id, node.r#type, error.message);
+            let value = serde_json::to_value(&error).unwrap_or_default();
+            self.variables.set(bp, "error", value, Some(VariableScope::Execution));
+            return Some(ERROR_PORT.to_string());
+        }
+        if self.frames.iter().any(|f| matches!(f.scope, Scope::Try | Scope::Call { .. })) {
+            self.caught = Some(error);
+            self.control = Some(Control::Throw);
+            return None;
+        }
+        log::error!("[LogicKernel] {} ({}): {}", node.id, node.r#type, error.message);
+        self.errors.push(error);
+        None
+    }
+
+    /// Stops the run. Returns the message for the handler to bail out with.
+    fn abort_with(&mut self, error: ExecutionError) -> String {
+        let message = error.to_string();
+        self.abort = Some(error);
+        message
+    }
+
+    // --- Primitives for node handlers (see NodeContext) ---
+
+    pub(crate) fn scope<'a>(&'a self, bp: &'a UnifiedBlueprint, payload: &'a serde_json::Value) -> KernelScope<'a> {
+        KernelScope { variables: &self.variables, blueprint: bp, payload }
+    }
+
+    /// Writes a variable within the size limits. Too large a value aborts the run.
+    pub(crate) fn assign(&mut self, bp: &UnifiedBlueprint, node_id: &str, name: &str, value: serde_json::Value, scope: Option<VariableScope>) -> Result<(), String> {
+        let bytes = value_size(&value);
+        let too_large = |bytes, limit| ExecutionError::ValueTooLarge { name: name.to_string(), bytes, limit, node_id: node_id.to_string() };
+        if bytes > self.limits.max_value_bytes {
+            return Err(self.abort_with(too_large(bytes, self.limits.max_value_bytes)));
+        }
+        let scope = scope.or_else(|| self.variables.scope_of(bp, name)).unwrap_or(VariableScope::Execution);
+        if scope == VariableScope::Execution {
+            let total: usize = self.variables.execution.iter()
+                .filter(|(k, _)| k.as_str() != name)
+                .map(|(k, v)| k.len() + value_size(v))
+                .sum::<usize>() + name.len() + bytes;
+            if total > self.limits.max_scope_bytes {
+                return Err(self.abort_with(too_large(total, self.limits.max_scope_bytes)));
+            }
+        }
+        self.variables.set(bp, name, value, Some(scope));
+        Ok(())
+    }
+
+    /// Charges one loop iteration of `node_id` against the gas limit.
+    fn charge_iteration(&mut self, node_id: &str) -> Result<(), String> {
+        match self.meter.charge(&self.limits, self.limits.iteration_cost, node_id) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(self.abort_with(e)),
+        }
+    }
+
+    /// Loop frames above the innermost call: what `break`/`continue` and the loop depth limit see.
+    fn loop_depth(&self) -> usize {
+        self.frames.iter().rev()
+            .take_while(|f| !matches!(f.scope, Scope::Call { .. }))
+            .filter(|f| matches!(f.scope, Scope::Loop { .. }))
+            .count()
+    }
+
+    fn call_depth(&self) -> usize {
+        self.frames.iter().filter(|f| matches!(f.scope, Scope::Call { .. })).count()
+    }
+
+    pub(crate) fn in_loop(&self) -> bool {
+        self.loop_depth() > 0
+    }
+
+    pub(crate) fn signal(&mut self, control: Control) {
+        self.control = Some(control);
+    }
+
+    pub(crate) fn set_return(&mut self, value: serde_json::Value) {
+        self.return_value = Some(value);
+        self.control = Some(Control::Return);
+    }
+
+    pub(crate) fn queue_effect(&mut self, bp: &UnifiedBlueprint, node: &UnifiedNode, effect: Effect) -> String {
+        self.effect_seq += 1;
+        let id = format!("effect-{}", self.effect_seq);
+        self.last_effect = Some(id.clone());
+        self.node_effects.insert(node.id.clone(), id.clone());
+        self.effects.push(QueuedEffect { id: id.clone(), blueprint_id: bp.id.clone(), node_id: node.id.clone(), effect });
+        id
+    }
+
+    /// Latest effect queued by `node_id`, or by the running flow when None.
+    pub(crate) fn effect_of(&self, node_id: Option<&str>) -> Option<String> {
+        match node_id {
+            Some(source) => self.node_effects.get(source).cloned(),
+            None => self.last_effect.clone(),
+        }
+    }
+
+    /// Saves the flow at `node`. The record is filed (with the frames it waits in) by `park`
+    /// once the node returns.
+    pub(crate) fn suspend(&mut self, bp: &UnifiedBlueprint, node: &UnifiedNode, payload: &serde_json::Value, wait: WaitFor, after: Option<f64>) -> String {
+        let record = ExecutionRecord {
+            id: self.executions.next_id(),
+            blueprint_id: bp.id.clone(),
+            node_id: node.id.clone(),
+            payload: payload.clone(),
+            variables: self.variables.execution.clone(),
+            wait,
+            deadline: after.map(|secs| self.executions.clock + secs),
+            result_var: node.data.get("resultVar").or_else(|| node.data.get("outputVar")).and_then(|v| v.as_str()).map(String::from),
+            frames: Vec::new(),
+        };
+        let id = record.id.clone();
+        self.suspended = Some(record);
+        id
+    }
+
+    /// Files a suspension. Inside a loop, try or call every frame above the root goes with it,
+    /// branches queued there included: the scope carries on as a whole when the record resumes.
+    /// The root's other branches keep running now, in the scope they had outside any call.
+    fn park(&mut self, mut record: ExecutionRecord) {
+        if self.frames.len() > 1 {
+            let root = Frame::root(&self.frames[0].blueprint_id, self.frames[0].payload.clone(), Vec::new());
+            let mut nested: Vec<Frame> = self.frames.drain(1..).collect();
+            if let Some(Scope::Call { caller_variables }) = nested.iter().map(|f| &f.scope).find(|s| matches!(s, Scope::Call { .. })) {
+                self.variables.execution = caller_variables.clone();
+            }
+            self.close_open_events(&mut nested);
+            record.frames = std::iter::once(root).chain(nested).collect();
+        }
+        log::info!("[LogicKernel] Suspended {} at {} ({:?}, {} frames)", record.id, record.node_id, record.wait, record.frames.len());
+        self.executions.suspend(record);
+    }
+
+    /// Halts the run before `node` (put back in front of its queue) for the debugger.
+    fn pause(&mut self, bp: &UnifiedBlueprint, node: &UnifiedNode) {
+        let mut frames = std::mem::take(&mut self.frames);
+        let depth = frames.len() - 1;
+        frames[depth].queue.push_front(node.id.clone());
+        self.close_open_events(&mut frames);
+        self.debugger.pause(PausedRun {
+            blueprint_id: bp.id.clone(),
+            node_id: node.id.clone(),
+            depth,
+            frames,
+            variables: self.variables.execution.clone(),
+            spent: self.meter.spent,
+            last_effect: self.last_effect.clone(),
+        });
+    }
+
+    /// Trace events can't stay open across a suspension or pause (a new `execute` clears the
+    /// trace): the scope nodes' events end there, without a port.
+    fn close_open_events(&mut self, frames: &mut [Frame]) {
+        for frame in frames.iter_mut().rev() {
+            if let Some((seq, spent)) = frame.traced.take() {
+                self.trace.exit(seq, None, (self.meter.spent - spent) as usize, None);
+            }
+        }
+    }
+
+    /// Runs the work stack until it is empty, the run aborts or the debugger halts it. Nodes come
+    /// off the top frame's queue breadth-first, so parallel branches take turns on the shared gas;
+    /// loop bodies, try bodies and calls push a frame that finishes before the one below carries
+    /// on. Nothing nests on the Rust stack, so deep chains are fine and the stack itself can be
+    /// saved (`park`, `pause`).
+    fn drive(&mut self) {
+        loop {
+            if self.abort.is_some() {
+                self.drop_frames();
+            }
+            let top = match self.frames.last_mut() {
+                Some(top) => top,
+                None => break,
+            };
+            if self.control.is_some() {
+                self.unwind();
+                continue;
+            }
+            let node_id = match top.queue.pop_front() {
+                Some(node_id) => node_id,
+                None => {
+                    self.close_frame();
+                    continue;
+                }
+            };
+            let index = self.frames.len() - 1;
+            let bp = self.blueprints[&self.frames[index].blueprint_id].clone();
+            let node = match bp.nodes.get(&node_id) {
+                Some(node) => node,
+                None => continue,
+            };
+            if self.debugger.should_pause(&node.id, index) {
+                self.pause(&bp, node);
+                break;
+            }
+
+            let payload = std::mem::take(&mut self.frames[index].payload);
+            let (flow, traced) = self.execute_node(&bp, node, &payload);
+            self.frames[index].payload = payload;
+            if let Some(record) = self.suspended.take() {
+                self.park(record);
+            }
+            match flow {
+                Flow::Next(port) => self.continue_from(&bp, node, Some(port)),
+                Flow::Stop => {}
+                scope => self.open(&bp, node, scope, traced),
+            }
+        }
+    }
+
+    /// Queues what follows `node` on `port` in the top frame (where `node` ran).
+    fn continue_from(&mut self, bp: &UnifiedBlueprint, node: &UnifiedNode, port: Option<String>) {
+        let next = match port {
+            Some(port) => self.next_nodes(bp, node, &port),
+            None => return,
+        };
+        if let Some(frame) = self.frames.last_mut() {
+            frame.queue.extend(next);
+            if frame.queue.len() > self.limits.max_queue {
+                self.abort_with(ExecutionError::QueueOverflow { limit: self.limits.max_queue, node_id: node.id.clone() });
+            }
+        }
+    }
+
+    /// Pushes the frame for a loop, try or call node. A loop's queue fills as each iteration starts.
+    fn open(&mut self, bp: &UnifiedBlueprint, node: &UnifiedNode, flow: Flow, traced: Option<(usize, u64)>) {
+        let payload = self.frames.last().map(|f| f.payload.clone()).unwrap_or_default();
+        let (blueprint_id, payload, queue, scope) = match flow {
+            Flow::Loop(iteration) => {
+                if self.loop_depth() >= self.limits.max_loop_depth {
+                    self.abort_with(ExecutionError::DepthExceeded { limit: self.limits.max_loop_depth, node_id: node.id.clone(), what: "loop".to_string() });
+                    return;
+                }
+                (bp.id.clone(), payload, Vec::new(), Scope::Loop { iteration })
+            }
+            Flow::Try => (bp.id.clone(), payload, self.next_nodes(bp, node, "body"), Scope::Try),
+            Flow::Call(target, args) => {
+                let callee = match self.blueprints.get(&target) {
+                    Some(callee) => callee.clone(),
+                    None => {
+                        let port = self.fail(bp, node, NodeError::new("unknown_blueprint", format!("Blueprint '{}' is not registered", target)));
+                        self.close_event(bp, traced, port.clone());
+                        self.continue_from(bp, node, port);
+                        return;
+                    }
                 };
-                next_port = if result { "true".to_string() } else { "false".to_string() };
+                if self.call_depth() >= self.limits.max_call_depth {
+                    self.abort_with(ExecutionError::DepthExceeded { limit: self.limits.max_call_depth, node_id: node.id.clone(), what: "call".to_string() });
+                    return;
+                }
+                let entries = callee.nodes.values().filter(|n| n.r#type == "on_call").map(|n| n.id.clone()).collect();
+                let caller_variables = std::mem::replace(&mut self.variables.execution, args.clone().into_iter().collect());
+                (target, serde_json::Value::Object(args), entries, Scope::Call { caller_variables })
+            }
+            Flow::Next(_) | Flow::Stop => return,
+        };
+        self.frames.push(Frame { blueprint_id, node_id: Some(node.id.clone()), payload, queue: queue.into(), scope, traced });
+    }
+
+    /// The top frame has no work left: a loop starts its next iteration, anything else is done.
+    fn close_frame(&mut self) {
+        let exit = match self.frames.last().map(|f| &f.scope) {
+            Some(Scope::Loop { .. }) => match self.iterate() {
+                Ok(true) => return,
+                Ok(false) => Exit::Done,
+                // An aborted run is reported once, as its ExecutionError
+                Err(_) if self.abort.is_some() => return,
+                Err(error) => Exit::Failed(error),
             },
-            _ => {
-                // Actions that require UI side-effects (navigate, alert, etc.)
-                // are emitted as triggers back to the bridge.
-                log::info!("[LogicKernel] Action {} requires UI side-effects.", node.r#type);
+            _ => Exit::Done,
+        };
+        if let Some(frame) = self.frames.pop() {
+            self.leave(frame, exit);
+        }
+    }
+
+    /// Starts the top loop frame's next iteration. False when the loop is over.
+    fn iterate(&mut self) -> Result<bool, NodeError> {
+        let index = self.frames.len() - 1;
+        let bp = self.blueprints[&self.frames[index].blueprint_id].clone();
+        let node = match self.frames[index].node_id.as_ref().and_then(|id| bp.nodes.get(id)) {
+            Some(node) => node,
+            None => return Ok(false),
+        };
+        let assignments = match &mut self.frames[index].scope {
+            Scope::Loop { iteration: Iteration::Items { items, next, item_var, index_var } } => {
+                if *next >= items.len() { return Ok(false); }
+                let item = std::mem::take(&mut items[*next]);
+                *next += 1;
+                vec![(item_var.clone(), item), (index_var.clone(), serde_json::json!(*next - 1))]
             }
+            Scope::Loop { iteration: Iteration::While { condition } } => {
+                let condition = condition.clone();
+                let scope = self.scope(&bp, &self.frames[index].payload);
+                let value = expression::eval_str(&condition, &scope).map_err(|e| NodeError::new("invalid_expression", e.to_string()))?;
+                if !expression::truthy(&value) { return Ok(false); }
+                Vec::new()
+            }
+            _ => return Ok(false),
+        };
+        self.charge_iteration(&node.id)?;
+        for (name, value) in assignments {
+            self.assign(&bp, &node.id, &name, value, Some(VariableScope::Execution))?;
         }
+        let body = self.next_nodes(&bp, node, "body");
+        self.frames[index].queue.extend(body);
+        Ok(true)
+    }
 
-        // Traverse to next nodes
-        let next_connections: Vec<&UnifiedConnection> = bp.connections.iter()
-            .filter(|c| {
-                if next_port != "default" {
-                    c.from_id == node.id && c.port.as_ref() == Some(&next_port)
-                } else {
-                    c.from_id == node.id
+    /// Handles a pending break/continue/return/throw at the top frame: consumes it there, or pops
+    /// the frame and lets the next one down have a go.
+    fn unwind(&mut self) {
+        let (control, frame) = match (self.control, self.frames.last_mut()) {
+            (Some(control), Some(frame)) => (control, frame),
+            _ => return,
+        };
+        let consumed = matches!(
+            (control, &frame.scope),
+            (Control::Break | Control::Continue, Scope::Loop { .. }) | (Control::Return, Scope::Call { .. }) | (Control::Throw, Scope::Try | Scope::Call { .. })
+        );
+        if consumed && control == Control::Continue {
+            // The rest of this iteration is skipped; `close_frame` starts the next one
+            frame.queue.clear();
+            self.control = None;
+            return;
+        }
+        let exit = match (consumed, control) {
+            (false, _) => Exit::Unwound,
+            (true, Control::Throw) => self.caught.take().map(Exit::Caught).unwrap_or(Exit::Done),
+            (true, _) => Exit::Done,
+        };
+        if consumed {
+            self.control = None;
+        }
+        if let Some(frame) = self.frames.pop() {
+            self.leave(frame, exit);
+        }
+    }
+
+    /// A popped frame is finished: the caller's scope comes back, and the node that opened the
+    /// frame continues on the port the exit decides (in the frame below).
+    fn leave(&mut self, frame: Frame, exit: Exit) {
+        let node_id = match frame.node_id {
+            Some(node_id) => node_id,
+            None => return,
+        };
+        let bp = match self.frames.last() {
+            Some(parent) => self.blueprints[&parent.blueprint_id].clone(),
+            None => return,
+        };
+        let node = match bp.nodes.get(&node_id) {
+            Some(node) => node,
+            None => return,
+        };
+        let is_call = matches!(frame.scope, Scope::Call { .. });
+        if let Scope::Call { caller_variables } = frame.scope {
+            self.variables.execution = caller_variables;
+        }
+        let port = match exit {
+            Exit::Unwound => None,
+            Exit::Failed(error) => self.fail(&bp, node, error),
+            Exit::Caught(error) if is_call => {
+                let message = format!("{}/{}: {}", error.blueprint_id, error.node_id, error.message);
+                self.fail(&bp, node, NodeError::new(&error.code, message))
+            }
+            Exit::Caught(error) => {
+                let value = serde_json::to_value(&error).unwrap_or_default();
+                self.assign(&bp, &node.id, "error", value, Some(VariableScope::Execution)).ok().map(|_| "catch".to_string())
+            }
+            Exit::Done if is_call => {
+                let value = self.return_value.take().unwrap_or(serde_json::Value::Null);
+                match node.data.get("resultVar").and_then(|v| v.as_str()) {
+                    Some(var) => self.assign(&bp, &node.id, var, value, None).ok().map(|_| "default".to_string()),
+                    None => Some("default".to_string()),
                 }
-            })
-            .collect();
+            }
+            Exit::Done => Some("default".to_string()),
+        };
+        self.close_event(&bp, frame.traced, port.clone());
+        self.continue_from(&bp, node, port);
+    }
+
+    /// Closes the trace event a loop/try/call node left open while its frame ran.
+    fn close_event(&mut self, bp: &UnifiedBlueprint, traced: Option<(usize, u64)>, port: Option<String>) {
+        if let Some((seq, spent)) = traced {
+            let after = self.snapshot(bp);
+            self.trace.exit(seq, port, (self.meter.spent - spent) as usize, after);
+        }
+    }
+
+    /// An aborted run stops everywhere. The outermost caller's scope is kept, as it would be
+    /// after the calls returned.
+    fn drop_frames(&mut self) {
+        let outermost = self.frames.iter_mut().find_map(|f| match &mut f.scope {
+            Scope::Call { caller_variables } => Some(std::mem::take(caller_variables)),
+            _ => None,
+        });
+        if let Some(variables) = outermost {
+            self.variables.execution = variables;
+        }
+        self.frames.clear();
+    }
+
+    /// Charges, traces and runs one node. A node opening a scope leaves its trace event open (and
+    /// returns it) until the scope's frame is done.
+    fn execute_node(&mut self, bp: &UnifiedBlueprint, node: &UnifiedNode, payload: &serde_json::Value) -> (Flow, Option<(usize, u64)>) {
+        let spent = self.meter.spent;
+        if let Err(e) = self.meter.charge(&self.limits, self.limits.cost(&node.r#type), &node.id) {
+            self.abort_with(e);
+            return (Flow::Stop, None);
+        }
+
+        log::info!("[LogicKernel] Executing: {} ({}) [Gas: {}]", node.id, node.r#type, self.meter.spent);
 
-        for conn in next_connections {
-            if let Some(next_node) = bp.nodes.get(&conn.to_id) {
-                self.execute_node(bp, next_node, payload);
+        let step = self.meter.spent as usize;
+        let traced = if self.trace.accepts() {
+            let before = self.snapshot(bp);
+            Some((self.trace.enter(&bp.id, &node.id, &node.r#type, self.frames.len() - 1, step, before), spent))
+        } else {
+            None
+        };
+        let flow = self.run_node(bp, node, payload);
+        match flow {
+            Flow::Loop(_) | Flow::Try | Flow::Call(..) => (flow, traced),
+            Flow::Next(ref port) => {
+                self.close_event(bp, traced, Some(port.clone()));
+                (flow, None)
+            }
+            Flow::Stop => {
+                self.close_event(bp, traced, None);
+                (flow, None)
             }
         }
     }
+
+    fn snapshot(&mut self, bp: &UnifiedBlueprint) -> Option<VariableSnapshot> {
+        self.trace.snapshot(&self.variables.execution, self.variables.blueprint.get(&bp.id))
+    }
+
+    /// Runs one node through its handler. Returns where to go next; a failure is routed by `fail`.
+    fn run_node(&mut self, bp: &UnifiedBlueprint, node: &UnifiedNode, payload: &serde_json::Value) -> Flow {
+        let handler = match self.nodes.get(&node.r#type) {
+            Some(handler) => handler,
+            None => {
+                // Triggers and unknown types pass straight through
+                log::info!("[LogicKernel] No handler for node type {}.", node.r#type);
+                return Flow::next();
+            }
+        };
+        let mut ctx = NodeContext { kernel: self, blueprint: bp, node, payload };
+        match handler.execute(&mut ctx) {
+            Ok(flow) => flow,
+            // An aborted run is reported once, as its ExecutionError
+            Err(_) if self.abort.is_some() => Flow::Stop,
+            Err(error) => match self.fail(bp, node, error) {
+                Some(port) => Flow::Next(port),
+                None => Flow::Stop,
+            },
+        }
+    }
+
+    /// Nodes connected to `node`'s `port`. `default` follows every connection except the
+    /// reserved ports: `timeout` (an awaited effect ran out of time) and `body` (loop iterations).
+    fn next_nodes(&self, bp: &UnifiedBlueprint, node: &UnifiedNode, port: &str) -> Vec<String> {
+        bp.connections.iter()
+            .filter(|c| {
+                if port != "default" {
+                    c.from_id == node.id && c.port.as_deref() == Some(port)
+                } else {
+                    c.from_id == node.id && !c.port.as_deref().is_some_and(|p| RESERVED_PORTS.contains(&p))
+                }
+            })
+            .map(|c| c.to_id.clone())
+            .collect()
+    }
 }
 
 impl OmniosPlugin for LogicKernel {
     fn name(&self) -> &str { "LogicKernel" }
     fn on_register(&mut self, _context: &mut PluginContext) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::trace::{TraceEvent, MAX_SNAPSHOT_BYTES};
+    use serde_json::json;
+
+    fn blueprint(nodes: serde_json::Value, connections: serde_json::Value) -> UnifiedBlueprint {
+        serde_json::from_value(json!({ "id": "bp", "nodes": nodes, "connections": connections, "variables": { "threshold": 100, "count": 0 } })).unwrap()
+    }
+
+    #[test]
+    fn test_expressions_drive_conditions_and_assignments() {
+        let bp = blueprint(
+            json!({
+                "start": { "id": "start", "type": "on_click", "data": {} },
+                "check": { "id": "check", "type": "condition", "data": { "expression": "payload.total > threshold && user.role == 'vip'" } },
+                "inc": { "id": "inc", "type": "set_var", "data": { "varName": "count", "expression": "(count ?? 0) + 1" } },
+                "bad": { "id": "bad", "type": "set_var", "data": { "varName": "oops", "expression": "payload.total +" } }
+            }),
+            json!([
+                { "id": "c1", "fromId": "start", "toId": "check" },
+                { "id": "c2", "fromId": "check", "toId": "inc", "port": "true" },
+                { "id": "c3", "fromId": "check", "toId": "bad", "port": "false" }
+            ]),
+        );
+        let mut kernel = LogicKernel::new();
+        kernel.variables.globals.insert("user".to_string(), json!({ "role": "vip" }));
+        kernel.register_blueprint(bp);
+
+        kernel.execute("bp", "on_click", &json!({ "total": 150 })).unwrap();
+        kernel.execute("bp", "on_click", &json!({ "total": 150 })).unwrap();
+        assert_eq!(kernel.variables.blueprint["bp"].get("count"), Some(&json!(2)));
+        assert!(kernel.errors.is_empty());
+
+        kernel.execute("bp", "on_click", &json!({ "total": 50 })).unwrap();
+        assert_eq!(kernel.errors.len(), 1);
+        assert_eq!(kernel.errors[0].node_id, "bad");
+        assert!(kernel.variables.get(&kernel.blueprints["bp"], "oops").is_none());
+    }
+
+    #[test]
+    fn test_effect_nodes_queue_resolved_effects() {
+        let bp = blueprint(
+            json!({
+                "start": { "id": "start", "type": "on_click", "data": {} },
+                "go": { "id": "go", "type": "navigate", "data": { "url": "/orders/{{ payload.orderId }}" } },
+                "bad": { "id": "bad", "type": "api_request", "data": {} }
+            }),
+            json!([
+                { "id": "c1", "fromId": "start", "toId": "go" },
+                { "id": "c2", "fromId": "start", "toId": "bad" }
+            ]),
+        );
+        let mut kernel = LogicKernel::new();
+        kernel.register_blueprint(bp);
+        kernel.execute("bp", "on_click", &json!({ "orderId": 42 })).unwrap();
+
+        let queued = kernel.drain_effects();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].node_id, "go");
+        assert_eq!(queued[0].effect, Effect::Navigate { url: "/orders/42".to_string(), new_tab: false });
+        assert_eq!(kernel.errors.len(), 1);
+        assert!(kernel.drain_effects().is_empty());
+    }
+
+    #[test]
+    fn test_loops_and_blueprint_calls() {
+        let main = blueprint(
+            json!({
+                "start": { "id": "start", "type": "on_click", "data": {} },
+                "each": { "id": "each", "type": "for_each", "data": { "items": "payload.prices" } },
+                "skip": { "id": "skip", "type": "condition", "data": { "expression": "item < 0" } },
+                "next": { "id": "next", "type": "continue", "data": {} },
+                "stop": { "id": "stop", "type": "condition", "data": { "expression": "index >= 3" } },
+                "out": { "id": "out", "type": "break", "data": {} },
+                "call": { "id": "call", "type": "call_blueprint", "data": { "blueprintId": "tax", "args": { "amount": "{{ item }}" }, "resultVar": "taxed" } },
+                "add": { "id": "add", "type": "set_var", "data": { "varName": "count", "expression": "count + taxed" } },
+                "after": { "id": "after", "type": "set_var", "data": { "varName": "threshold", "expression": "index" } },
+                "spin": { "id": "spin", "type": "while", "data": { "condition": "true" } }
+            }),
+            json!([
+                { "id": "c1", "fromId": "start", "toId": "each" },
+                { "id": "c2", "fromId": "each", "toId": "skip", "port": "body" },
+                { "id": "c3", "fromId": "skip", "toId": "next", "port": "true" },
+                { "id": "c4", "fromId": "skip", "toId": "stop", "port": "false" },
+                { "id": "c5", "fromId": "stop", "toId": "out", "port": "true" },
+                { "id": "c6", "fromId": "stop", "toId": "call", "port": "false" },
+                { "id": "c7", "fromId": "call", "toId": "add" },
+                { "id": "c8", "fromId": "each", "toId": "after", "port": "done" },
+                { "id": "c9", "fromId": "after", "toId": "spin" }
+            ]),
+        );
+        let tax: UnifiedBlueprint = serde_json::from_value(json!({
+            "id": "tax", "variables": {}, "connections": [{ "id": "t1", "fromId": "entry", "toId": "ret" }],
+            "nodes": {
+                "entry": { "id": "entry", "type": "on_call", "data": {} },
+                "ret": { "id": "ret", "type": "return", "data": { "expression": "amount * 2" } }
+            }
+        })).unwrap();
+        let mut kernel = LogicKernel::new();
+        kernel.register_blueprint(main);
+        kernel.register_blueprint(tax);
+        let result = kernel.execute("bp", "on_click", &json!({ "prices": [1, -5, 2, 10, 100] }));
+
+        // -5 skipped, loop broken at index 3: (1 + 2) * 2
+        assert_eq!(kernel.variables.blueprint["bp"].get("count"), Some(&json!(6)));
+        assert_eq!(kernel.variables.blueprint["bp"].get("threshold"), Some(&json!(3)));
+        // The callee's `amount` never leaks into the caller's scope
+        assert!(kernel.variables.execution.get("amount").is_none());
+        // The endless while loop is stopped by the gas limit
+        assert_eq!(result, Err(ExecutionError::GasExhausted { limit: 1000, node_id: "spin".to_string() }));
+        assert!(kernel.errors.is_empty());
+
+        let recursive: UnifiedBlueprint = serde_json::from_value(json!({
+            "id": "rec", "variables": {}, "connections": [{ "id": "r1", "fromId": "entry", "toId": "again" }],
+            "nodes": {
+                "entry": { "id": "entry", "type": "on_call", "data": {} },
+                "again": { "id": "again", "type": "call_blueprint", "data": { "blueprintId": "rec" } }
+            }
+        })).unwrap();
+        kernel.register_blueprint(recursive);
+        let result = kernel.execute("rec", "on_call", &json!({}));
+        assert!(matches!(result, Err(ExecutionError::DepthExceeded { limit: 32, ref what, .. }) if what == "call"));
+        assert_eq!(kernel.error, result.err());
+    }
+
+    #[test]
+    fn test_trace_and_debugger() {
+        use crate::plugins::debugger::DebugCommand;
+
+        let bp = blueprint(
+            json!({
+                "start": { "id": "start", "type": "on_click", "data": {} },
+                "each": { "id": "each", "type": "for_each", "data": { "items": "[10, 20]" } },
+                "add": { "id": "add", "type": "set_var", "data": { "varName": "count", "expression": "count + item" } },
+                "end": { "id": "end", "type": "set_var", "data": { "varName": "threshold", "expression": "count" } }
+            }),
+            json!([
+                { "id": "c1", "fromId": "start", "toId": "each" },
+                { "id": "c2", "fromId": "each", "toId": "add", "port": "body" },
+                { "id": "c3", "fromId": "each", "toId": "end", "port": "done" }
+            ]),
+        );
+        let mut kernel = LogicKernel::new();
+        kernel.register_blueprint(bp);
+        kernel.execute("bp", "on_click", &json!({})).unwrap();
+
+        let visited: Vec<(&str, usize, Option<&str>)> = kernel.trace.events.iter()
+            .map(|e| (e.node_id.as_str(), e.depth, e.port.as_deref()))
+            .collect();
+        assert_eq!(visited, vec![
+            ("start", 0, Some("default")),
+            ("each", 0, Some("default")),
+            ("add", 1, Some("default")),
+            ("add", 1, Some("default")),
+            ("end", 0, Some("default")),
+        ]);
+        let adds: Vec<&TraceEvent> = kernel.trace.events.iter().filter(|e| e.node_id == "add").collect();
+        assert_eq!(adds[1].before.as_ref().unwrap().blueprint.get("count"), Some(&json!(10)));
+        assert_eq!(adds[1].after.as_ref().unwrap().blueprint.get("count"), Some(&json!(30)));
+        assert_eq!(kernel.trace.events[1].steps, 5); // The loop node, two iterations and their body nodes
+
+        // Every event carries its variables until the trace's snapshot budget is spent
+        assert!(kernel.trace.events.iter().all(|e| e.before.is_some() && e.after.is_some()));
+        let blob = HashMap::from([("blob".to_string(), json!("x".repeat(MAX_SNAPSHOT_BYTES / 2)))]);
+        assert!(kernel.trace.snapshot(&blob, None).is_some());
+        assert!(kernel.trace.snapshot(&blob, None).is_none());
+        assert!(kernel.trace.snapshots_truncated);
+
+        // Halted before the breakpoint: nothing from `each` on has run yet
+        kernel.debugger.enabled = true;
+        kernel.debugger.breakpoints.insert("each".to_string());
+        kernel.execute("bp", "on_click", &json!({})).unwrap();
+        let state = kernel.debug_state();
+        assert_eq!((state.paused, state.node_id.as_deref(), state.depth), (true, Some("each"), 0));
+        assert_eq!(state.variables.unwrap().blueprint.get("count"), Some(&json!(30)));
+
+        // Into the first iteration, over to the second, out of the loop, then on to the end
+        let state = kernel.debug(DebugCommand::StepInto);
+        assert_eq!((state.node_id.as_deref(), state.depth), (Some("add"), 1));
+        assert_eq!(state.variables.unwrap().execution.get("item"), Some(&json!(10)));
+        let state = kernel.debug(DebugCommand::StepOver);
+        let variables = state.variables.unwrap();
+        assert_eq!((variables.execution.get("item"), variables.blueprint.get("count")), (Some(&json!(20)), Some(&json!(40))));
+        assert_eq!(kernel.debug(DebugCommand::StepOut).node_id.as_deref(), Some("end"));
+        assert_eq!(kernel.variables.blueprint["bp"].get("threshold"), Some(&json!(30)));
+        assert!(!kernel.debug(DebugCommand::Continue).paused);
+        assert_eq!(kernel.variables.blueprint["bp"].get("threshold"), Some(&json!(60)));
+
+        // Turning debugging off lets a paused run finish
+        kernel.execute("bp", "on_click", &json!({})).unwrap();
+        assert!(kernel.debug_state().paused);
+        kernel.set_debugging(false);
+        assert!(!kernel.debug_state().paused);
+        assert_eq!(kernel.variables.blueprint["bp"].get("threshold"), Some(&json!(90)));
+    }
+
+    #[test]
+    fn test_suspended_executions_resume() {
+        let bp = blueprint(
+            json!({
+                "start": { "id": "start", "type": "on_click", "data": {} },
+                "local": { "id": "local", "type": "set_var", "data": { "varName": "step", "value": 1 } },
+                "fetch": { "id": "fetch", "type": "api_request", "data": { "url": "/api/total" } },
+                "await": { "id": "await", "type": "await_effect", "data": { "resultVar": "response", "timeout": 5000 } },
+                "store": { "id": "store", "type": "set_var", "data": { "varName": "count", "expression": "response.total + step" } },
+                "late": { "id": "late", "type": "set_var", "data": { "varName": "count", "value": -1 } },
+                "pause": { "id": "pause", "type": "wait", "data": { "duration": 500 } },
+                "after": { "id": "after", "type": "set_var", "data": { "varName": "threshold", "value": 0 } }
+            }),
+            json!([
+                { "id": "c1", "fromId": "start", "toId": "local" },
+                { "id": "c2", "fromId": "local", "toId": "fetch" },
+                { "id": "c3", "fromId": "fetch", "toId": "await" },
+                { "id": "c4", "fromId": "await", "toId": "store" },
+                { "id": "c5", "fromId": "await", "toId": "late", "port": "timeout" },
+                { "id": "c6", "fromId": "local", "toId": "pause" },
+                { "id": "c7", "fromId": "pause", "toId": "after" }
+            ]),
+        );
+        let mut kernel = LogicKernel::new();
+        kernel.register_blueprint(bp);
+        kernel.execute("bp", "on_click", &json!({})).unwrap();
+        assert_eq!(kernel.executions.len(), 2);
+
+        // Records survive a round trip through JSON
+        let saved: ExecutionTable = serde_json::from_str(&serde_json::to_string(&kernel.executions).unwrap()).unwrap();
+        kernel.executions = ExecutionTable::default();
+        kernel.executions.restore(saved);
+
+        let effect = kernel.drain_effects().remove(0);
+        assert_eq!(kernel.resume_effect(&effect.id, json!({ "total": 41 })), Ok(true));
+        assert_eq!(kernel.variables.blueprint["bp"].get("count"), Some(&json!(42)));
+
+        assert_eq!(kernel.advance_clock(0.4), 0);
+        assert_eq!(kernel.advance_clock(0.2), 1);
+        assert_eq!(kernel.variables.blueprint["bp"].get("threshold"), Some(&json!(0)));
+        assert!(kernel.executions.is_empty());
+        assert!(kernel.resume("exec-1", json!(null)).is_err());
+    }
+
+    #[test]
+    fn test_nested_waits_resume() {
+        // In a try body: the record keeps the try frame; the root's other branch runs right away
+        let bp = blueprint(
+            json!({
+                "start": { "id": "start", "type": "on_click", "data": {} },
+                "guard": { "id": "guard", "type": "try", "data": {} },
+                "pause": { "id": "pause", "type": "wait", "data": { "duration": 500 } },
+                "body": { "id": "body", "type": "set_var", "data": { "varName": "count", "value": 1 } },
+                "caught": { "id": "caught", "type": "set_var", "data": { "varName": "count", "value": -1 } },
+                "after": { "id": "after", "type": "set_var", "data": { "varName": "threshold", "expression": "count" } },
+                "side": { "id": "side", "type": "set_var", "data": { "varName": "threshold", "value": 7 } }
+            }),
+            json!([
+                { "id": "c1", "fromId": "start", "toId": "guard" },
+                { "id": "c2", "fromId": "guard", "toId": "pause", "port": "body" },
+                { "id": "c3", "fromId": "pause", "toId": "body" },
+                { "id": "c4", "fromId": "guard", "toId": "caught", "port": "catch" },
+                { "id": "c5", "fromId": "guard", "toId": "after" },
+                { "id": "c6", "fromId": "start", "toId": "side" }
+            ]),
+        );
+        let mut kernel = LogicKernel::new();
+        kernel.register_blueprint(bp);
+        kernel.execute("bp", "on_click", &json!({})).unwrap();
+        assert_eq!(kernel.executions.len(), 1);
+        assert_eq!(kernel.variables.blueprint["bp"].get("threshold"), Some(&json!(7)));
+        assert_eq!(kernel.advance_clock(0.6), 1);
+        assert_eq!(kernel.variables.blueprint["bp"].get("threshold"), Some(&json!(1)));
+        assert!(kernel.errors.is_empty());
+
+        // In a loop body: each iteration waits, and the loop carries on from the record
+        let bp = blueprint(
+            json!({
+                "start": { "id": "start", "type": "on_click", "data": {} },
+                "each": { "id": "each", "type": "for_each", "data": { "items": "[1, 2]" } },
+                "pause": { "id": "pause", "type": "wait", "data": { "duration": 500 } },
+                "add": { "id": "add", "type": "set_var", "data": { "varName": "count", "expression": "count + item" } },
+                "done": { "id": "done", "type": "set_var", "data": { "varName": "threshold", "expression": "count" } }
+            }),
+            json!([
+                { "id": "c1", "fromId": "start", "toId": "each" },
+                { "id": "c2", "fromId": "each", "toId": "pause", "port": "body" },
+                { "id": "c3", "fromId": "pause", "toId": "add" },
+                { "id": "c4", "fromId": "each", "toId": "done", "port": "done" }
+            ]),
+        );
+        let mut kernel = LogicKernel::new();
+        kernel.register_blueprint(bp);
+        kernel.execute("bp", "on_click", &json!({})).unwrap();
+        assert_eq!(kernel.executions.pending().next().unwrap().frames.len(), 2);
+
+        // Records keep their frames through a round trip in JSON
+        let saved: ExecutionTable = serde_json::from_str(&serde_json::to_string(&kernel.executions).unwrap()).unwrap();
+        kernel.executions = ExecutionTable::default();
+        kernel.executions.restore(saved);
+
+        assert_eq!(kernel.advance_clock(0.6), 1);
+        assert_eq!(kernel.variables.blueprint["bp"].get("count"), Some(&json!(1)));
+        assert_eq!(kernel.executions.len(), 1); // The second iteration's wait
+        assert_eq!(kernel.advance_clock(0.6), 1);
+        assert_eq!(kernel.variables.blueprint["bp"].get("threshold"), Some(&json!(3)));
+        assert!(kernel.executions.is_empty());
+
+        // In a callee: the call returns once the awaited effect comes back
+        let callee: UnifiedBlueprint = serde_json::from_value(json!({
+            "id": "slow", "variables": {},
+            "connections": [
+                { "id": "s1", "fromId": "entry", "toId": "fetch" },
+                { "id": "s2", "fromId": "fetch", "toId": "await" },
+                { "id": "s3", "fromId": "await", "toId": "ret" }
+            ],
+            "nodes": {
+                "entry": { "id": "entry", "type": "on_call", "data": {} },
+                "fetch": { "id": "fetch", "type": "api_request", "data": { "url": "/api/total" } },
+                "await": { "id": "await", "type": "await_effect", "data": { "resultVar": "response" } },
+                "ret": { "id": "ret", "type": "return", "data": { "expression": "response.total + amount" } }
+            }
+        })).unwrap();
+        let bp = blueprint(
+            json!({
+                "start": { "id": "start", "type": "on_click", "data": {} },
+                "call": { "id": "call", "type": "call_blueprint", "data": { "blueprintId": "slow", "args": { "amount": 1 }, "resultVar": "result" } },
+                "store": { "id": "store", "type": "set_var", "data": { "varName": "count", "expression": "result" } }
+            }),
+            json!([
+                { "id": "c1", "fromId": "start", "toId": "call" },
+                { "id": "c2", "fromId": "call", "toId": "store" }
+            ]),
+        );
+        let mut kernel = LogicKernel::new();
+        kernel.register_blueprint(callee);
+        kernel.register_blueprint(bp);
+        kernel.execute("bp", "on_click", &json!({})).unwrap();
+        // Until then the caller's scope is back in place
+        assert!(kernel.variables.execution.get("amount").is_none());
+
+        let effect = kernel.drain_effects().remove(0);
+        assert_eq!(kernel.resume_effect(&effect.id, json!({ "total": 41 })), Ok(true));
+        assert_eq!(kernel.variables.blueprint["bp"].get("count"), Some(&json!(42)));
+        assert!(kernel.variables.execution.get("response").is_none());
+        assert!(kernel.errors.is_empty());
+    }
+
+    #[test]
+    fn test_work_queue_and_limits() {
+        // A 5000-node chain runs without growing the stack
+        let mut nodes = serde_json::Map::new();
+        let mut connections = Vec::new();
+        nodes.insert("start".to_string(), json!({ "id": "start", "type": "on_click", "data": {} }));
+        let mut prev = "start".to_string();
+        for i in 0..5000 {
+            let id = format!("n{}", i);
+            nodes.insert(id.clone(), json!({ "id": id, "type": "set_var", "data": { "varName": "count", "expression": "count + 1" } }));
+            connections.push(json!({ "id": format!("c{}", i), "fromId": prev, "toId": id }));
+            prev = id;
+        }
+        let mut kernel = LogicKernel::new();
+        kernel.register_blueprint(blueprint(json!(nodes), json!(connections)));
+        kernel.limits.max_steps = 10_000;
+        kernel.limits.max_duration_ms = None;
+        kernel.execute("bp", "on_click", &json!({})).unwrap();
+        assert_eq!(kernel.variables.blueprint["bp"].get("count"), Some(&json!(5000)));
+
+        // Per-type costs: the chain is now too expensive
+        kernel.limits.node_costs.insert("set_var".to_string(), 3);
+        let result = kernel.execute("bp", "on_click", &json!({}));
+        assert!(matches!(result, Err(ExecutionError::GasExhausted { limit: 10_000, .. })));
+
+        // Two branches share the gas in turns, so neither starves
+        let bp = blueprint(
+            json!({
+                "start": { "id": "start", "type": "on_click", "data": {} },
+                "a": { "id": "a", "type": "set_var", "data": { "varName": "count", "expression": "count + 1" } },
+                "b": { "id": "b", "type": "set_var", "data": { "varName": "threshold", "expression": "threshold + 1" } }
+            }),
+            json!([
+                { "id": "c1", "fromId": "start", "toId": "a" },
+                { "id": "c2", "fromId": "start", "toId": "b" },
+                { "id": "c3", "fromId": "a", "toId": "a" },
+                { "id": "c4", "fromId": "b", "toId": "b" }
+            ]),
+        );
+        let mut kernel = LogicKernel::new();
+        kernel.register_blueprint(bp);
+        kernel.limits.max_duration_ms = None;
+        assert!(kernel.execute("bp", "on_click", &json!({})).is_err());
+        let vars = &kernel.variables.blueprint["bp"];
+        let (a, b) = (vars["count"].as_i64().unwrap(), vars["threshold"].as_i64().unwrap() - 100);
+        assert!((a - b).abs() <= 1 && a > 400);
+
+        // Oversized values abort the run instead of being stored
+        let bp = blueprint(
+            json!({
+                "start": { "id": "start", "type": "on_click", "data": {} },
+                "big": { "id": "big", "type": "set_var", "data": { "varName": "blob", "expression": "payload.text" } }
+            }),
+            json!([{ "id": "c1", "fromId": "start", "toId": "big" }]),
+        );
+        kernel.register_blueprint(bp);
+        kernel.limits.max_value_bytes = 16;
+        let result = kernel.execute("bp", "on_click", &json!({ "text": "far more than sixteen bytes" }));
+        assert!(matches!(result, Err(ExecutionError::ValueTooLarge { bytes: 27, limit: 16, ref node_id, .. }) if node_id == "big"));
+        assert!(kernel.variables.execution.get("blob").is_none());
+        assert!(kernel.errors.is_empty());
+    }
+
+    #[test]
+    fn test_error_ports_and_try() {
+        let main = blueprint(
+            json!({
+                "start": { "id": "start", "type": "on_click", "data": {} },
+                "bad": { "id": "bad", "type": "set_var", "data": { "varName": "oops", "expression": "1 +" } },
+                "report": { "id": "report", "type": "set_var", "data": { "varName": "threshold", "expression": "error.code" } },
+                "guard": { "id": "guard", "type": "try", "data": {} },
+                "each": { "id": "each", "type": "for_each", "data": { "items": "payload.items" } },
+                "never": { "id": "never", "type": "set_var", "data": { "varName": "count", "value": -1 } },
+                "caught": { "id": "caught", "type": "set_var", "data": { "varName": "count", "expression": "error.nodeId" } },
+                "after": { "id": "after", "type": "set_var", "data": { "varName": "count", "value": 1 } },
+                "call": { "id": "call", "type": "call_blueprint", "data": { "blueprintId": "failing" } }
+            }),
+            json!([
+                { "id": "c1", "fromId": "caught", "toId": "bad" },
+                { "id": "c2", "fromId": "bad", "toId": "report", "port": "error" },
+                { "id": "c3", "fromId": "start", "toId": "guard" },
+                { "id": "c4", "fromId": "guard", "toId": "each", "port": "body" },
+                { "id": "c5", "fromId": "each", "toId": "never", "port": "done" },
+                { "id": "c6", "fromId": "guard", "toId": "caught", "port": "catch" },
+                { "id": "c7", "fromId": "guard", "toId": "after" },
+                { "id": "c8", "fromId": "start", "toId": "call" }
+            ]),
+        );
+        let failing: UnifiedBlueprint = serde_json::from_value(json!({
+            "id": "failing", "variables": {}, "connections": [{ "id": "f1", "fromId": "entry", "toId": "boom" }],
+            "nodes": {
+                "entry": { "id": "entry", "type": "on_call", "data": {} },
+                "boom": { "id": "boom", "type": "set_var", "data": { "varName": "x", "expression": "nope(" } }
+            }
+        })).unwrap();
+        let mut kernel = LogicKernel::new();
+        kernel.register_blueprint(main);
+        kernel.register_blueprint(failing);
+        kernel.execute("bp", "on_click", &json!({ "items": "not a list" })).unwrap();
+
+        // The try body failed, so only `catch` ran; then `bad`'s error port saw its own error
+        let vars = &kernel.variables.blueprint["bp"];
+        assert_eq!(vars.get("threshold"), Some(&json!("invalid_expression")));
+        assert_eq!(vars.get("count"), Some(&json!("each")));
+        // The callee's failure fails the call, which nothing handles
+        assert_eq!(kernel.errors.len(), 1);
+        assert_eq!((kernel.errors[0].node_id.as_str(), kernel.errors[0].code.as_str()), ("call", "invalid_expression"));
+        assert!(kernel.errors[0].message.starts_with("failing/boom: "));
+
+        // Without a failure the try continues on its default port
+        kernel.execute("bp", "on_click", &json!({ "items": [] })).unwrap();
+        assert_eq!(kernel.variables.blueprint["bp"].get("count"), Some(&json!(1)));
+
+        // A failed effect fails the node awaiting it
+        let bp = blueprint(
+            json!({
+                "start": { "id": "start", "type": "on_click", "data": {} },
+                "fetch": { "id": "fetch", "type": "api_request", "data": { "url": "/api/total" } },
+                "await": { "id": "await", "type": "await_effect", "data": { "resultVar": "response" } },
+                "store": { "id": "store", "type": "set_var", "data": { "varName": "count", "value": 2 } }
+            }),
+            json!([
+                { "id": "c1", "fromId": "start", "toId": "fetch" },
+                { "id": "c2", "fromId": "fetch", "toId": "await" },
+                { "id": "c3", "fromId": "await", "toId": "store" }
+            ]),
+        );
+        kernel.register_blueprint(bp);
+        kernel.execute("bp", "on_click", &json!({})).unwrap();
+        let effect = kernel.drain_effects().remove(0);
+        assert_eq!(kernel.resume_effect(&effect.id, json!({ "error": "HTTP 503" })), Ok(true));
+        assert_eq!(kernel.errors[0], LogicError {
+            blueprint_id: "bp".to_string(),
+            node_id: "await".to_string(),
+            code: "effect_failed".to_string(),
+            message: "HTTP 503".to_string(),
+        });
+        assert!(kernel.variables.execution.get("response").is_none());
+        assert_eq!(kernel.variables.blueprint["bp"].get("count"), Some(&json!(1)));
+    }
+}
@@ -0,0 +1,328 @@
+use crate::plugins::interaction::{format_distance, Rect, SnapGuide};
+use crate::plugins::pointer::hovered_element;
+use crate::plugins::selection::SELECTION;
+use crate::plugins::spatial_index::SPATIAL_INDEX;
+use crate::{LAYOUT_TREE, TAFFY};
+use serde::Serialize;
+use taffy::prelude::LayoutTree;
+use taffy::style::{LengthPercentage, LengthPercentageAuto};
+use wasm_bindgen::prelude::*;
+
+// --- MEASUREMENT / REDLINES ---
+// "How far is this from that": edge-to-edge distances between two boxes, and the padding and
+// margin bands of a single element. Results are SnapGuide lines so the canvas draws them like
+// snap guides (kinds: "distance", "extension", "overlap", "inset", "padding", "margin").
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Relation {
+    Apart,
+    Overlap,
+    Inside,   // `from` lies within `to`
+    Contains, // `to` lies within `from`
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct Edges {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct BoxRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl From<&Rect> for BoxRect {
+    fn from(r: &Rect) -> Self {
+        Self { x: r.min_x, y: r.min_y, width: r.max_x - r.min_x, height: r.max_y - r.min_y }
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Measurement {
+    pub from: Vec<String>,
+    pub to: String,
+    pub relation: Relation,
+    pub horizontal: f32, // Edge-to-edge gaps, 0 on an axis where the boxes overlap
+    pub vertical: f32,
+    pub overlap: Option<BoxRect>,
+    pub guides: Vec<SnapGuide>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BoxModel {
+    pub id: String,
+    pub border_box: BoxRect,
+    pub content_box: BoxRect,
+    pub margin_box: BoxRect,
+    pub padding: Edges,
+    pub margin: Edges,
+    pub guides: Vec<SnapGuide>,
+}
+
+fn line(kind: &str, orientation: &str, value: f32, from: f32, to: f32) -> SnapGuide {
+    SnapGuide {
+        orientation: orientation.to_string(),
+        value,
+        label: format_distance(to - from),
+        kind: kind.to_string(),
+        start: from,
+        end: to,
+    }
+}
+
+fn contains(outer: &Rect, inner: &Rect) -> bool {
+    outer.min_x <= inner.min_x && outer.min_y <= inner.min_y && outer.max_x >= inner.max_x && outer.max_y >= inner.max_y
+}
+
+/// Gap segment between two extents, or None when they overlap.
+fn gap(a: (f32, f32), b: (f32, f32)) -> Option<(f32, f32)> {
+    if a.1 <= b.0 { Some((a.1, b.0)) } else if b.1 <= a.0 { Some((b.1, a.0)) } else { None }
+}
+
+/// Distances from an inner box to the walls of the box around it.
+fn insets(inner: &Rect, outer: &Rect) -> Vec<SnapGuide> {
+    let (cx, cy) = ((inner.min_x + inner.max_x) / 2.0, (inner.min_y + inner.max_y) / 2.0);
+    [
+        line("inset", "horizontal", cy, outer.min_x, inner.min_x),
+        line("inset", "horizontal", cy, inner.max_x, outer.max_x),
+        line("inset", "vertical", cx, outer.min_y, inner.min_y),
+        line("inset", "vertical", cx, inner.max_y, outer.max_y),
+    ]
+    .into_iter()
+    .filter(|g| g.end - g.start > 0.0)
+    .collect()
+}
+
+/// Redlines between `a` (the measured element) and `b` (the reference).
+pub fn measure(from: Vec<String>, to: &str, a: &Rect, b: &Rect) -> Measurement {
+    let mut m = Measurement {
+        from,
+        to: to.to_string(),
+        relation: Relation::Apart,
+        horizontal: 0.0,
+        vertical: 0.0,
+        overlap: None,
+        guides: Vec::new(),
+    };
+
+    if contains(b, a) {
+        m.relation = Relation::Inside;
+        m.guides = insets(a, b);
+        return m;
+    }
+    if contains(a, b) {
+        m.relation = Relation::Contains;
+        m.guides = insets(b, a);
+        return m;
+    }
+
+    let gap_x = gap((a.min_x, a.max_x), (b.min_x, b.max_x));
+    let gap_y = gap((a.min_y, a.max_y), (b.min_y, b.max_y));
+
+    if gap_x.is_none() && gap_y.is_none() {
+        let o = Rect { min_x: a.min_x.max(b.min_x), min_y: a.min_y.max(b.min_y), max_x: a.max_x.min(b.max_x), max_y: a.max_y.min(b.max_y) };
+        m.relation = Relation::Overlap;
+        m.guides.push(line("overlap", "horizontal", (o.min_y + o.max_y) / 2.0, o.min_x, o.max_x));
+        m.guides.push(line("overlap", "vertical", (o.min_x + o.max_x) / 2.0, o.min_y, o.max_y));
+        m.overlap = Some(BoxRect::from(&o));
+        return m;
+    }
+
+    // Each gap is drawn through the shared cross extent, or from the measured element's center
+    // with an extension line along the reference edge when they don't share one
+    if let Some((x0, x1)) = gap_x {
+        m.horizontal = x1 - x0;
+        let y = match gap_y {
+            None => (a.min_y.max(b.min_y) + a.max_y.min(b.max_y)) / 2.0,
+            Some(_) => (a.min_y + a.max_y) / 2.0,
+        };
+        m.guides.push(line("distance", "horizontal", y, x0, x1));
+        if y < b.min_y || y > b.max_y {
+            let edge = if b.min_x >= a.max_x { b.min_x } else { b.max_x };
+            m.guides.push(line("extension", "vertical", edge, y.min(b.min_y), y.max(b.max_y)));
+        }
+    }
+    if let Some((y0, y1)) = gap_y {
+        m.vertical = y1 - y0;
+        let x = match gap_x {
+            None => (a.min_x.max(b.min_x) + a.max_x.min(b.max_x)) / 2.0,
+            Some(_) => (a.min_x + a.max_x) / 2.0,
+        };
+        m.guides.push(line("distance", "vertical", x, y0, y1));
+        if x < b.min_x || x > b.max_x {
+            let edge = if b.min_y >= a.max_y { b.min_y } else { b.max_y };
+            m.guides.push(line("extension", "horizontal", edge, x.min(b.min_x), x.max(b.max_x)));
+        }
+    }
+    m
+}
+
+/// Box model around a border box, with one guide per non-zero padding/margin side.
+pub fn box_model(id: &str, border: &Rect, padding: Edges, margin: Edges) -> BoxModel {
+    let content = Rect {
+        min_x: border.min_x + padding.left,
+        min_y: border.min_y + padding.top,
+        max_x: border.max_x - padding.right,
+        max_y: border.max_y - padding.bottom,
+    };
+    let outer = Rect {
+        min_x: border.min_x - margin.left,
+        min_y: border.min_y - margin.top,
+        max_x: border.max_x + margin.right,
+        max_y: border.max_y + margin.bottom,
+    };
+    let (cx, cy) = ((border.min_x + border.max_x) / 2.0, (border.min_y + border.max_y) / 2.0);
+
+    let guides = [
+        line("padding", "horizontal", cy, border.min_x, content.min_x),
+        line("padding", "horizontal", cy, content.max_x, border.max_x),
+        line("padding", "vertical", cx, border.min_y, content.min_y),
+        line("padding", "vertical", cx, content.max_y, border.max_y),
+        line("margin", "horizontal", cy, outer.min_x, border.min_x),
+        line("margin", "horizontal", cy, border.max_x, outer.max_x),
+        line("margin", "vertical", cx, outer.min_y, border.min_y),
+        line("margin", "vertical", cx, border.max_y, outer.max_y),
+    ]
+    .into_iter()
+    .filter(|g| g.end - g.start > 0.0)
+    .collect();
+
+    BoxModel {
+        id: id.to_string(),
+        border_box: BoxRect::from(border),
+        content_box: BoxRect::from(&content),
+        margin_box: BoxRect::from(&outer),
+        padding,
+        margin,
+        guides,
+    }
+}
+
+/// Padding and margin from the computed layout. Percentages resolve against the parent width, auto margins count as 0.
+fn layout_edges(id: &str) -> (Edges, Edges) {
+    let taffy = TAFFY.lock().unwrap();
+    let tree = LAYOUT_TREE.lock().unwrap();
+    let node = match tree.get(id) { Some(n) => *n, None => return (Edges::default(), Edges::default()) };
+    let style = match taffy.style(node) { Ok(s) => s, Err(_) => return (Edges::default(), Edges::default()) };
+    let basis = taffy.parent(node).and_then(|p| taffy.layout(p).ok()).map(|l| l.size.width).unwrap_or(0.0);
+
+    let lp = |v: LengthPercentage| match v {
+        LengthPercentage::Points(p) => p,
+        LengthPercentage::Percent(f) => f * basis,
+    };
+    let lpa = |v: LengthPercentageAuto| match v {
+        LengthPercentageAuto::Points(p) => p,
+        LengthPercentageAuto::Percent(f) => f * basis,
+        LengthPercentageAuto::Auto => 0.0,
+    };
+    let p = style.padding;
+    let m = style.margin;
+    (
+        Edges { top: lp(p.top), right: lp(p.right), bottom: lp(p.bottom), left: lp(p.left) },
+        Edges { top: lpa(m.top), right: lpa(m.right), bottom: lpa(m.bottom), left: lpa(m.left) },
+    )
+}
+
+fn union_bounds(ids: &[String]) -> Option<Rect> {
+    let index = SPATIAL_INDEX.lock().unwrap();
+    ids.iter()
+        .filter_map(|id| index.query_one(id))
+        .map(|b| Rect::from(&b))
+        .reduce(|g, r| Rect {
+            min_x: g.min_x.min(r.min_x),
+            min_y: g.min_y.min(r.min_y),
+            max_x: g.max_x.max(r.max_x),
+            max_y: g.max_y.max(r.max_y),
+        })
+}
+
+fn measure_ids(from: Vec<String>, to: &str) -> String {
+    let a = union_bounds(&from);
+    let b = union_bounds(&[to.to_string()]);
+    match (a, b) {
+        (Some(a), Some(b)) => serde_json::to_string(&measure(from, to, &a, &b)).unwrap_or("null".to_string()),
+        _ => "null".to_string(),
+    }
+}
+
+// --- WASM Exports ---
+// Coordinates are world units. Unknown elements return "null".
+
+#[wasm_bindgen]
+pub fn measure_elements(from_id: &str, to_id: &str) -> String {
+    measure_ids(vec![from_id.to_string()], to_id)
+}
+
+/// Measures the selection (as one box) against the hovered element.
+#[wasm_bindgen]
+pub fn measure_selection_to_hovered() -> String {
+    let from = SELECTION.lock().unwrap().ids.clone();
+    let to = match hovered_element() {
+        Some(id) if !from.contains(&id) => id,
+        _ => return "null".to_string(),
+    };
+    measure_ids(from, &to)
+}
+
+#[wasm_bindgen]
+pub fn get_box_model(id: &str) -> String {
+    let border = match SPATIAL_INDEX.lock().unwrap().query_one(id) {
+        Some(b) => Rect::from(&b),
+        None => return "null".to_string(),
+    };
+    let (padding, margin) = layout_edges(id);
+    serde_json::to_string(&box_model(id, &border, padding, margin)).unwrap_or("null".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(m: &[SnapGuide]) -> Vec<(&str, &str, &str)> {
+        m.iter().map(|g| (g.kind.as_str(), g.orientation.as_str(), g.label.as_str())).collect()
+    }
+
+    #[test]
+    fn test_measure_relations() {
+        // Side by side, sharing rows 20..50: one horizontal distance through the shared band
+        let m = measure(vec!["a".into()], "b", &Rect::from_xywh(0.0, 0.0, 100.0, 50.0), &Rect::from_xywh(124.0, 20.0, 50.0, 50.0));
+        assert_eq!(m.relation, Relation::Apart);
+        assert_eq!((m.horizontal, m.vertical), (24.0, 0.0));
+        assert_eq!(kinds(&m.guides), vec![("distance", "horizontal", "24")]);
+        assert_eq!(m.guides[0].value, 35.0);
+
+        // Diagonal: both gaps, each with an extension line to reach the reference
+        let m = measure(vec!["a".into()], "b", &Rect::from_xywh(0.0, 0.0, 100.0, 50.0), &Rect::from_xywh(110.0, 80.0, 50.0, 50.0));
+        assert_eq!((m.horizontal, m.vertical), (10.0, 30.0));
+        assert_eq!(m.guides.iter().filter(|g| g.kind == "extension").count(), 2);
+
+        let m = measure(vec!["a".into()], "b", &Rect::from_xywh(0.0, 0.0, 100.0, 100.0), &Rect::from_xywh(60.0, 80.0, 100.0, 100.0));
+        assert_eq!(m.relation, Relation::Overlap);
+        assert_eq!(m.overlap, Some(BoxRect { x: 60.0, y: 80.0, width: 40.0, height: 20.0 }));
+
+        let m = measure(vec!["child".into()], "parent", &Rect::from_xywh(16.0, 8.0, 50.0, 50.0), &Rect::from_xywh(0.0, 0.0, 100.0, 58.0));
+        assert_eq!(m.relation, Relation::Inside);
+        assert_eq!(kinds(&m.guides), vec![("inset", "horizontal", "16"), ("inset", "horizontal", "34"), ("inset", "vertical", "8")]);
+    }
+
+    #[test]
+    fn test_box_model_guides() {
+        let padding = Edges { top: 8.0, right: 8.0, bottom: 8.0, left: 8.0 };
+        let margin = Edges { top: 0.0, right: 0.0, bottom: 12.0, left: 0.0 };
+        let model = box_model("card", &Rect::from_xywh(100.0, 100.0, 200.0, 80.0), padding, margin);
+        assert_eq!(model.content_box, BoxRect { x: 108.0, y: 108.0, width: 184.0, height: 64.0 });
+        assert_eq!(model.margin_box.height, 92.0);
+        assert_eq!(model.guides.iter().filter(|g| g.kind == "padding").count(), 4);
+        let margins: Vec<_> = model.guides.iter().filter(|g| g.kind == "margin").collect();
+        assert_eq!((margins.len(), margins[0].start, margins[0].end), (1, 180.0, 192.0));
+    }
+}
@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+// --- EXECUTION LIMITS ---
+// Each node costs gas by type, and every loop iteration costs gas too. Variable writes are
+// size-capped, and runs have a wall-clock budget. Hitting any limit aborts the whole
+// execution with an ExecutionError. Node failures (bad expression, missing field) only stop
+// their own branch and are reported as LogicErrors.
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExecutionLimits {
+    pub max_steps: u64,                   // Gas per execution (and per resumption)
+    pub default_cost: u64,
+    pub node_costs: HashMap<String, u64>, // Node type -> cost, overriding `default_cost`
+    pub iteration_cost: u64,              // Charged by for_each/while per iteration
+    pub max_queue: usize,                 // Pending nodes (fan-out) in one run
+    pub max_call_depth: usize,
+    pub max_loop_depth: usize,            // Nested loop bodies (a body that re-enters its loop nests)
+    pub max_value_bytes: usize,           // Approximate size of one variable's value
+    pub max_scope_bytes: usize,           // Approximate size of all execution-scope variables
+    pub max_duration_ms: Option<f64>,     // Wall-clock budget; None disables it
+}
+
+impl Default for ExecutionLimits {
+    fn default() -> Self {
+        Self {
+            max_steps: 1000,
+            default_cost: 1,
+            node_costs: HashMap::from([
+                ("api_request".to_string(), 10),
+                ("http_request".to_string(), 10),
+                ("call_blueprint".to_string(), 5),
+            ]),
+            iteration_cost: 1,
+            max_queue: 10_000,
+            max_call_depth: 32,
+            max_loop_depth: 64,
+            max_value_bytes: 1 << 20,
+            max_scope_bytes: 8 << 20,
+            max_duration_ms: Some(100.0),
+        }
+    }
+}
+
+impl ExecutionLimits {
+    pub fn cost(&self, node_type: &str) -> u64 {
+        self.node_costs.get(node_type).copied().unwrap_or(self.default_cost)
+    }
+
+    /// These limits, but never looser than `max`: budgets and caps are lowered to its values and
+    /// costs raised to them. For limits supplied by untrusted callers (the server's requests).
+    pub fn clamped_to(&self, max: &ExecutionLimits) -> Self {
+        let mut node_costs: HashMap<String, u64> = max.node_costs.keys().chain(self.node_costs.keys())
+            .map(|node_type| (node_type.clone(), self.cost(node_type).max(max.cost(node_type))))
+            .collect();
+        node_costs.retain(|_, cost| *cost != self.default_cost.max(max.default_cost));
+        Self {
+            max_steps: self.max_steps.min(max.max_steps),
+            default_cost: self.default_cost.max(max.default_cost),
+            node_costs,
+            iteration_cost: self.iteration_cost.max(max.iteration_cost),
+            max_queue: self.max_queue.min(max.max_queue),
+            max_call_depth: self.max_call_depth.min(max.max_call_depth),
+            max_loop_depth: self.max_loop_depth.min(max.max_loop_depth),
+            max_value_bytes: self.max_value_bytes.min(max.max_value_bytes),
+            max_scope_bytes: self.max_scope_bytes.min(max.max_scope_bytes),
+            max_duration_ms: match (self.max_duration_ms, max.max_duration_ms) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+}
+
+/// Why an execution was stopped.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ExecutionError {
+    #[serde(rename_all = "camelCase")]
+    GasExhausted { limit: u64, node_id: String },
+    #[serde(rename_all = "camelCase")]
+    Timeout { budget_ms: f64, node_id: String },
+    #[serde(rename_all = "camelCase")]
+    QueueOverflow { limit: usize, node_id: String },
+    #[serde(rename_all = "camelCase")]
+    DepthExceeded { limit: usize, node_id: String, what: String }, // "call" or "loop"
+    #[serde(rename_all = "camelCase")]
+    ValueTooLarge { name: String, bytes: usize, limit: usize, node_id: String },
+    #[serde(rename_all = "camelCase")]
+    UnknownBlueprint { blueprint_id: String },
+    #[serde(rename_all = "camelCase")]
+    UnknownExecution { execution_id: String },
+    #[serde(rename_all = "camelCase")]
+    StaleExecution { execution_id: String, message: String }, // Its blueprint or node changed since it suspended
+}
+
+impl std::fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecutionError::GasExhausted { limit, node_id } => write!(f, "Gas limit exceeded ({} steps) at {}", limit, node_id),
+            ExecutionError::Timeout { budget_ms, node_id } => write!(f, "Time budget of {}ms exceeded at {}", budget_ms, node_id),
+            ExecutionError::QueueOverflow { limit, node_id } => write!(f, "More than {} pending nodes at {}", limit, node_id),
+            ExecutionError::DepthExceeded { limit, node_id, what } => write!(f, "{} depth limit ({}) exceeded at {}", what, limit, node_id),
+            ExecutionError::ValueTooLarge { name, bytes, limit, node_id } => write!(f, "'{}' would hold {} bytes (limit {}) at {}", name, bytes, limit, node_id),
+            ExecutionError::UnknownBlueprint { blueprint_id } => write!(f, "Blueprint '{}' is not registered", blueprint_id),
+            ExecutionError::UnknownExecution { execution_id } => write!(f, "No pending execution '{}'", execution_id),
+            ExecutionError::StaleExecution { execution_id, message } => write!(f, "Execution '{}': {}", execution_id, message),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionError {}
+
+/// Approximate in-memory size of a value (strings by length, containers by their contents).
+pub fn value_size(value: &Value) -> usize {
+    match value {
+        Value::Null | Value::Bool(_) => 1,
+        Value::Number(_) => 8,
+        Value::String(s) => s.len(),
+        Value::Array(items) => 8 + items.iter().map(value_size).sum::<usize>(),
+        Value::Object(map) => 8 + map.iter().map(|(k, v)| k.len() + value_size(v)).sum::<usize>(),
+    }
+}
+
+/// Milliseconds since an arbitrary origin. Without a clock (wasm outside a window) it stays at 0,
+/// which disables the wall-clock budget.
+pub fn now_ms() -> f64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use std::sync::OnceLock;
+        use std::time::Instant;
+        static ORIGIN: OnceLock<Instant> = OnceLock::new();
+        ORIGIN.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+    }
+    #[cfg(all(target_arch = "wasm32", feature = "browser"))]
+    {
+        web_sys::window().and_then(|w| w.performance()).map(|p| p.now()).unwrap_or(0.0)
+    }
+    #[cfg(all(target_arch = "wasm32", not(feature = "browser")))]
+    {
+        0.0
+    }
+}
+
+/// Gas and time used by the run in progress.
+#[derive(Debug, Clone, Default)]
+pub struct Meter {
+    pub spent: u64,
+    pub started_ms: f64,
+}
+
+impl Meter {
+    pub fn start() -> Self {
+        Self { spent: 0, started_ms: now_ms() }
+    }
+
+    /// Charges `cost`; fails if the run would exceed its gas or time budget.
+    pub fn charge(&mut self, limits: &ExecutionLimits, cost: u64, node_id: &str) -> Result<(), ExecutionError> {
+        if self.spent + cost > limits.max_steps {
+            return Err(ExecutionError::GasExhausted { limit: limits.max_steps, node_id: node_id.to_string() });
+        }
+        if let Some(budget_ms) = limits.max_duration_ms {
+            if now_ms() - self.started_ms > budget_ms {
+                return Err(ExecutionError::Timeout { budget_ms, node_id: node_id.to_string() });
+            }
+        }
+        self.spent += cost;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requested_limits_are_clamped() {
+        let max = ExecutionLimits::default();
+        let requested: ExecutionLimits = serde_json::from_value(serde_json::json!({
+            "maxSteps": u64::MAX, "maxDurationMs": null, "defaultCost": 0, "nodeCosts": { "api_request": 1, "set_var": 4 }, "maxValueBytes": 64
+        })).unwrap();
+        let limits = requested.clamped_to(&max);
+        assert_eq!(limits.max_steps, max.max_steps);
+        assert_eq!(limits.max_duration_ms, max.max_duration_ms);
+        assert_eq!((limits.cost("api_request"), limits.cost("set_var"), limits.cost("while")), (10, 4, 1));
+        assert_eq!(limits.max_value_bytes, 64);
+    }
+}
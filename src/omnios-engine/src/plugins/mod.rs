@@ -6,8 +6,28 @@ pub mod network;
 pub mod devtools;
 pub mod logic;
 pub mod logic_kernel;
+pub mod expression;
+pub mod effects;
+pub mod executions;
+pub mod metering;
+pub mod trace;
+pub mod debugger;
+pub mod nodes;
+pub mod variables;
+pub mod validator;
 pub mod spatial_index;
 pub mod layout;
 pub mod simulation;
 pub mod vqa;
 pub mod interaction;
+pub mod animation;
+pub mod timeline;
+pub mod scheduler;
+pub mod selection;
+pub mod resize;
+pub mod arrange;
+pub mod drop_target;
+pub mod camera;
+pub mod pointer;
+pub mod navigation;
+pub mod measure;
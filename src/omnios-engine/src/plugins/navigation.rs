@@ -0,0 +1,274 @@
+use crate::{DesignerElement, HyperCommand, PROJECT_STATE};
+use crate::plugins::animation::parse_numeric;
+use crate::plugins::resize::px;
+use crate::plugins::selection::{is_selectable, SELECTION};
+use crate::plugins::spatial_index::SPATIAL_INDEX;
+use serde::Serialize;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+// --- KEYBOARD NAVIGATION ---
+// Resolves the keyboard actions from ShortcutsPlugin against the element tree: arrow keys
+// nudge freedom elements (or reorder flex children), Tab/Enter/Escape walk the tree.
+
+pub const NUDGE_SMALL: f32 = 1.0;
+pub const NUDGE_LARGE: f32 = 10.0;
+
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionResult {
+    pub action: String,
+    pub handled: bool,
+    pub commands: Vec<HyperCommand>, // For `apply_command`
+}
+
+/// Direction and distance for the Nudge* actions.
+pub fn nudge_delta(action: &str) -> Option<(f32, f32)> {
+    let (base, step) = match action.strip_suffix("Large") {
+        Some(base) => (base, NUDGE_LARGE),
+        None => (action, NUDGE_SMALL),
+    };
+    match base {
+        "NudgeLeft" => Some((-step, 0.0)),
+        "NudgeRight" => Some((step, 0.0)),
+        "NudgeUp" => Some((0.0, -step)),
+        "NudgeDown" => Some((0.0, step)),
+        _ => None,
+    }
+}
+
+fn is_positioned(el: &DesignerElement, view_mode: &str) -> bool {
+    el.layout_mode.as_deref() == Some("freedom")
+        || crate::resolve_final_styles(el, view_mode).get("position").and_then(|v| v.as_str()) == Some("absolute")
+}
+
+/// +1/-1 along the parent's main axis for an arrow delta, None when the arrow runs across it.
+fn reorder_step(parent: &DesignerElement, view_mode: &str, dx: f32, dy: f32) -> Option<i64> {
+    let styles = crate::resolve_final_styles(parent, view_mode);
+    let direction = styles.get("flexDirection").and_then(|v| v.as_str()).unwrap_or("row");
+    let (along, reversed) = match direction {
+        "column" => (dy, false),
+        "column-reverse" => (dy, true),
+        "row-reverse" => (dx, true),
+        _ => (dx, false),
+    };
+    if along == 0.0 { return None; }
+    let step = if along > 0.0 { 1 } else { -1 };
+    Some(if reversed { -step } else { step })
+}
+
+/// Nudge commands: insets for freedom elements, REORDER_ELEMENT for flex children.
+/// `offsets` supplies the current inset of elements whose styles have no numeric left/top.
+pub fn nudge_commands(
+    elements: &HashMap<String, DesignerElement>,
+    view_mode: &str,
+    ids: &[String],
+    dx: f32,
+    dy: f32,
+    offsets: &HashMap<String, (f32, f32)>,
+) -> Vec<HyperCommand> {
+    let mut commands = Vec::new();
+    // Flex children grouped per parent, so several selected siblings move as a block
+    let mut by_parent: Vec<(String, i64, Vec<String>)> = Vec::new();
+
+    for id in ids {
+        let el = match elements.get(id) { Some(e) => e, None => continue };
+        if is_positioned(el, view_mode) {
+            let styles = crate::resolve_final_styles(el, view_mode);
+            let fallback = offsets.get(id).copied().unwrap_or((0.0, 0.0));
+            let left = styles.get("left").and_then(parse_numeric).unwrap_or(fallback.0);
+            let top = styles.get("top").and_then(parse_numeric).unwrap_or(fallback.1);
+            let mut updates = serde_json::Map::new();
+            if dx != 0.0 { updates.insert("left".to_string(), px(left + dx)); }
+            if dy != 0.0 { updates.insert("top".to_string(), px(top + dy)); }
+            commands.push(HyperCommand::update_style(id, serde_json::Value::Object(updates), view_mode));
+            continue;
+        }
+
+        let parent_id = match &el.parent_id { Some(p) => p.clone(), None => continue };
+        let step = match elements.get(&parent_id).and_then(|p| reorder_step(p, view_mode, dx, dy)) { Some(s) => s, None => continue };
+        match by_parent.iter_mut().find(|(p, _, _)| *p == parent_id) {
+            Some((_, _, list)) => list.push(id.clone()),
+            None => by_parent.push((parent_id, step, vec![id.clone()])),
+        }
+    }
+
+    // Move the leading element first so the block keeps its shape
+    for (parent_id, step, selected) in by_parent {
+        let mut order = elements[&parent_id].children.clone().unwrap_or_default();
+        let mut moving: Vec<usize> = selected.iter().filter_map(|id| order.iter().position(|c| c == id)).collect();
+        moving.sort();
+        if step > 0 { moving.reverse(); }
+
+        // The block stops at either end of the list
+        let blocked = match step {
+            s if s > 0 => moving.first().map(|i| i + 1 >= order.len()).unwrap_or(true),
+            _ => moving.first().map(|i| *i == 0).unwrap_or(true),
+        };
+        if blocked { continue; }
+
+        for index in moving {
+            let target = (index as i64 + step) as usize;
+            let id = order.remove(index);
+            order.insert(target, id.clone());
+            commands.push(HyperCommand::new("REORDER_ELEMENT", &id, serde_json::json!({ "parentId": parent_id, "newIndex": target })));
+        }
+    }
+
+    commands
+}
+
+/// Next (or previous) selectable sibling, wrapping around the parent's children.
+pub fn sibling(elements: &HashMap<String, DesignerElement>, id: &str, forward: bool) -> Option<String> {
+    let parent_id = elements.get(id)?.parent_id.clone()?;
+    let siblings = elements.get(&parent_id)?.children.clone().unwrap_or_default();
+    let start = siblings.iter().position(|s| s == id)?;
+    let n = siblings.len();
+    (1..n)
+        .map(|k| if forward { (start + k) % n } else { (start + n - k) % n })
+        .map(|i| &siblings[i])
+        .find(|s| is_selectable(elements, s))
+        .cloned()
+}
+
+pub fn first_child(elements: &HashMap<String, DesignerElement>, id: &str) -> Option<String> {
+    elements.get(id)?.children.as_ref()?.iter().find(|c| is_selectable(elements, c)).cloned()
+}
+
+/// The parent, unless it is a top-level element (page/root), which is never selected by navigation.
+pub fn parent(elements: &HashMap<String, DesignerElement>, id: &str) -> Option<String> {
+    let parent_id = elements.get(id)?.parent_id.clone()?;
+    elements.get(&parent_id)?.parent_id.as_ref()?;
+    Some(parent_id)
+}
+
+/// Where Tab starts when nothing is selected: the first child of the first top-level element.
+fn first_in_tree(elements: &HashMap<String, DesignerElement>) -> Option<String> {
+    let mut roots: Vec<&DesignerElement> = elements.values().filter(|el| el.parent_id.is_none()).collect();
+    roots.sort_by(|a, b| a.id.cmp(&b.id));
+    roots.iter().find_map(|root| first_child(elements, &root.id))
+}
+
+/// Resolves a shortcut action engine-side. Unknown actions come back with `handled: false`.
+pub fn perform(action: &str) -> ActionResult {
+    let mut result = ActionResult { action: action.to_string(), ..ActionResult::default() };
+
+    if let Some((dx, dy)) = nudge_delta(action) {
+        let ids = SELECTION.lock().unwrap().ids.clone();
+        // Insets from layout, for elements placed without explicit left/top
+        let parents: Vec<(String, Option<String>)> = {
+            let state_guard = PROJECT_STATE.lock().unwrap();
+            match state_guard.as_ref() {
+                Some(state) => ids.iter().filter_map(|id| state.elements.get(id).map(|el| (id.clone(), el.parent_id.clone()))).collect(),
+                None => return result,
+            }
+        };
+        let offsets: HashMap<String, (f32, f32)> = {
+            let index = SPATIAL_INDEX.lock().unwrap();
+            parents.iter().filter_map(|(id, parent_id)| {
+                let b = index.query_one(id)?;
+                let p = parent_id.as_ref().and_then(|pid| index.query_one(pid));
+                Some((id.clone(), p.map(|p| (b.min_x - p.min_x, b.min_y - p.min_y)).unwrap_or((b.min_x, b.min_y))))
+            }).collect()
+        };
+        let state_guard = PROJECT_STATE.lock().unwrap();
+        if let Some(state) = state_guard.as_ref() {
+            result.commands = nudge_commands(&state.elements, &state.view_mode, &ids, dx, dy, &offsets);
+        }
+        result.handled = true;
+        return result;
+    }
+
+    let state_guard = PROJECT_STATE.lock().unwrap();
+    let elements = match state_guard.as_ref() { Some(s) => &s.elements, None => return result };
+    let mut selection = SELECTION.lock().unwrap();
+    let primary = selection.primary.clone();
+
+    let next = match (action, primary.as_deref()) {
+        ("SelectNext", Some(id)) => sibling(elements, id, true),
+        ("SelectPrevious", Some(id)) => sibling(elements, id, false),
+        ("SelectNext", None) | ("SelectPrevious", None) => first_in_tree(elements),
+        ("SelectChild", Some(id)) => first_child(elements, id),
+        ("SelectParent", Some(id)) | ("Escape", Some(id)) => match parent(elements, id) {
+            Some(p) => Some(p),
+            None => {
+                // Escaping past the top level clears the selection
+                selection.clear();
+                result.handled = true;
+                return result;
+            }
+        },
+        _ => return result,
+    };
+
+    if let Some(id) = next {
+        selection.select(elements, &[id], false);
+    }
+    result.handled = true;
+    result
+}
+
+// --- WASM Exports ---
+
+/// Runs an action name returned by `handle_key_event`. Returns `{ action, handled, commands }`;
+/// selection changes show up in `get_selection_deltas`.
+#[wasm_bindgen]
+pub fn perform_action(action: &str) -> String {
+    serde_json::to_string(&perform(action)).unwrap_or("{}".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> HashMap<String, DesignerElement> {
+        let raw = serde_json::json!([
+            { "id": "root", "type": "page", "parentId": null, "children": ["row", "canvas"] },
+            { "id": "row", "type": "container", "parentId": "root", "children": ["a", "b", "c"] },
+            { "id": "a", "type": "box", "parentId": "row" },
+            { "id": "b", "type": "box", "parentId": "row", "locked": true },
+            { "id": "c", "type": "box", "parentId": "row" },
+            { "id": "canvas", "type": "container", "parentId": "root", "children": ["free"] },
+            { "id": "free", "type": "box", "parentId": "canvas", "layoutMode": "freedom", "styles": { "left": "20px" } }
+        ]);
+        let list: Vec<DesignerElement> = serde_json::from_value(raw).unwrap();
+        list.into_iter().map(|el| (el.id.clone(), el)).collect()
+    }
+
+    #[test]
+    fn test_nudge_moves_freedom_and_reorders_flex() {
+        let elements = tree();
+        let offsets = HashMap::from([("free".to_string(), (20.0, 35.0))]);
+
+        let commands = nudge_commands(&elements, "desktop", &["free".to_string()], NUDGE_LARGE, 0.0, &offsets);
+        assert_eq!(commands[0].payload["updates"]["left"], "30px");
+
+        let commands = nudge_commands(&elements, "desktop", &["free".to_string()], 0.0, -NUDGE_SMALL, &offsets);
+        assert_eq!(commands[0].payload["updates"]["top"], "34px");
+
+        // Right arrow in a row moves `a` one slot later; up arrow runs across the row and does nothing
+        let commands = nudge_commands(&elements, "desktop", &["a".to_string()], 1.0, 0.0, &offsets);
+        assert_eq!(commands[0].action, "REORDER_ELEMENT");
+        assert_eq!(commands[0].payload["newIndex"], 1);
+        assert!(nudge_commands(&elements, "desktop", &["a".to_string()], 0.0, -1.0, &offsets).is_empty());
+
+        // A block already at the end stays put
+        assert!(nudge_commands(&elements, "desktop", &["b".to_string(), "c".to_string()], 1.0, 0.0, &offsets).is_empty());
+        let commands = nudge_commands(&elements, "desktop", &["b".to_string(), "c".to_string()], -1.0, 0.0, &offsets);
+        let moves: Vec<_> = commands.iter().map(|c| (c.target_id.as_str(), c.payload["newIndex"].as_u64().unwrap())).collect();
+        assert_eq!(moves, vec![("b", 0), ("c", 1)]);
+    }
+
+    #[test]
+    fn test_tree_navigation() {
+        let elements = tree();
+        // Locked `b` is skipped, and Tab wraps around
+        assert_eq!(sibling(&elements, "a", true).as_deref(), Some("c"));
+        assert_eq!(sibling(&elements, "c", true).as_deref(), Some("a"));
+        assert_eq!(sibling(&elements, "a", false).as_deref(), Some("c"));
+        assert_eq!(first_child(&elements, "canvas").as_deref(), Some("free"));
+        assert_eq!(parent(&elements, "free").as_deref(), Some("canvas"));
+        assert_eq!(parent(&elements, "canvas"), None);
+        assert_eq!(nudge_delta("NudgeDownLarge"), Some((0.0, NUDGE_LARGE)));
+    }
+}
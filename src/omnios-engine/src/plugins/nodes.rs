@@ -0,0 +1,609 @@
+use crate::plugins::effects::{self, Effect};
+use crate::plugins::executions::{self, Iteration, WaitFor};
+use crate::plugins::expression;
+use crate::plugins::logic_kernel::{Control, LogicKernel, UnifiedBlueprint, UnifiedNode};
+use crate::plugins::variables::VariableScope;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// --- LOGIC NODE HANDLERS ---
+// Every node type the kernel runs is a NodeHandler: a spec (ports + data schema, used by the
+// validator and the editor) and an execute function. The built-ins below are registered the
+// same way plugins register theirs, through `PluginContext.nodes`.
+// A failing node continues on its `error` port when one is connected (every node has it),
+// else the nearest enclosing `try` catches it; otherwise it's an unhandled LogicError.
+
+/// Output port every node has: taken when the node fails, with the error in the `error` variable.
+pub const ERROR_PORT: &str = "error";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FieldKind {
+    String,
+    Number,
+    Boolean,
+    Object,
+    Array,
+    Expression, // Source evaluated by the expression language
+    Any,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldSpec {
+    pub name: String,
+    pub kind: FieldKind,
+    pub required: bool,
+    pub aliases: Vec<String>, // Accepted instead of `name` (e.g. `path` for `url`)
+}
+
+impl FieldSpec {
+    pub fn new(name: &str, kind: FieldKind) -> Self {
+        Self { name: name.to_string(), kind, required: false, aliases: Vec::new() }
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn or(mut self, alias: &str) -> Self {
+        self.aliases.push(alias.to_string());
+        self
+    }
+
+    /// The field and its aliases, in lookup order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name.as_str()).chain(self.aliases.iter().map(|a| a.as_str()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeSpec {
+    pub node_type: String,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    pub fields: Vec<FieldSpec>,
+}
+
+impl NodeSpec {
+    /// One `default` input and output, no fields.
+    pub fn new(node_type: &str) -> Self {
+        Self { node_type: node_type.to_string(), inputs: vec!["default".to_string()], outputs: vec!["default".to_string()], fields: Vec::new() }
+    }
+
+    pub fn outputs(mut self, ports: &[&str]) -> Self {
+        self.outputs = ports.iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    pub fn field(mut self, field: FieldSpec) -> Self {
+        self.fields.push(field);
+        self
+    }
+}
+
+/// Where execution goes after a node. The scope variants push a frame on the kernel's work
+/// stack; the node's own ports are followed once that frame is done.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Flow {
+    Next(String),    // Continue on this output port
+    Stop,            // End this branch (suspended, break, return, ...)
+    Loop(Iteration), // Run `body` once per iteration, then continue on `done`/untagged connections
+    Try,             // Run `body`; a failure in it continues on `catch` instead
+    Call(String, Map<String, Value>), // Run that blueprint's `on_call` nodes with these args; its return value goes to `resultVar`
+}
+
+impl Flow {
+    pub fn next() -> Self {
+        Flow::Next("default".to_string())
+    }
+}
+
+/// A node failure. `code` is machine-readable (`invalid_expression`, `type_mismatch`, ...);
+/// plain strings convert with the generic `node_failed`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NodeError {
+    pub code: String,
+    pub message: String,
+}
+
+impl NodeError {
+    pub fn new(code: &str, message: impl Into<String>) -> Self {
+        Self { code: code.to_string(), message: message.into() }
+    }
+}
+
+impl From<String> for NodeError {
+    fn from(message: String) -> Self {
+        Self::new("node_failed", message)
+    }
+}
+
+impl From<&str> for NodeError {
+    fn from(message: &str) -> Self {
+        Self::new("node_failed", message)
+    }
+}
+
+impl std::fmt::Display for NodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+pub trait NodeHandler: Send + Sync {
+    fn spec(&self) -> NodeSpec;
+
+    /// Errors go to the node's `error` port, an enclosing `try`, or are recorded as unhandled.
+    fn execute(&self, ctx: &mut NodeContext) -> Result<Flow, NodeError>;
+}
+
+#[derive(Clone, Default)]
+pub struct NodeRegistry {
+    handlers: HashMap<String, (NodeSpec, Arc<dyn NodeHandler>)>,
+}
+
+impl std::fmt::Debug for NodeRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.specs().iter().map(|s| &s.node_type)).finish()
+    }
+}
+
+impl NodeRegistry {
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::default();
+        registry.register(Arc::new(SetVar));
+        registry.register(Arc::new(Condition));
+        registry.register(Arc::new(ForEach));
+        registry.register(Arc::new(While));
+        registry.register(Arc::new(LoopControl("break")));
+        registry.register(Arc::new(LoopControl("continue")));
+        registry.register(Arc::new(Return));
+        registry.register(Arc::new(CallBlueprint));
+        registry.register(Arc::new(Try));
+        registry.register(Arc::new(Wait("wait")));
+        registry.register(Arc::new(Wait("delay")));
+        registry.register(Arc::new(AwaitEffect));
+        for node_type in EFFECT_NODES {
+            registry.register(Arc::new(EffectNode(node_type)));
+        }
+        registry
+    }
+
+    /// Adds or replaces the handler for its node type.
+    pub fn register(&mut self, handler: Arc<dyn NodeHandler>) {
+        let spec = handler.spec();
+        self.handlers.insert(spec.node_type.clone(), (spec, handler));
+    }
+
+    pub fn extend(&mut self, other: NodeRegistry) {
+        self.handlers.extend(other.handlers);
+    }
+
+    pub fn get(&self, node_type: &str) -> Option<Arc<dyn NodeHandler>> {
+        self.handlers.get(node_type).map(|(_, h)| h.clone())
+    }
+
+    pub fn spec(&self, node_type: &str) -> Option<&NodeSpec> {
+        self.handlers.get(node_type).map(|(s, _)| s)
+    }
+
+    /// All specs, sorted by node type.
+    pub fn specs(&self) -> Vec<&NodeSpec> {
+        let mut specs: Vec<&NodeSpec> = self.handlers.values().map(|(s, _)| s).collect();
+        specs.sort_by(|a, b| a.node_type.cmp(&b.node_type));
+        specs
+    }
+
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+}
+
+/// What a handler can see and do while its node runs.
+pub struct NodeContext<'a> {
+    pub(crate) kernel: &'a mut LogicKernel,
+    pub blueprint: &'a UnifiedBlueprint,
+    pub node: &'a UnifiedNode,
+    pub payload: &'a Value,
+}
+
+impl NodeContext<'_> {
+    pub fn data(&self, key: &str) -> Option<&Value> {
+        self.node.data.get(key).filter(|v| !v.is_null())
+    }
+
+    pub fn data_str(&self, key: &str) -> Option<&str> {
+        self.data(key).and_then(|v| v.as_str())
+    }
+
+    pub fn get_var(&self, name: &str) -> Option<Value> {
+        self.kernel.variables.get(self.blueprint, name).cloned()
+    }
+
+    /// Writes a variable (innermost existing scope unless `scope` is given). Fails, aborting the
+    /// run, if the value is over the size limits.
+    pub fn set_var(&mut self, name: &str, value: Value, scope: Option<VariableScope>) -> Result<(), String> {
+        self.kernel.assign(self.blueprint, &self.node.id, name, value, scope)
+    }
+
+    pub fn eval(&self, src: &str) -> Result<Value, NodeError> {
+        expression::eval_str(src, &self.kernel.scope(self.blueprint, self.payload))
+            .map_err(|e| NodeError::new("invalid_expression", e.to_string()))
+    }
+
+    /// Resolves `{{ expr }}` templates anywhere inside `value`.
+    pub fn resolve(&self, value: &Value) -> Result<Value, NodeError> {
+        effects::resolve_value(value, &self.kernel.scope(self.blueprint, self.payload))
+            .map_err(|e| NodeError::new("invalid_expression", e.to_string()))
+    }
+
+    /// Queues an effect for the host. Returns its id.
+    pub fn queue_effect(&mut self, effect: Effect) -> String {
+        self.kernel.queue_effect(self.blueprint, self.node, effect)
+    }
+
+    /// Suspends the branch at this node. The handler should return `Flow::Stop`.
+    pub fn suspend(&mut self, wait: WaitFor, after_secs: Option<f64>) -> String {
+        self.kernel.suspend(self.blueprint, self.node, self.payload, wait, after_secs)
+    }
+
+    /// Latest effect queued by `node_id`, or by this flow when None.
+    pub fn effect_of(&self, node_id: Option<&str>) -> Option<String> {
+        self.kernel.effect_of(node_id)
+    }
+
+    pub fn in_loop(&self) -> bool {
+        self.kernel.in_loop()
+    }
+
+    pub fn break_loop(&mut self) {
+        self.kernel.signal(Control::Break);
+    }
+
+    pub fn continue_loop(&mut self) {
+        self.kernel.signal(Control::Continue);
+    }
+
+    pub fn return_value(&mut self, value: Value) {
+        self.kernel.set_return(value);
+    }
+}
+
+// --- Built-ins ---
+
+/// `expression` is evaluated; otherwise the literal `value` field.
+fn value_or_expression(ctx: &NodeContext) -> Result<Value, NodeError> {
+    match ctx.data_str("expression") {
+        Some(src) => ctx.eval(src),
+        None => Ok(ctx.data("value").cloned().unwrap_or(Value::Null)),
+    }
+}
+
+struct SetVar;
+
+impl NodeHandler for SetVar {
+    fn spec(&self) -> NodeSpec {
+        NodeSpec::new("set_var")
+            .field(FieldSpec::new("varName", FieldKind::String).required())
+            .field(FieldSpec::new("expression", FieldKind::Expression))
+            .field(FieldSpec::new("value", FieldKind::Any))
+            .field(FieldSpec::new("scope", FieldKind::String))
+    }
+
+    fn execute(&self, ctx: &mut NodeContext) -> Result<Flow, NodeError> {
+        if let Some(name) = ctx.data_str("varName").map(String::from) {
+            // `expression` is evaluated (`count + 1`); `value` is stored as-is
+            let value = value_or_expression(ctx)?;
+            let scope = ctx.data_str("scope").and_then(VariableScope::parse);
+            ctx.set_var(&name, value, scope)?;
+        }
+        Ok(Flow::next())
+    }
+}
+
+struct Condition;
+
+impl NodeHandler for Condition {
+    fn spec(&self) -> NodeSpec {
+        NodeSpec::new("condition")
+            .outputs(&["true", "false"])
+            .field(FieldSpec::new("expression", FieldKind::Expression).required().or("left"))
+            .field(FieldSpec::new("right", FieldKind::Any))
+            .field(FieldSpec::new("operator", FieldKind::String))
+    }
+
+    fn execute(&self, ctx: &mut NodeContext) -> Result<Flow, NodeError> {
+        let result = match ctx.data_str("expression") {
+            Some(src) => expression::truthy(&ctx.eval(src)?),
+            None => {
+                // Literal comparison (older blueprints)
+                let left = ctx.data("left").unwrap_or(&Value::Null);
+                let right = ctx.data("right").unwrap_or(&Value::Null);
+                match ctx.data_str("operator").unwrap_or("==") {
+                    "==" => expression::loose_eq(left, right),
+                    "!=" => !expression::loose_eq(left, right),
+                    _ => false,
+                }
+            }
+        };
+        Ok(Flow::Next(if result { "true" } else { "false" }.to_string()))
+    }
+}
+
+struct ForEach;
+
+impl NodeHandler for ForEach {
+    fn spec(&self) -> NodeSpec {
+        NodeSpec::new("for_each")
+            .outputs(&["body", "done", "default"])
+            .field(FieldSpec::new("items", FieldKind::Expression).required())
+            .field(FieldSpec::new("itemVar", FieldKind::String))
+            .field(FieldSpec::new("indexVar", FieldKind::String))
+    }
+
+    fn execute(&self, ctx: &mut NodeContext) -> Result<Flow, NodeError> {
+        let items = match ctx.data("items") {
+            Some(Value::String(src)) => ctx.eval(src)?,
+            Some(literal) => literal.clone(),
+            None => Value::Null,
+        };
+        let items = match items {
+            Value::Array(items) => items,
+            Value::Null => Vec::new(),
+            other => return Err(NodeError::new("type_mismatch", format!("for_each needs an array, got {}", expression::type_name(&other)))),
+        };
+        let item_var = ctx.data_str("itemVar").unwrap_or("item").to_string();
+        let index_var = ctx.data_str("indexVar").unwrap_or("index").to_string();
+        Ok(Flow::Loop(Iteration::Items { items, next: 0, item_var, index_var }))
+    }
+}
+
+struct While;
+
+impl NodeHandler for While {
+    fn spec(&self) -> NodeSpec {
+        NodeSpec::new("while")
+            .outputs(&["body", "done", "default"])
+            .field(FieldSpec::new("condition", FieldKind::Expression).required().or("expression"))
+    }
+
+    fn execute(&self, ctx: &mut NodeContext) -> Result<Flow, NodeError> {
+        let condition = ctx.data_str("condition").or_else(|| ctx.data_str("expression"))
+            .ok_or(NodeError::new("missing_field", "while needs a 'condition' expression"))?
+            .to_string();
+        Ok(Flow::Loop(Iteration::While { condition }))
+    }
+}
+
+struct LoopControl(&'static str);
+
+impl NodeHandler for LoopControl {
+    fn spec(&self) -> NodeSpec {
+        NodeSpec::new(self.0).outputs(&[])
+    }
+
+    fn execute(&self, ctx: &mut NodeContext) -> Result<Flow, NodeError> {
+        if !ctx.in_loop() {
+            return Err(format!("'{}' outside of a loop", self.0).into());
+        }
+        if self.0 == "break" { ctx.break_loop() } else { ctx.continue_loop() }
+        Ok(Flow::Stop)
+    }
+}
+
+struct Return;
+
+impl NodeHandler for Return {
+    fn spec(&self) -> NodeSpec {
+        NodeSpec::new("return")
+            .outputs(&[])
+            .field(FieldSpec::new("expression", FieldKind::Expression))
+            .field(FieldSpec::new("value", FieldKind::Any))
+    }
+
+    fn execute(&self, ctx: &mut NodeContext) -> Result<Flow, NodeError> {
+        let value = value_or_expression(ctx)?;
+        ctx.return_value(value);
+        Ok(Flow::Stop)
+    }
+}
+
+/// Runs another blueprint's `on_call` nodes in a fresh execution scope seeded with `args`
+/// (also visible as `payload`). Its `return` value is stored in `resultVar`.
+struct CallBlueprint;
+
+impl NodeHandler for CallBlueprint {
+    fn spec(&self) -> NodeSpec {
+        NodeSpec::new("call_blueprint")
+            .field(FieldSpec::new("blueprintId", FieldKind::String).required())
+            .field(FieldSpec::new("args", FieldKind::Object))
+            .field(FieldSpec::new("resultVar", FieldKind::String))
+    }
+
+    fn execute(&self, ctx: &mut NodeContext) -> Result<Flow, NodeError> {
+        let target = ctx.data_str("blueprintId").ok_or(NodeError::new("missing_field", "call_blueprint needs 'blueprintId'"))?.to_string();
+        let args = match ctx.resolve(ctx.data("args").unwrap_or(&Value::Null))? {
+            Value::Object(args) => args,
+            _ => Map::new(),
+        };
+        Ok(Flow::Call(target, args))
+    }
+}
+
+/// Runs its `body` subgraph; a failure in it that no `error` port handles stops the body and
+/// continues on `catch`, with the error object in the `error` variable.
+struct Try;
+
+impl NodeHandler for Try {
+    fn spec(&self) -> NodeSpec {
+        NodeSpec::new("try").outputs(&["body", "catch", "default"])
+    }
+
+    fn execute(&self, _ctx: &mut NodeContext) -> Result<Flow, NodeError> {
+        Ok(Flow::Try)
+    }
+}
+
+struct Wait(&'static str);
+
+impl NodeHandler for Wait {
+    fn spec(&self) -> NodeSpec {
+        NodeSpec::new(self.0)
+            .field(FieldSpec::new("duration", FieldKind::Number))
+            .field(FieldSpec::new("seconds", FieldKind::Number))
+    }
+
+    fn execute(&self, ctx: &mut NodeContext) -> Result<Flow, NodeError> {
+        // `duration` in ms (as the TS engine), or `seconds`
+        let secs = executions::duration_secs(&ctx.node.data, "duration", "seconds").unwrap_or(1.0);
+        ctx.suspend(WaitFor::Delay, Some(secs));
+        Ok(Flow::Stop)
+    }
+}
+
+struct AwaitEffect;
+
+impl NodeHandler for AwaitEffect {
+    fn spec(&self) -> NodeSpec {
+        NodeSpec::new("await_effect")
+            .outputs(&["default", "timeout"])
+            .field(FieldSpec::new("effectNode", FieldKind::String))
+            .field(FieldSpec::new("timeout", FieldKind::Number))
+            .field(FieldSpec::new("timeoutSeconds", FieldKind::Number))
+            .field(FieldSpec::new("resultVar", FieldKind::String))
+    }
+
+    fn execute(&self, ctx: &mut NodeContext) -> Result<Flow, NodeError> {
+        // Waits on `effectNode`'s latest effect, else the last effect this flow queued
+        let effect_id = ctx.effect_of(ctx.data_str("effectNode"));
+        let timeout = executions::duration_secs(&ctx.node.data, "timeout", "timeoutSeconds");
+        ctx.suspend(WaitFor::Effect { effect_id }, timeout);
+        Ok(Flow::Stop)
+    }
+}
+
+const EFFECT_NODES: [&str; 9] = [
+    "navigate", "alert", "show_alert", "api_request", "http_request",
+    "set_style", "set_element_style", "play_animation", "emit_event",
+];
+
+/// Effect nodes (navigate, alert, api_request, ...) queue a typed effect for the host.
+struct EffectNode(&'static str);
+
+impl NodeHandler for EffectNode {
+    fn spec(&self) -> NodeSpec {
+        let spec = NodeSpec::new(self.0);
+        match self.0 {
+            "navigate" => spec
+                .field(FieldSpec::new("url", FieldKind::String).required().or("path"))
+                .field(FieldSpec::new("newTab", FieldKind::Boolean)),
+            "alert" | "show_alert" => spec
+                .field(FieldSpec::new("message", FieldKind::String))
+                .field(FieldSpec::new("level", FieldKind::String)),
+            "api_request" | "http_request" => spec
+                .field(FieldSpec::new("url", FieldKind::String).required())
+                .field(FieldSpec::new("method", FieldKind::String))
+                .field(FieldSpec::new("headers", FieldKind::Object))
+                .field(FieldSpec::new("body", FieldKind::Any))
+                .field(FieldSpec::new("resultVar", FieldKind::String).or("outputVar")),
+            "set_style" | "set_element_style" => spec
+                .field(FieldSpec::new("styles", FieldKind::Object).required())
+                .field(FieldSpec::new("elementId", FieldKind::String).or("targetId")),
+            "play_animation" => spec
+                .field(FieldSpec::new("elementId", FieldKind::String).or("targetId"))
+                .field(FieldSpec::new("animation", FieldKind::String)),
+            "emit_event" => spec
+                .field(FieldSpec::new("name", FieldKind::String).required().or("event"))
+                .field(FieldSpec::new("payload", FieldKind::Any)),
+            _ => spec,
+        }
+    }
+
+    fn execute(&self, ctx: &mut NodeContext) -> Result<Flow, NodeError> {
+        let data = ctx.resolve(&ctx.node.data)?;
+        match effects::effect_from_node(self.0, &data, ctx.payload) {
+            Some(effect) => {
+                ctx.queue_effect(effect?);
+                Ok(Flow::next())
+            }
+            None => Err(format!("'{}' is not an effect node", self.0).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sdk::{OmniosPlugin, PluginContext, PluginRegistry};
+    use serde_json::json;
+
+    /// A plugin node: doubles `input` into `output`, or fails on non-numbers.
+    struct Double;
+
+    impl NodeHandler for Double {
+        fn spec(&self) -> NodeSpec {
+            NodeSpec::new("double")
+                .outputs(&["ok"])
+                .field(FieldSpec::new("input", FieldKind::Expression).required())
+                .field(FieldSpec::new("output", FieldKind::String).required())
+        }
+
+        fn execute(&self, ctx: &mut NodeContext) -> Result<Flow, NodeError> {
+            let input = ctx.eval(ctx.data_str("input").unwrap_or("null"))?;
+            let n = input.as_f64().ok_or_else(|| format!("double needs a number, got {}", expression::type_name(&input)))?;
+            let output = ctx.data_str("output").unwrap_or("result").to_string();
+            ctx.set_var(&output, json!(n * 2.0), None)?;
+            Ok(Flow::Next("ok".to_string()))
+        }
+    }
+
+    struct MathPlugin;
+
+    impl OmniosPlugin for MathPlugin {
+        fn name(&self) -> &str { "Math" }
+        fn on_register(&mut self, context: &mut PluginContext) {
+            context.nodes.register(Arc::new(Double));
+        }
+    }
+
+    #[test]
+    fn test_plugin_node_types() {
+        let mut plugins = PluginRegistry::new();
+        plugins.register(Box::new(MathPlugin));
+
+        let mut kernel = LogicKernel::new();
+        kernel.nodes.extend(plugins.nodes().clone());
+        assert_eq!(kernel.nodes.spec("double").unwrap().outputs, vec!["ok"]);
+
+        let bp: UnifiedBlueprint = serde_json::from_value(json!({
+            "id": "bp", "variables": { "total": 0 },
+            "nodes": {
+                "start": { "id": "start", "type": "on_click", "data": {} },
+                "dbl": { "id": "dbl", "type": "double", "data": { "input": "payload.n", "output": "total" } },
+                "mark": { "id": "mark", "type": "set_var", "data": { "varName": "seen", "value": true } }
+            },
+            "connections": [
+                { "id": "c1", "fromId": "start", "toId": "dbl" },
+                { "id": "c2", "fromId": "dbl", "toId": "mark", "port": "ok" }
+            ]
+        })).unwrap();
+        kernel.register_blueprint(bp);
+
+        kernel.execute("bp", "on_click", &json!({ "n": 21 })).unwrap();
+        assert_eq!(kernel.variables.blueprint["bp"].get("total"), Some(&json!(42.0)));
+        assert_eq!(kernel.variables.execution.get("seen"), Some(&json!(true)));
+
+        kernel.execute("bp", "on_click", &json!({ "n": "x" })).unwrap();
+        assert_eq!(kernel.errors[0].node_id, "dbl");
+        assert!(kernel.variables.execution.get("seen").is_none());
+    }
+}
@@ -85,8 +85,7 @@ impl OmniosPlugin for RigidBodyPlugin {
     
     fn on_register(&mut self, _context: &mut PluginContext) {}
 
-    fn render(&self, _ctx: &RenderContext) {
-        // Step physics at 60fps assumption (dt = 0.016)
-        self.step(0.016);
+    fn render(&self, ctx: &RenderContext) {
+        self.step(ctx.dt);
     }
 }
@@ -0,0 +1,277 @@
+use crate::plugins::camera::CAMERA;
+use crate::plugins::selection::ancestors;
+use crate::plugins::spatial_index::SPATIAL_INDEX;
+use crate::PROJECT_STATE;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::sync::Mutex;
+use wasm_bindgen::prelude::*;
+
+// --- POINTER TRACKING ---
+// Keeps the stack of elements under the cursor (outermost first) and turns raw pointer
+// input into per-element enter/leave/down/up/click/double-click events. Every event is
+// delivered to the receiving element's blueprint as a logic trigger.
+
+lazy_static! {
+    static ref POINTER_TRACKER: Mutex<PointerTracker> = Mutex::new(PointerTracker::default());
+}
+
+/// Two clicks on the same element within this window form a double click.
+pub const DOUBLE_CLICK_MS: f64 = 300.0;
+/// ...as long as the pointer stayed within this many screen pixels.
+pub const DOUBLE_CLICK_SLOP: f32 = 5.0;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PointerEventKind {
+    Enter,
+    Leave,
+    Down,
+    Up,
+    Click,
+    DoubleClick,
+}
+
+impl PointerEventKind {
+    pub fn trigger_name(self) -> &'static str {
+        match self {
+            PointerEventKind::Enter => "on_hover",
+            PointerEventKind::Leave => "on_hover_end",
+            PointerEventKind::Down => "on_pointer_down",
+            PointerEventKind::Up => "on_pointer_up",
+            PointerEventKind::Click => "on_click",
+            PointerEventKind::DoubleClick => "on_double_click",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PointerEvent {
+    pub kind: PointerEventKind,
+    pub element_id: String, // Element receiving the event
+    pub target_id: String,  // Deepest element under the pointer
+    pub x: f32,             // World coordinates
+    pub y: f32,
+}
+
+#[derive(Default, Debug)]
+pub struct PointerTracker {
+    pub hover: Vec<String>, // Outermost first
+    pressed: Option<Vec<String>>,
+    last_click: Option<(String, f64, f32, f32)>, // target, time, screen x/y
+}
+
+impl PointerTracker {
+    fn events(kind: PointerEventKind, ids: &[String], target: &str, x: f32, y: f32) -> Vec<PointerEvent> {
+        ids.iter().map(|id| PointerEvent { kind, element_id: id.clone(), target_id: target.to_string(), x, y }).collect()
+    }
+
+    /// Updates the hover stack. Leaves fire innermost first, enters outermost first (like mouseleave/mouseenter).
+    pub fn move_to(&mut self, stack: Vec<String>, x: f32, y: f32) -> Vec<PointerEvent> {
+        let shared = self.hover.iter().zip(&stack).take_while(|(a, b)| a == b).count();
+        let target = stack.last().or(self.hover.last()).cloned().unwrap_or_default();
+
+        let left: Vec<String> = self.hover[shared..].iter().rev().cloned().collect();
+        let entered: Vec<String> = stack[shared..].to_vec();
+
+        let mut out = Self::events(PointerEventKind::Leave, &left, &target, x, y);
+        out.extend(Self::events(PointerEventKind::Enter, &entered, &target, x, y));
+        self.hover = stack;
+        out
+    }
+
+    /// Down and up bubble from the deepest element outwards.
+    pub fn down(&mut self, stack: Vec<String>, x: f32, y: f32) -> Vec<PointerEvent> {
+        let mut out = self.move_to(stack.clone(), x, y);
+        let target = stack.last().cloned().unwrap_or_default();
+        let bubble: Vec<String> = stack.iter().rev().cloned().collect();
+        out.extend(Self::events(PointerEventKind::Down, &bubble, &target, x, y));
+        self.pressed = Some(stack);
+        out
+    }
+
+    /// `screen` is the pointer in screen pixels (for the double-click slop), `time_ms` a host timestamp.
+    pub fn up(&mut self, stack: Vec<String>, x: f32, y: f32, screen: (f32, f32), time_ms: f64) -> Vec<PointerEvent> {
+        let mut out = self.move_to(stack.clone(), x, y);
+        let target = stack.last().cloned().unwrap_or_default();
+        let bubble: Vec<String> = stack.iter().rev().cloned().collect();
+        out.extend(Self::events(PointerEventKind::Up, &bubble, &target, x, y));
+
+        // A click lands on the deepest element that was under the pointer both at down and up
+        let pressed = self.pressed.take().unwrap_or_default();
+        let common = pressed.iter().zip(&stack).take_while(|(a, b)| a == b).count();
+        if common == 0 { return out; }
+
+        let clicked: Vec<String> = stack[..common].iter().rev().cloned().collect();
+        let click_target = clicked[0].clone();
+        out.extend(Self::events(PointerEventKind::Click, &clicked, &click_target, x, y));
+
+        let is_double = match &self.last_click {
+            Some((id, t, sx, sy)) => {
+                *id == click_target
+                    && time_ms - t <= DOUBLE_CLICK_MS
+                    && (screen.0 - sx).abs() <= DOUBLE_CLICK_SLOP
+                    && (screen.1 - sy).abs() <= DOUBLE_CLICK_SLOP
+            }
+            None => false,
+        };
+        if is_double {
+            out.extend(Self::events(PointerEventKind::DoubleClick, &clicked, &click_target, x, y));
+            self.last_click = None; // A third click starts over
+        } else {
+            self.last_click = Some((click_target, time_ms, screen.0, screen.1));
+        }
+        out
+    }
+
+    /// The pointer left the canvas.
+    pub fn leave(&mut self) -> Vec<PointerEvent> {
+        self.pressed = None;
+        self.move_to(Vec::new(), 0.0, 0.0)
+    }
+}
+
+/// Hit element plus its ancestors, outermost first, at a world point.
+fn hover_stack_at(x: f32, y: f32) -> Vec<String> {
+    let hit = match SPATIAL_INDEX.lock().unwrap().hit_test(x, y) {
+        Some(id) => id,
+        None => return Vec::new(),
+    };
+    let state_guard = PROJECT_STATE.lock().unwrap();
+    let mut stack = match state_guard.as_ref() {
+        Some(state) => ancestors(&state.elements, &hit),
+        None => Vec::new(),
+    };
+    stack.reverse();
+    stack.push(hit);
+    stack
+}
+
+/// Fires each event's trigger on the receiving element's blueprint, then returns the events as JSON.
+fn dispatch(events: Vec<PointerEvent>) -> String {
+    // Resolve blueprints first: logic may read project state, so never run it under that lock
+    let routed: Vec<(String, &PointerEvent)> = {
+        let state_guard = PROJECT_STATE.lock().unwrap();
+        match state_guard.as_ref() {
+            Some(state) => events.iter()
+                .filter_map(|e| state.elements.get(&e.element_id).and_then(|el| el.blueprint_id.clone()).map(|bp| (bp, e)))
+                .collect(),
+            None => Vec::new(),
+        }
+    };
+
+    for (bp, event) in routed {
+        let payload = serde_json::json!({
+            "elementId": event.element_id,
+            "targetId": event.target_id,
+            "x": event.x,
+            "y": event.y,
+        });
+        crate::run_logic(&bp, event.kind.trigger_name(), &payload);
+    }
+    serde_json::to_string(&events).unwrap_or("[]".to_string())
+}
+
+fn world(x: f32, y: f32) -> (f32, f32) {
+    CAMERA.lock().unwrap().screen_to_world(x, y)
+}
+
+/// Deepest element under the pointer.
+pub fn hovered_element() -> Option<String> {
+    POINTER_TRACKER.lock().unwrap().hover.last().cloned()
+}
+
+// --- WASM Exports ---
+// Coordinates are screen pixels. Each call returns the pointer events it fired.
+
+#[wasm_bindgen]
+pub fn pointer_move(x: f32, y: f32) -> String {
+    let (wx, wy) = world(x, y);
+    let stack = hover_stack_at(wx, wy);
+    let events = POINTER_TRACKER.lock().unwrap().move_to(stack, wx, wy);
+    dispatch(events)
+}
+
+#[wasm_bindgen]
+pub fn pointer_down(x: f32, y: f32) -> String {
+    let (wx, wy) = world(x, y);
+    let stack = hover_stack_at(wx, wy);
+    let events = POINTER_TRACKER.lock().unwrap().down(stack, wx, wy);
+    dispatch(events)
+}
+
+/// `time_ms` is the host event timestamp, used for double-click detection.
+#[wasm_bindgen]
+pub fn pointer_up(x: f32, y: f32, time_ms: f64) -> String {
+    let (wx, wy) = world(x, y);
+    let stack = hover_stack_at(wx, wy);
+    let events = POINTER_TRACKER.lock().unwrap().up(stack, wx, wy, (x, y), time_ms);
+    dispatch(events)
+}
+
+#[wasm_bindgen]
+pub fn pointer_leave() -> String {
+    let events = POINTER_TRACKER.lock().unwrap().leave();
+    dispatch(events)
+}
+
+/// Hovered element ids, outermost first.
+#[wasm_bindgen]
+pub fn get_hover_stack() -> String {
+    serde_json::to_string(&POINTER_TRACKER.lock().unwrap().hover).unwrap_or("[]".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn kinds(events: &[PointerEvent]) -> Vec<(PointerEventKind, &str)> {
+        events.iter().map(|e| (e.kind, e.element_id.as_str())).collect()
+    }
+
+    #[test]
+    fn test_enter_and_leave_follow_the_stack() {
+        let mut tracker = PointerTracker::default();
+        let events = tracker.move_to(stack(&["root", "card"]), 0.0, 0.0);
+        assert_eq!(kinds(&events), vec![(PointerEventKind::Enter, "root"), (PointerEventKind::Enter, "card")]);
+
+        // Moving from the card into its button only enters the button
+        let events = tracker.move_to(stack(&["root", "card", "button"]), 0.0, 0.0);
+        assert_eq!(kinds(&events), vec![(PointerEventKind::Enter, "button")]);
+
+        // Jumping to a sibling leaves innermost first
+        let events = tracker.move_to(stack(&["root", "footer"]), 0.0, 0.0);
+        assert_eq!(kinds(&events), vec![
+            (PointerEventKind::Leave, "button"),
+            (PointerEventKind::Leave, "card"),
+            (PointerEventKind::Enter, "footer"),
+        ]);
+        assert_eq!(events[0].target_id, "footer");
+    }
+
+    #[test]
+    fn test_click_and_double_click() {
+        let mut tracker = PointerTracker::default();
+        tracker.down(stack(&["root", "card", "button"]), 0.0, 0.0);
+        // Released over the card but outside the button: only the card (and root) are clicked
+        let events = tracker.up(stack(&["root", "card"]), 0.0, 0.0, (10.0, 10.0), 0.0);
+        let clicks: Vec<_> = kinds(&events).into_iter().filter(|(k, _)| *k == PointerEventKind::Click).collect();
+        assert_eq!(clicks, vec![(PointerEventKind::Click, "card"), (PointerEventKind::Click, "root")]);
+
+        tracker.down(stack(&["root", "card"]), 0.0, 0.0);
+        let events = tracker.up(stack(&["root", "card"]), 0.0, 0.0, (12.0, 10.0), 200.0);
+        assert!(events.iter().any(|e| e.kind == PointerEventKind::DoubleClick && e.element_id == "card"));
+
+        // Too slow for another double click
+        tracker.down(stack(&["root", "card"]), 0.0, 0.0);
+        tracker.up(stack(&["root", "card"]), 0.0, 0.0, (12.0, 10.0), 300.0);
+        tracker.down(stack(&["root", "card"]), 0.0, 0.0);
+        let events = tracker.up(stack(&["root", "card"]), 0.0, 0.0, (12.0, 10.0), 900.0);
+        assert!(!events.iter().any(|e| e.kind == PointerEventKind::DoubleClick));
+    }
+}
@@ -0,0 +1,494 @@
+use crate::{HyperCommand, PROJECT_STATE};
+use crate::plugins::animation::parse_numeric;
+use crate::plugins::camera::{Camera, CAMERA};
+use crate::plugins::interaction::{column_lines, grid_lines, Rect, SnapConfig, SnapGuide, SNAP_CONFIG};
+use crate::plugins::spatial_index::SPATIAL_INDEX;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use wasm_bindgen::prelude::*;
+
+// --- RESIZE ENGINE ---
+// A resize gesture snapshots the start bounds of every target, then each pointer update
+// recomputes the group box from that snapshot (never incrementally, so rounding does not drift).
+// Every target is scaled proportionally inside the group box and emitted as UPDATE_STYLE.
+// Pointer deltas and snap distances arrive in screen pixels and are divided by the camera zoom,
+// as drag snapping does, so the handle stays under the pointer at any zoom.
+
+lazy_static! {
+    static ref RESIZE_SESSION: Mutex<Option<ResizeSession>> = Mutex::new(None);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeHandle {
+    TopLeft,
+    Top,
+    TopRight,
+    Right,
+    BottomRight,
+    Bottom,
+    BottomLeft,
+    Left,
+}
+
+impl ResizeHandle {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nw" | "top-left" | "topLeft" => Some(ResizeHandle::TopLeft),
+            "n" | "top" => Some(ResizeHandle::Top),
+            "ne" | "top-right" | "topRight" => Some(ResizeHandle::TopRight),
+            "e" | "right" => Some(ResizeHandle::Right),
+            "se" | "bottom-right" | "bottomRight" => Some(ResizeHandle::BottomRight),
+            "s" | "bottom" => Some(ResizeHandle::Bottom),
+            "sw" | "bottom-left" | "bottomLeft" => Some(ResizeHandle::BottomLeft),
+            "w" | "left" => Some(ResizeHandle::Left),
+            _ => None,
+        }
+    }
+
+    /// Which edges the handle drags: -1 = min edge, 1 = max edge, 0 = neither.
+    pub fn direction(self) -> (i8, i8) {
+        match self {
+            ResizeHandle::TopLeft => (-1, -1),
+            ResizeHandle::Top => (0, -1),
+            ResizeHandle::TopRight => (1, -1),
+            ResizeHandle::Right => (1, 0),
+            ResizeHandle::BottomRight => (1, 1),
+            ResizeHandle::Bottom => (0, 1),
+            ResizeHandle::BottomLeft => (-1, 1),
+            ResizeHandle::Left => (-1, 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResizeModifiers {
+    pub shift: bool, // Keep aspect ratio
+    pub alt: bool,   // Resize from center
+}
+
+#[derive(Debug, Clone)]
+pub struct ResizeTarget {
+    pub id: String,
+    pub bounds: Rect,
+    pub parent: Option<Rect>,
+    pub positioned: bool, // Freedom/absolute children also get new left/top insets
+    pub min_size: (f32, f32),
+    pub max_size: (f32, f32),
+}
+
+#[derive(Debug, Clone)]
+pub struct ResizeSession {
+    pub handle: ResizeHandle,
+    pub targets: Vec<ResizeTarget>,
+    pub group: Rect,
+    pub others: Vec<Rect>, // Snap candidates around the group
+    pub view_mode: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResizeResult {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub commands: Vec<HyperCommand>,
+    pub guides: Vec<SnapGuide>,
+}
+
+const MIN_SIZE: f32 = 1.0;
+
+fn width(r: &Rect) -> f32 { r.max_x - r.min_x }
+fn height(r: &Rect) -> f32 { r.max_y - r.min_y }
+
+impl ResizeSession {
+    pub fn new(handle: ResizeHandle, targets: Vec<ResizeTarget>, others: Vec<Rect>, view_mode: &str) -> Option<Self> {
+        let first = targets.first()?.bounds;
+        let group = targets.iter().fold(first, |g, t| Rect {
+            min_x: g.min_x.min(t.bounds.min_x),
+            min_y: g.min_y.min(t.bounds.min_y),
+            max_x: g.max_x.max(t.bounds.max_x),
+            max_y: g.max_y.max(t.bounds.max_y),
+        });
+        Some(Self { handle, targets, group, others, view_mode: view_mode.to_string() })
+    }
+
+    /// Allowed range for one side of the group box, from every target's min/max size.
+    fn size_range(&self, vertical: bool) -> (f32, f32) {
+        let group_size = if vertical { height(&self.group) } else { width(&self.group) };
+        let mut lo = MIN_SIZE;
+        let mut hi = f32::MAX;
+        for t in &self.targets {
+            let size = if vertical { height(&t.bounds) } else { width(&t.bounds) };
+            if size <= 0.0 { continue; }
+            let (min, max) = if vertical { (t.min_size.1, t.max_size.1) } else { (t.min_size.0, t.max_size.0) };
+            lo = lo.max(group_size * min.max(MIN_SIZE) / size);
+            hi = hi.min(group_size * max / size);
+        }
+        (lo, hi.max(lo))
+    }
+
+    /// The shared parent's box, when every target has the same one.
+    fn shared_parent(&self) -> Option<Rect> {
+        let parent = self.targets.first()?.parent?;
+        self.targets.iter().all(|t| t.parent == Some(parent)).then_some(parent)
+    }
+
+    /// New group box for a pointer delta measured from the start of the gesture.
+    pub fn resize_box(&self, dx: f32, dy: f32, mods: ResizeModifiers, config: &SnapConfig) -> (Rect, Vec<SnapGuide>) {
+        let g = self.group;
+        let (gw, gh) = (width(&g), height(&g));
+        let (cx, cy) = ((g.min_x + g.max_x) / 2.0, (g.min_y + g.max_y) / 2.0);
+        let (hx, hy) = self.handle.direction();
+        let (hxf, hyf) = (hx as f32, hy as f32);
+        let parent = self.shared_parent();
+
+        // Fixed point of each axis: the opposite edge, or the center with alt
+        let anchor = |h: i8, min: f32, max: f32, center: f32| {
+            if mods.alt || h == 0 { center } else if h > 0 { min } else { max }
+        };
+        let ax = anchor(hx, g.min_x, g.max_x, cx);
+        let ay = anchor(hy, g.min_y, g.max_y, cy);
+        let factor = if mods.alt { 2.0 } else { 1.0 };
+
+        let mut w = if hx != 0 { gw + hxf * dx * factor } else { gw };
+        let mut h = if hy != 0 { gh + hyf * dy * factor } else { gh };
+
+        // Snap the dragged edges
+        let mut guides = Vec::new();
+        let edge_from = |anchor: f32, size: f32, dir: f32| if mods.alt { anchor + dir * size / 2.0 } else { anchor + dir * size };
+        let size_from = |anchor: f32, edge: f32, dir: f32| if mods.alt { (edge - anchor) * dir * 2.0 } else { (edge - anchor) * dir };
+
+        if hx != 0 {
+            let lines = self.snap_lines(false, edge_from(ax, w, hxf), parent, config);
+            if let Some(line) = nearest(edge_from(ax, w, hxf), &lines, config.threshold) {
+                w = size_from(ax, line, hxf);
+                guides.push(self.edge_guide("vertical", line, false));
+            }
+        }
+        if hy != 0 {
+            let lines = self.snap_lines(true, edge_from(ay, h, hyf), parent, config);
+            if let Some(line) = nearest(edge_from(ay, h, hyf), &lines, config.threshold) {
+                h = size_from(ay, line, hyf);
+                guides.push(self.edge_guide("horizontal", line, true));
+            }
+        }
+
+        // Aspect lock: corners follow the axis that moved most, edges drive the other side
+        let locked = mods.shift && gw > 0.0 && gh > 0.0;
+        if locked {
+            let (sx, sy) = (w / gw, h / gh);
+            let s = match (hx, hy) {
+                (0, _) => sy,
+                (_, 0) => sx,
+                _ => if (sx - 1.0).abs() >= (sy - 1.0).abs() { sx } else { sy },
+            };
+            w = gw * s;
+            h = gh * s;
+        }
+
+        // Min/max sizes, then parent containment (measured from the fixed point)
+        let (mut lo_w, mut hi_w) = self.size_range(false);
+        let (mut lo_h, mut hi_h) = self.size_range(true);
+        if let Some(p) = parent {
+            let room = |h: i8, anchor: f32, min: f32, max: f32| {
+                if mods.alt || h == 0 { 2.0 * (anchor - min).min(max - anchor) } else if h > 0 { max - anchor } else { anchor - min }
+            };
+            hi_w = hi_w.min(room(hx, ax, p.min_x, p.max_x).max(MIN_SIZE));
+            hi_h = hi_h.min(room(hy, ay, p.min_y, p.max_y).max(MIN_SIZE));
+            lo_w = lo_w.min(hi_w);
+            lo_h = lo_h.min(hi_h);
+        }
+
+        if locked {
+            let s = (w / gw).min((hi_w / gw).min(hi_h / gh)).max((lo_w / gw).max(lo_h / gh));
+            w = gw * s;
+            h = gh * s;
+        } else {
+            w = w.min(hi_w).max(lo_w);
+            h = h.min(hi_h).max(lo_h);
+        }
+
+        let place = |h: i8, anchor: f32, size: f32| {
+            if mods.alt || h == 0 { anchor - size / 2.0 } else if h > 0 { anchor } else { anchor - size }
+        };
+        let mut rect = Rect { min_x: place(hx, ax, w), min_y: place(hy, ay, h), max_x: 0.0, max_y: 0.0 };
+        rect.max_x = rect.min_x + w;
+        rect.max_y = rect.min_y + h;
+        if config.pixel_grid {
+            rect = Rect { min_x: rect.min_x.round(), min_y: rect.min_y.round(), max_x: rect.max_x.round(), max_y: rect.max_y.round() };
+        }
+
+        // Constraints may have pulled the edge off its snap line
+        guides.retain(|guide| {
+            let edges = if guide.orientation == "vertical" { [rect.min_x, rect.max_x] } else { [rect.min_y, rect.max_y] };
+            edges.iter().any(|e| (e - guide.value).abs() < 0.5)
+        });
+        (rect, guides)
+    }
+
+    fn snap_lines(&self, vertical: bool, edge: f32, parent: Option<Rect>, config: &SnapConfig) -> Vec<f32> {
+        let mut lines = Vec::new();
+        if config.objects {
+            for o in &self.others {
+                let (a, b) = if vertical { (o.min_y, o.max_y) } else { (o.min_x, o.max_x) };
+                lines.extend([a, (a + b) / 2.0, b]);
+            }
+        }
+        if let Some(size) = config.grid_size {
+            lines.extend(grid_lines(0.0, size, (edge, edge)));
+        }
+        match (vertical, parent) {
+            (false, Some(p)) => if let Some(columns) = &config.columns { lines.extend(column_lines(&p, columns)); },
+            (true, Some(p)) => if let Some(step) = config.baseline { lines.extend(grid_lines(p.min_y, step, (edge, edge))); },
+            _ => {}
+        }
+        lines
+    }
+
+    fn edge_guide(&self, orientation: &str, value: f32, vertical: bool) -> SnapGuide {
+        let (start, end) = if vertical { (self.group.min_x, self.group.max_x) } else { (self.group.min_y, self.group.max_y) };
+        SnapGuide { orientation: orientation.to_string(), value, label: String::new(), kind: "edge".to_string(), start, end }
+    }
+
+    /// UPDATE_STYLE commands placing every target proportionally inside `rect`.
+    pub fn commands_for(&self, rect: &Rect) -> Vec<HyperCommand> {
+        let g = self.group;
+        let sx = if width(&g) > 0.0 { width(rect) / width(&g) } else { 1.0 };
+        let sy = if height(&g) > 0.0 { height(rect) / height(&g) } else { 1.0 };
+
+        self.targets.iter().map(|t| {
+            let x = rect.min_x + (t.bounds.min_x - g.min_x) * sx;
+            let y = rect.min_y + (t.bounds.min_y - g.min_y) * sy;
+            let mut updates = serde_json::Map::new();
+            updates.insert("width".to_string(), px(width(&t.bounds) * sx));
+            updates.insert("height".to_string(), px(height(&t.bounds) * sy));
+
+            if let (true, Some(p)) = (t.positioned, t.parent) {
+                if (x - t.bounds.min_x).abs() > 0.01 { updates.insert("left".to_string(), px(x - p.min_x)); }
+                if (y - t.bounds.min_y).abs() > 0.01 { updates.insert("top".to_string(), px(y - p.min_y)); }
+            }
+            HyperCommand::update_style(&t.id, serde_json::Value::Object(updates), &self.view_mode)
+        }).collect()
+    }
+
+    /// `update` for a pointer delta and snap config in screen pixels.
+    pub fn update_on_screen(&self, dx: f32, dy: f32, mods: ResizeModifiers, config: &SnapConfig, camera: &Camera) -> ResizeResult {
+        self.update(camera.screen_distance(dx), camera.screen_distance(dy), mods, &config.in_world(camera))
+    }
+
+    pub fn update(&self, dx: f32, dy: f32, mods: ResizeModifiers, config: &SnapConfig) -> ResizeResult {
+        let (rect, guides) = self.resize_box(dx, dy, mods, config);
+        ResizeResult {
+            x: rect.min_x,
+            y: rect.min_y,
+            width: width(&rect),
+            height: height(&rect),
+            commands: self.commands_for(&rect),
+            guides,
+        }
+    }
+}
+
+fn nearest(value: f32, lines: &[f32], threshold: f32) -> Option<f32> {
+    lines.iter()
+        .map(|l| (*l, (l - value).abs()))
+        .filter(|(_, d)| *d < threshold)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(l, _)| l)
+}
+
+pub(crate) fn px(value: f32) -> serde_json::Value {
+    let rounded = (value * 100.0).round() / 100.0;
+    serde_json::Value::String(format!("{}px", rounded))
+}
+
+/// Snapshots targets from the project and spatial index. An empty id list resizes the current selection.
+/// `config` is in world units.
+fn build_session(ids: Vec<String>, handle: ResizeHandle, config: &SnapConfig) -> Option<ResizeSession> {
+    let ids = if ids.is_empty() { crate::plugins::selection::SELECTION.lock().unwrap().ids.clone() } else { ids };
+
+    // (id, parent id, positioned, min, max) plus every id that moves with the targets
+    let (specs, excluded, view_mode) = {
+        let state_guard = PROJECT_STATE.lock().unwrap();
+        let state = state_guard.as_ref()?;
+        let mut excluded = HashSet::new();
+        let mut specs = Vec::new();
+
+        for id in &ids {
+            let el = match state.elements.get(id) { Some(e) => e, None => continue };
+            let styles = crate::resolve_final_styles(el, &state.view_mode);
+            let num = |key: &str| styles.get(key).and_then(parse_numeric);
+            let positioned = el.layout_mode.as_deref() == Some("freedom")
+                || styles.get("position").and_then(|v| v.as_str()) == Some("absolute");
+            specs.push((
+                id.clone(),
+                el.parent_id.clone(),
+                positioned,
+                (num("minWidth").unwrap_or(0.0), num("minHeight").unwrap_or(0.0)),
+                (num("maxWidth").unwrap_or(f32::MAX), num("maxHeight").unwrap_or(f32::MAX)),
+            ));
+
+            let mut stack = vec![id.clone()];
+            while let Some(current) = stack.pop() {
+                if let Some(children) = state.elements.get(&current).and_then(|e| e.children.as_ref()) {
+                    stack.extend(children.iter().cloned());
+                }
+                excluded.insert(current);
+            }
+        }
+        (specs, excluded, state.view_mode.clone())
+    };
+
+    let index = SPATIAL_INDEX.lock().unwrap();
+    let targets: Vec<ResizeTarget> = specs.into_iter().filter_map(|(id, parent_id, positioned, min_size, max_size)| {
+        let bounds = Rect::from(&index.query_one(&id)?);
+        let parent = parent_id.and_then(|pid| index.query_one(&pid)).map(|b| Rect::from(&b));
+        Some(ResizeTarget { id, bounds, parent, positioned, min_size, max_size })
+    }).collect();
+
+    let mut session = ResizeSession::new(handle, targets, Vec::new(), &view_mode)?;
+    let r = config.search_radius;
+    let g = session.group;
+    session.others = index.query_intersecting(g.min_x - r, g.min_y - r, width(&g) + r * 2.0, height(&g) + r * 2.0)
+        .iter()
+        .filter(|id| !excluded.contains(*id))
+        .filter_map(|id| index.query_one(id))
+        .map(|b| Rect::from(&b))
+        .collect();
+    Some(session)
+}
+
+// --- WASM Exports ---
+
+/// Starts a resize gesture on `ids` (JSON array, empty = current selection) from a handle
+/// ("nw", "n", "ne", "e", "se", "s", "sw", "w").
+#[wasm_bindgen]
+pub fn begin_resize(ids_json: &str, handle: &str) -> bool {
+    let handle = match ResizeHandle::from_name(handle) { Some(h) => h, None => return false };
+    let ids: Vec<String> = serde_json::from_str(ids_json).unwrap_or_default();
+    let config = SNAP_CONFIG.lock().unwrap().in_world(&CAMERA.lock().unwrap());
+    let session = build_session(ids, handle, &config);
+    let started = session.is_some();
+    *RESIZE_SESSION.lock().unwrap() = session;
+    started
+}
+
+/// Pointer delta since `begin_resize`, in screen pixels. Returns `{ x, y, width, height, commands, guides }`
+/// in world units; `commands` are UPDATE_STYLE commands for `apply_command`.
+#[wasm_bindgen]
+pub fn update_resize(dx: f32, dy: f32, shift: bool, alt: bool) -> String {
+    let config = SNAP_CONFIG.lock().unwrap().clone();
+    let camera = *CAMERA.lock().unwrap();
+    let session = RESIZE_SESSION.lock().unwrap();
+    match session.as_ref() {
+        Some(s) => serde_json::to_string(&s.update_on_screen(dx, dy, ResizeModifiers { shift, alt }, &config, &camera)).unwrap_or("{}".to_string()),
+        None => "{}".to_string(),
+    }
+}
+
+#[wasm_bindgen]
+pub fn end_resize() {
+    *RESIZE_SESSION.lock().unwrap() = None;
+}
+
+/// One-shot resize (begin, update, end) for scripted edits.
+#[wasm_bindgen]
+pub fn resize_elements(ids_json: &str, handle: &str, dx: f32, dy: f32, shift: bool, alt: bool) -> String {
+    let handle = match ResizeHandle::from_name(handle) { Some(h) => h, None => return "{}".to_string() };
+    let ids: Vec<String> = serde_json::from_str(ids_json).unwrap_or_default();
+    let config = SNAP_CONFIG.lock().unwrap().clone();
+    let camera = *CAMERA.lock().unwrap();
+    match build_session(ids, handle, &config.in_world(&camera)) {
+        Some(s) => serde_json::to_string(&s.update_on_screen(dx, dy, ResizeModifiers { shift, alt }, &config, &camera)).unwrap_or("{}".to_string()),
+        None => "{}".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(id: &str, bounds: Rect, parent: Option<Rect>) -> ResizeTarget {
+        ResizeTarget { id: id.to_string(), bounds, parent, positioned: true, min_size: (0.0, 0.0), max_size: (f32::MAX, f32::MAX) }
+    }
+
+    fn no_snap() -> SnapConfig {
+        SnapConfig { objects: false, spacing: false, ..SnapConfig::default() }
+    }
+
+    #[test]
+    fn test_anchors_aspect_and_center() {
+        let session = ResizeSession::new(ResizeHandle::TopLeft, vec![target("a", Rect::from_xywh(100.0, 100.0, 200.0, 100.0), None)], vec![], "desktop").unwrap();
+
+        // Dragging the top-left corner keeps the bottom-right fixed
+        let (r, _) = session.resize_box(-20.0, 10.0, ResizeModifiers::default(), &no_snap());
+        assert_eq!(r, Rect::from_xywh(80.0, 110.0, 220.0, 90.0));
+
+        // Shift keeps 2:1, following the axis that moved most
+        let (r, _) = session.resize_box(-40.0, 0.0, ResizeModifiers { shift: true, alt: false }, &no_snap());
+        assert_eq!(r, Rect::from_xywh(60.0, 80.0, 240.0, 120.0));
+
+        // Alt resizes symmetrically around the center
+        let session = ResizeSession { handle: ResizeHandle::Right, ..session };
+        let (r, _) = session.resize_box(10.0, 0.0, ResizeModifiers { shift: false, alt: true }, &no_snap());
+        assert_eq!(r, Rect::from_xywh(90.0, 100.0, 220.0, 100.0));
+    }
+
+    #[test]
+    fn test_min_max_containment_and_snapping() {
+        let parent = Rect::from_xywh(0.0, 0.0, 400.0, 400.0);
+        let mut t = target("a", Rect::from_xywh(100.0, 100.0, 100.0, 100.0), Some(parent));
+        t.min_size = (50.0, 0.0);
+        let session = ResizeSession::new(ResizeHandle::BottomRight, vec![t], vec![Rect::from_xywh(250.0, 0.0, 10.0, 10.0)], "desktop").unwrap();
+
+        // Parent containment stops the right edge at 400, min width stops at 50
+        let (r, _) = session.resize_box(500.0, 0.0, ResizeModifiers::default(), &no_snap());
+        assert_eq!(r.max_x, 400.0);
+        let (r, _) = session.resize_box(-90.0, 0.0, ResizeModifiers::default(), &no_snap());
+        assert_eq!(r.max_x - r.min_x, 50.0);
+
+        // The dragged right edge snaps to the neighbour's left edge (250)
+        let (r, guides) = session.resize_box(47.0, 0.0, ResizeModifiers::default(), &SnapConfig::default());
+        assert_eq!(r.max_x, 250.0);
+        assert_eq!(guides[0].value, 250.0);
+    }
+
+    #[test]
+    fn test_screen_deltas_and_snapping_follow_zoom() {
+        let session = ResizeSession::new(ResizeHandle::Right, vec![target("a", Rect::from_xywh(100.0, 100.0, 100.0, 100.0), None)], vec![Rect::from_xywh(250.0, 0.0, 10.0, 10.0)], "desktop").unwrap();
+        let camera = Camera { zoom: 2.0, ..Camera::default() };
+
+        // 90 screen pixels are 45 world units; 5 short of the neighbour is beyond the 2.5-unit threshold
+        let result = session.update_on_screen(90.0, 0.0, ResizeModifiers::default(), &SnapConfig::default(), &camera);
+        assert_eq!(result.width, 145.0);
+        assert!(result.guides.is_empty());
+
+        // 96 pixels land 2 units short: within 5 screen pixels, so the edge snaps to 250
+        let result = session.update_on_screen(96.0, 0.0, ResizeModifiers::default(), &SnapConfig::default(), &camera);
+        assert_eq!(result.width, 150.0);
+        assert_eq!(result.guides[0].value, 250.0);
+    }
+
+    #[test]
+    fn test_multi_selection_scales_proportionally() {
+        let parent = Rect::from_xywh(0.0, 0.0, 1000.0, 1000.0);
+        let session = ResizeSession::new(
+            ResizeHandle::Left,
+            vec![target("a", Rect::from_xywh(100.0, 0.0, 100.0, 50.0), Some(parent)), target("b", Rect::from_xywh(300.0, 0.0, 100.0, 50.0), Some(parent))],
+            vec![],
+            "desktop",
+        ).unwrap();
+
+        // Group 100..400 grows to 0..400: everything scales by 4/3 from the right edge
+        let result = session.update(-100.0, 0.0, ResizeModifiers::default(), &no_snap());
+        assert_eq!(result.commands.len(), 2);
+        let b = &result.commands[1];
+        assert_eq!(b.action, "UPDATE_STYLE");
+        assert_eq!(b.payload["updates"]["left"], "266.67px");
+        assert_eq!(b.payload["updates"]["width"], "133.33px");
+        assert_eq!(b.payload["updates"].get("top"), None);
+    }
+}
@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+// --- FIXED-TIMESTEP FRAME SCHEDULER ---
+// Real frame time is accumulated and consumed in fixed simulation steps, so physics and
+// springs behave the same at 30, 60 or 144 Hz. Rendering runs once per frame with an
+// interpolation alpha describing how far the leftover time reaches into the next step.
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FramePhase {
+    Input,
+    Logic,
+    Physics,
+    Animation,
+    Render,
+}
+
+impl FramePhase {
+    /// Phases run once per fixed step, in this order. `Render` runs once per frame afterwards.
+    pub const SIMULATION: [FramePhase; 4] = [FramePhase::Input, FramePhase::Logic, FramePhase::Physics, FramePhase::Animation];
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameContext {
+    pub phase: FramePhase,
+    pub dt: f32,    // fixed step for simulation phases, real frame time for Render
+    pub alpha: f32, // interpolation factor (0..1), only meaningful in Render
+    pub frame: u64,
+    pub step: u64,  // global fixed-step index
+}
+
+pub type System = fn(&FrameContext);
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct FramePlan {
+    pub frame: u64,
+    pub first_step: u64,
+    pub steps: u32,
+    pub fixed_dt: f32,
+    pub real_dt: f32,
+    pub alpha: f32,
+}
+
+pub struct FrameScheduler {
+    pub fixed_dt: f32,
+    /// Upper bound on catch-up steps per frame (avoids the spiral of death after a stall).
+    pub max_steps_per_frame: u32,
+    pub frame_count: u64,
+    pub step_count: u64,
+    pub alpha: f32,
+    accumulator: f32,
+    systems: Vec<(FramePhase, System)>,
+    recording: Option<Vec<f32>>,
+}
+
+impl FrameScheduler {
+    pub fn new(fixed_dt: f32) -> Self {
+        Self {
+            fixed_dt,
+            max_steps_per_frame: 8,
+            frame_count: 0,
+            step_count: 0,
+            alpha: 0.0,
+            accumulator: 0.0,
+            systems: Vec::new(),
+            recording: None,
+        }
+    }
+
+    /// Systems run in registration order within their phase.
+    pub fn add_system(&mut self, phase: FramePhase, system: System) {
+        self.systems.push((phase, system));
+    }
+
+    pub fn set_fixed_rate(&mut self, hz: f32) {
+        if hz > 0.0 {
+            self.fixed_dt = 1.0 / hz;
+            self.accumulator = 0.0;
+        }
+    }
+
+    /// Consumes `real_dt` and decides how many fixed steps this frame runs.
+    pub fn plan_frame(&mut self, real_dt: f32) -> FramePlan {
+        let real_dt = if real_dt.is_finite() { real_dt.max(0.0) } else { 0.0 };
+        if let Some(rec) = &mut self.recording {
+            rec.push(real_dt);
+        }
+
+        self.accumulator += real_dt;
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_dt && steps < self.max_steps_per_frame {
+            self.accumulator -= self.fixed_dt;
+            steps += 1;
+        }
+        // Drop time we could not catch up on rather than carrying it forever
+        if steps == self.max_steps_per_frame && self.accumulator >= self.fixed_dt {
+            self.accumulator %= self.fixed_dt;
+        }
+
+        let plan = FramePlan {
+            frame: self.frame_count,
+            first_step: self.step_count,
+            steps,
+            fixed_dt: self.fixed_dt,
+            real_dt,
+            alpha: self.accumulator / self.fixed_dt,
+        };
+
+        self.frame_count += 1;
+        self.step_count += steps as u64;
+        self.alpha = plan.alpha;
+        plan
+    }
+
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    /// Returns the recorded `real_dt` sequence; feeding it back through `run_frame` replays the same steps.
+    pub fn stop_recording(&mut self) -> Vec<f32> {
+        self.recording.take().unwrap_or_default()
+    }
+
+    /// Clears timing state so a recorded sequence replays from step zero.
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+        self.alpha = 0.0;
+        self.frame_count = 0;
+        self.step_count = 0;
+    }
+}
+
+/// Runs one frame. The scheduler lock is released before systems run, so systems may query it.
+pub fn run_frame(scheduler: &Mutex<FrameScheduler>, real_dt: f32) -> FramePlan {
+    let (plan, systems) = {
+        let mut s = scheduler.lock().unwrap();
+        let plan = s.plan_frame(real_dt);
+        (plan, s.systems.clone())
+    };
+
+    let run_phase = |ctx: &FrameContext| {
+        for (phase, system) in &systems {
+            if *phase == ctx.phase { system(ctx); }
+        }
+    };
+
+    for i in 0..plan.steps {
+        for phase in FramePhase::SIMULATION {
+            run_phase(&FrameContext { phase, dt: plan.fixed_dt, alpha: 0.0, frame: plan.frame, step: plan.first_step + i as u64 });
+        }
+    }
+
+    run_phase(&FrameContext { phase: FramePhase::Render, dt: plan.real_dt, alpha: plan.alpha, frame: plan.frame, step: plan.first_step + plan.steps as u64 });
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    lazy_static! {
+        static ref PHASE_LOG: Mutex<Vec<FramePhase>> = Mutex::new(Vec::new());
+    }
+
+    fn log_phase(ctx: &FrameContext) {
+        PHASE_LOG.lock().unwrap().push(ctx.phase);
+    }
+
+    #[test]
+    fn test_step_count_is_rate_independent() {
+        for hz in [30.0, 60.0, 144.0] {
+            let mut scheduler = FrameScheduler::new(1.0 / 120.0);
+            let steps: u32 = (0..hz as usize).map(|_| scheduler.plan_frame(1.0 / hz).steps).sum();
+            assert!((119..=120).contains(&steps), "{} Hz ran {} steps", hz, steps);
+            assert!(scheduler.alpha >= 0.0 && scheduler.alpha < 1.0);
+        }
+    }
+
+    #[test]
+    fn test_phase_order() {
+        let scheduler = Mutex::new(FrameScheduler::new(0.01));
+        {
+            let mut s = scheduler.lock().unwrap();
+            for phase in [FramePhase::Render, FramePhase::Animation, FramePhase::Physics, FramePhase::Logic, FramePhase::Input] {
+                s.add_system(phase, log_phase);
+            }
+        }
+
+        let plan = run_frame(&scheduler, 0.025);
+        assert_eq!(plan.steps, 2);
+        assert!((plan.alpha - 0.5).abs() < 1e-3);
+
+        let log = PHASE_LOG.lock().unwrap();
+        let step = [FramePhase::Input, FramePhase::Logic, FramePhase::Physics, FramePhase::Animation];
+        assert_eq!(&log[0..4], &step);
+        assert_eq!(&log[4..8], &step);
+        assert_eq!(log[8], FramePhase::Render);
+    }
+
+    #[test]
+    fn test_recorded_frames_replay_identically() {
+        let mut live = FrameScheduler::new(1.0 / 60.0);
+        live.start_recording();
+        let live_steps: Vec<u32> = [0.016, 0.033, 0.007, 0.1].iter().map(|dt| live.plan_frame(*dt).steps).collect();
+
+        let mut replay = FrameScheduler::new(1.0 / 60.0);
+        let replay_steps: Vec<u32> = live.stop_recording().iter().map(|dt| replay.plan_frame(*dt).steps).collect();
+        assert_eq!(live_steps, replay_steps);
+    }
+}
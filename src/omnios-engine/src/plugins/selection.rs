@@ -0,0 +1,392 @@
+use crate::DesignerElement;
+use crate::plugins::spatial_index::SpatialIndex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use wasm_bindgen::prelude::*;
+use lazy_static::lazy_static;
+
+// --- SELECTION MODEL ---
+// The engine owns which elements are selected so marquee, snapping and resize all agree
+// on the same set. The selection never holds an element together with one of its
+// ancestors, and locked or hidden elements (or descendants of them) cannot be selected.
+
+lazy_static! {
+    pub static ref SELECTION: Mutex<Selection> = Mutex::new(Selection::default());
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MarqueeMode {
+    /// Only elements completely inside the rectangle.
+    Contains,
+    /// Any element the rectangle touches.
+    Intersects,
+}
+
+impl MarqueeMode {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "intersects" | "touch" => MarqueeMode::Intersects,
+            _ => MarqueeMode::Contains,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectionDelta {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub primary: Option<String>,
+}
+
+#[derive(Default, Debug)]
+pub struct Selection {
+    pub ids: Vec<String>, // In selection order
+    pub primary: Option<String>,
+    deltas: Vec<SelectionDelta>,
+}
+
+fn is_flag_set(el: &DesignerElement, key: &str) -> bool {
+    el.props.get(key).and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// An element can be selected unless it, or any ancestor, is locked or hidden (`visible: false`).
+pub fn is_selectable(elements: &HashMap<String, DesignerElement>, id: &str) -> bool {
+    let mut current = elements.get(id);
+    while let Some(el) = current {
+        let hidden = el.props.get("visible").and_then(|v| v.as_bool()) == Some(false);
+        if hidden || is_flag_set(el, "locked") { return false; }
+        current = el.parent_id.as_ref().and_then(|pid| elements.get(pid));
+    }
+    elements.contains_key(id)
+}
+
+pub fn ancestors(elements: &HashMap<String, DesignerElement>, id: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = elements.get(id).and_then(|el| el.parent_id.clone());
+    while let Some(pid) = current {
+        if result.contains(&pid) { break; } // Guard against malformed cycles
+        current = elements.get(&pid).and_then(|el| el.parent_id.clone());
+        result.push(pid);
+    }
+    result
+}
+
+/// Drops ids whose ancestor is also in the list, keeping the outermost ones (order preserved).
+pub fn outermost(elements: &HashMap<String, DesignerElement>, ids: &[String]) -> Vec<String> {
+    let set: HashSet<&String> = ids.iter().collect();
+    let mut seen = HashSet::new();
+    ids.iter()
+        .filter(|id| seen.insert(id.as_str()))
+        .filter(|id| !ancestors(elements, id).iter().any(|a| set.contains(a)))
+        .cloned()
+        .collect()
+}
+
+impl Selection {
+    /// Replaces the whole selection, recording what changed.
+    fn commit(&mut self, ids: Vec<String>, primary: Option<String>) -> bool {
+        let primary = primary.filter(|p| ids.contains(p)).or_else(|| ids.first().cloned());
+        let added: Vec<String> = ids.iter().filter(|id| !self.ids.contains(id)).cloned().collect();
+        let removed: Vec<String> = self.ids.iter().filter(|id| !ids.contains(id)).cloned().collect();
+
+        if added.is_empty() && removed.is_empty() && primary == self.primary {
+            return false;
+        }
+        self.deltas.push(SelectionDelta { added, removed, primary: primary.clone() });
+        self.ids = ids;
+        self.primary = primary;
+        true
+    }
+
+    /// Selects `ids` (replacing, or added to the current selection when `additive`).
+    /// The last requested id becomes primary. Selecting a child of a selected element
+    /// replaces the parent, and vice versa.
+    pub fn select(&mut self, elements: &HashMap<String, DesignerElement>, ids: &[String], additive: bool) -> bool {
+        let requested: Vec<String> = ids.iter().filter(|id| is_selectable(elements, id)).cloned().collect();
+
+        let mut next: Vec<String> = if additive { self.ids.clone() } else { Vec::new() };
+        for id in &requested {
+            // The newest pick wins over any selected ancestor or descendant
+            let lineage = ancestors(elements, id);
+            next.retain(|existing| !lineage.contains(existing) && !ancestors(elements, existing).contains(id));
+            next.retain(|existing| existing != id);
+            next.push(id.clone());
+        }
+        let next = outermost(elements, &next);
+        let primary = requested.last().cloned().or_else(|| self.primary.clone());
+        self.commit(next, primary)
+    }
+
+    /// Adds the element if it is not selected, removes it otherwise.
+    pub fn toggle(&mut self, elements: &HashMap<String, DesignerElement>, id: &str) -> bool {
+        if self.ids.iter().any(|s| s == id) {
+            let next: Vec<String> = self.ids.iter().filter(|s| *s != id).cloned().collect();
+            let primary = self.primary.clone().filter(|p| p != id);
+            self.commit(next, primary)
+        } else {
+            self.select(elements, &[id.to_string()], true)
+        }
+    }
+
+    pub fn clear(&mut self) -> bool {
+        self.commit(Vec::new(), None)
+    }
+
+    pub fn set_primary(&mut self, id: &str) -> bool {
+        if !self.ids.iter().any(|s| s == id) { return false; }
+        self.commit(self.ids.clone(), Some(id.to_string()))
+    }
+
+    /// Selects every selectable child of `parent_id`. Without a parent, the scope is the
+    /// parent of the primary selection, falling back to the root element(s).
+    pub fn select_all(&mut self, elements: &HashMap<String, DesignerElement>, parent_id: Option<&str>) -> bool {
+        let scope = parent_id.map(|p| p.to_string()).or_else(|| {
+            self.primary.as_ref().and_then(|p| elements.get(p)).and_then(|el| el.parent_id.clone())
+        });
+
+        let mut children: Vec<String> = match &scope {
+            Some(pid) => elements.get(pid).and_then(|el| el.children.clone()).unwrap_or_default(),
+            None => {
+                let mut roots: Vec<&DesignerElement> = elements.values().filter(|el| el.parent_id.is_none()).collect();
+                roots.sort_by(|a, b| a.id.cmp(&b.id));
+                roots.iter().flat_map(|el| el.children.clone().unwrap_or_default()).collect()
+            }
+        };
+        children.retain(|id| is_selectable(elements, id));
+        self.commit(children, self.primary.clone())
+    }
+
+    /// Selects elements inside (or touching) the rectangle. Containers that wrap the whole
+    /// rectangle are the surface being dragged on and are never picked, and only the
+    /// outermost matching element of each branch is kept.
+    pub fn marquee(
+        &mut self,
+        elements: &HashMap<String, DesignerElement>,
+        index: &SpatialIndex,
+        rect: (f32, f32, f32, f32),
+        mode: MarqueeMode,
+        additive: bool,
+    ) -> bool {
+        let hits = marquee_hits(elements, index, rect, mode);
+        if additive {
+            let mut next = self.ids.clone();
+            next.extend(hits);
+            let next = outermost(elements, &next);
+            self.commit(next, self.primary.clone())
+        } else {
+            self.commit(hits, None)
+        }
+    }
+
+    /// Drops ids that were removed or became locked/hidden since they were selected.
+    pub fn prune(&mut self, elements: &HashMap<String, DesignerElement>) -> bool {
+        let next: Vec<String> = self.ids.iter().filter(|id| is_selectable(elements, id)).cloned().collect();
+        self.commit(next, self.primary.clone())
+    }
+
+    pub fn drain_deltas(&mut self) -> Vec<SelectionDelta> {
+        std::mem::take(&mut self.deltas)
+    }
+}
+
+pub fn marquee_hits(
+    elements: &HashMap<String, DesignerElement>,
+    index: &SpatialIndex,
+    rect: (f32, f32, f32, f32),
+    mode: MarqueeMode,
+) -> Vec<String> {
+    // Normalise rectangles dragged up or to the left
+    let (x, y, w, h) = rect;
+    let (min_x, max_x) = if w < 0.0 { (x + w, x) } else { (x, x + w) };
+    let (min_y, max_y) = if h < 0.0 { (y + h, y) } else { (y, y + h) };
+
+    let candidates = match mode {
+        MarqueeMode::Contains => index.query_area(min_x, min_y, max_x - min_x, max_y - min_y),
+        MarqueeMode::Intersects => index.query_intersecting(min_x, min_y, max_x - min_x, max_y - min_y),
+    };
+    let mut hits: Vec<(String, f32, f32)> = candidates
+        .into_iter()
+        .filter_map(|id| index.query_one(&id).map(|b| (id, b)))
+        .filter(|(id, b)| {
+            let wraps_marquee = b.min_x <= min_x && b.min_y <= min_y && b.max_x >= max_x && b.max_y >= max_y;
+            let inside = b.min_x >= min_x && b.min_y >= min_y && b.max_x <= max_x && b.max_y <= max_y;
+            let matches = match mode {
+                MarqueeMode::Contains => inside,
+                MarqueeMode::Intersects => !wraps_marquee,
+            };
+            matches && is_selectable(elements, id)
+        })
+        .map(|(id, b)| (id, b.min_y, b.min_x))
+        .collect();
+
+    // Reading order keeps the result stable regardless of R-tree traversal
+    hits.sort_by(|a, b| {
+        a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal))
+            .then(a.0.cmp(&b.0))
+    });
+    let ids: Vec<String> = hits.into_iter().map(|(id, _, _)| id).collect();
+    outermost(elements, &ids)
+}
+
+// --- WASM Exports ---
+
+fn with_selection<F>(f: F) -> String
+where
+    F: FnOnce(&mut Selection, &HashMap<String, DesignerElement>),
+{
+    let state_lock = crate::PROJECT_STATE.lock().unwrap();
+    let empty = HashMap::new();
+    let elements = state_lock.as_ref().map(|s| &s.elements).unwrap_or(&empty);
+    let mut selection = SELECTION.lock().unwrap();
+    f(&mut selection, elements);
+    selection_json(&selection)
+}
+
+fn selection_json(selection: &Selection) -> String {
+    serde_json::json!({ "ids": selection.ids, "primary": selection.primary }).to_string()
+}
+
+#[wasm_bindgen]
+pub fn get_selection() -> String {
+    selection_json(&SELECTION.lock().unwrap())
+}
+
+#[wasm_bindgen]
+pub fn select_elements(ids_json: &str, additive: bool) -> String {
+    let ids: Vec<String> = serde_json::from_str(ids_json).unwrap_or_default();
+    with_selection(|sel, elements| { sel.select(elements, &ids, additive); })
+}
+
+#[wasm_bindgen]
+pub fn toggle_selection(id: &str) -> String {
+    with_selection(|sel, elements| { sel.toggle(elements, id); })
+}
+
+#[wasm_bindgen]
+pub fn clear_selection() -> String {
+    with_selection(|sel, _| { sel.clear(); })
+}
+
+#[wasm_bindgen]
+pub fn set_primary_selection(id: &str) -> String {
+    with_selection(|sel, _| { sel.set_primary(id); })
+}
+
+#[wasm_bindgen]
+pub fn select_all(parent_id: Option<String>) -> String {
+    with_selection(|sel, elements| { sel.select_all(elements, parent_id.as_deref()); })
+}
+
+/// Rectangle in screen coordinates. `mode` is "contains" (default) or "intersects".
+#[wasm_bindgen]
+pub fn marquee_select(x: f32, y: f32, width: f32, height: f32, mode: &str, additive: bool) -> String {
+    let mode = MarqueeMode::from_name(mode);
+    let rect = crate::plugins::camera::CAMERA.lock().unwrap().screen_rect_to_world(x, y, width, height);
+    with_selection(|sel, elements| {
+        let index = crate::plugins::spatial_index::SPATIAL_INDEX.lock().unwrap();
+        sel.marquee(elements, &index, rect, mode, additive);
+    })
+}
+
+/// Selection changes since the last call, as `[{ added, removed, primary }]`.
+#[wasm_bindgen]
+pub fn get_selection_deltas() -> String {
+    let deltas = SELECTION.lock().unwrap().drain_deltas();
+    serde_json::to_string(&deltas).unwrap_or("[]".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> HashMap<String, DesignerElement> {
+        let raw = serde_json::json!([
+            { "id": "root", "type": "container", "parentId": null, "children": ["card", "locked", "hidden"] },
+            { "id": "card", "type": "container", "parentId": "root", "children": ["title", "body"] },
+            { "id": "title", "type": "text", "parentId": "card" },
+            { "id": "body", "type": "text", "parentId": "card" },
+            { "id": "locked", "type": "container", "parentId": "root", "locked": true, "children": ["inner"] },
+            { "id": "inner", "type": "text", "parentId": "locked" },
+            { "id": "hidden", "type": "text", "parentId": "root", "visible": false }
+        ]);
+        let list: Vec<DesignerElement> = serde_json::from_value(raw).unwrap();
+        list.into_iter().map(|el| (el.id.clone(), el)).collect()
+    }
+
+    fn index() -> SpatialIndex {
+        let mut index = SpatialIndex::new();
+        index.insert_or_update("root".to_string(), 0.0, 0.0, 1000.0, 1000.0);
+        index.insert_or_update("card".to_string(), 100.0, 100.0, 200.0, 200.0);
+        index.insert_or_update("title".to_string(), 110.0, 110.0, 100.0, 20.0);
+        index.insert_or_update("body".to_string(), 110.0, 140.0, 100.0, 20.0);
+        index.insert_or_update("locked".to_string(), 400.0, 100.0, 100.0, 100.0);
+        index.insert_or_update("inner".to_string(), 410.0, 110.0, 20.0, 20.0);
+        index.insert_or_update("hidden".to_string(), 600.0, 100.0, 50.0, 50.0);
+        index
+    }
+
+    #[test]
+    fn test_marquee_modes_are_hierarchy_aware() {
+        let elements = tree();
+        let index = index();
+
+        // Fully contains: the card is inside, its children are dropped in favour of it
+        let hits = marquee_hits(&elements, &index, (50.0, 50.0, 700.0, 300.0), MarqueeMode::Contains);
+        assert_eq!(hits, vec!["card".to_string()]);
+
+        // Only the title is fully inside; the card merely intersects
+        let hits = marquee_hits(&elements, &index, (105.0, 105.0, 120.0, 30.0), MarqueeMode::Contains);
+        assert_eq!(hits, vec!["title".to_string()]);
+
+        // Intersecting from within the card picks the children, not the card wrapping the marquee
+        let hits = marquee_hits(&elements, &index, (150.0, 115.0, 10.0, 40.0), MarqueeMode::Intersects);
+        assert_eq!(hits, vec!["title".to_string(), "body".to_string()]);
+
+        // Locked and hidden branches are never hit
+        let hits = marquee_hits(&elements, &index, (390.0, 90.0, 300.0, 100.0), MarqueeMode::Intersects);
+        assert!(hits.is_empty());
+    }
+
+    #[test]
+    fn test_selection_deltas_and_parent_child_exclusion() {
+        let elements = tree();
+        let mut sel = Selection::default();
+
+        assert!(sel.select(&elements, &["title".to_string(), "body".to_string()], false));
+        assert_eq!(sel.primary.as_deref(), Some("body"));
+
+        // Adding the parent replaces its selected children
+        sel.select(&elements, &["card".to_string()], true);
+        assert_eq!(sel.ids, vec!["card".to_string()]);
+
+        // Clicking a locked element behaves like clicking empty canvas
+        sel.select(&elements, &["inner".to_string()], false);
+        assert!(sel.ids.is_empty());
+        assert!(!sel.clear());
+
+        let deltas = sel.drain_deltas();
+        assert_eq!(deltas.len(), 3);
+        assert_eq!(deltas[0].added, vec!["title".to_string(), "body".to_string()]);
+        assert_eq!(deltas[1].added, vec!["card".to_string()]);
+        assert_eq!(deltas[1].removed, vec!["title".to_string(), "body".to_string()]);
+        assert_eq!(deltas[2].removed, vec!["card".to_string()]);
+        assert!(sel.drain_deltas().is_empty());
+    }
+
+    #[test]
+    fn test_select_all_is_scoped_to_parent() {
+        let elements = tree();
+        let mut sel = Selection::default();
+        sel.select_all(&elements, Some("card"));
+        assert_eq!(sel.ids, vec!["title".to_string(), "body".to_string()]);
+
+        // Without a scope, the primary's siblings are used; locked/hidden ones are skipped
+        sel.select(&elements, &["card".to_string()], false);
+        sel.select_all(&elements, None);
+        assert_eq!(sel.ids, vec!["card".to_string()]);
+    }
+}
@@ -26,6 +26,16 @@ impl RTreeObject for ElementBounds {
     }
 }
 
+impl ElementBounds {
+    pub fn new(id: String, x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self { id, min_x: x, min_y: y, max_x: x + width, max_y: y + height }
+    }
+}
+
+/// `sync` rebuilds from scratch once more than this share of the index changed:
+/// one bulk load beats that many R-tree removals and insertions.
+const REBUILD_RATIO: f32 = 0.25;
+
 pub struct SpatialIndex {
     pub tree: RTree<ElementBounds>,
     element_map: HashMap<String, ElementBounds>, // Quick lookup to update/remove
@@ -45,18 +55,66 @@ impl SpatialIndex {
             self.tree.remove(&old_bounds);
         }
 
-        let new_bounds = ElementBounds {
-            id: id.clone(),
-            min_x: x,
-            min_y: y,
-            max_x: x + width,
-            max_y: y + height,
-        };
+        let new_bounds = ElementBounds::new(id.clone(), x, y, width, height);
 
         self.tree.insert(new_bounds.clone());
         self.element_map.insert(id, new_bounds);
     }
 
+    /// Replaces the whole index with one bulk-loaded R-tree.
+    pub fn rebuild(&mut self, items: Vec<ElementBounds>) {
+        self.element_map = items.iter().map(|b| (b.id.clone(), b.clone())).collect();
+        // Duplicate ids keep the last entry, like repeated insert_or_update calls
+        let items = if self.element_map.len() == items.len() { items } else { self.element_map.values().cloned().collect() };
+        self.tree = RTree::bulk_load(items);
+    }
+
+    /// Brings the index in line with a complete set of bounds (e.g. after a layout pass),
+    /// touching only entries that moved, resized, appeared or disappeared.
+    /// Returns how many entries changed.
+    pub fn sync(&mut self, items: Vec<ElementBounds>) -> usize {
+        let changed: Vec<&ElementBounds> = items.iter().filter(|b| self.element_map.get(&b.id) != Some(*b)).collect();
+        let present = items.iter().filter(|b| self.element_map.contains_key(&b.id)).count();
+        let removed: Vec<String> = if present < self.element_map.len() {
+            let ids: std::collections::HashSet<&String> = items.iter().map(|b| &b.id).collect();
+            self.element_map.keys().filter(|id| !ids.contains(id)).cloned().collect()
+        } else {
+            Vec::new() // Every indexed id is still present
+        };
+
+        let total = changed.len() + removed.len();
+        if total == 0 { return 0; }
+        if total as f32 > self.element_map.len() as f32 * REBUILD_RATIO {
+            self.rebuild(items);
+            return total;
+        }
+
+        let changed: Vec<ElementBounds> = changed.into_iter().cloned().collect();
+        for id in &removed {
+            self.remove(id);
+        }
+        for b in changed {
+            if let Some(old_bounds) = self.element_map.insert(b.id.clone(), b.clone()) {
+                self.tree.remove(&old_bounds);
+            }
+            self.tree.insert(b);
+        }
+        total
+    }
+
+    /// O(1) lookup by id.
+    pub fn get(&self, id: &str) -> Option<&ElementBounds> {
+        self.element_map.get(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.element_map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.element_map.is_empty()
+    }
+
     pub fn remove(&mut self, id: &str) {
         if let Some(old_bounds) = self.element_map.remove(id) {
             self.tree.remove(&old_bounds);
@@ -70,7 +128,8 @@ impl SpatialIndex {
     pub fn hit_test(&self, x: f32, y: f32) -> Option<String> {
         // Find all elements containing the point
         let envelope = AABB::from_point([x, y]);
-        let candidates: Vec<&ElementBounds> = self.tree.locate_in_envelope(&envelope).collect();
+        // `locate_in_envelope` would only return boxes *inside* the point, i.e. none
+        let candidates: Vec<&ElementBounds> = self.tree.locate_in_envelope_intersecting(&envelope).collect();
         
         // Z-Index handling would go here, for now return the smallest one (most likely nested child)
         // Heuristic: Smallest area is usually the deepest child
@@ -94,6 +153,14 @@ impl SpatialIndex {
             .collect()
     }
 
+    /// Like `query_area`, but also returns elements that only partially overlap the rectangle.
+    pub fn query_intersecting(&self, x: f32, y: f32, width: f32, height: f32) -> Vec<String> {
+        let envelope = AABB::from_corners([x, y], [x + width, y + height]);
+        self.tree.locate_in_envelope_intersecting(&envelope)
+            .map(|b| b.id.clone())
+            .collect()
+    }
+
     pub fn clear(&mut self) {
         self.tree = RTree::new();
         self.element_map.clear();
@@ -124,8 +191,11 @@ pub fn remove_element_bounds(id: &str) {
     index.remove(id);
 }
 
+// Pointer-facing queries take screen coordinates and go through the camera
+
 #[wasm_bindgen]
 pub fn query_area(x: f32, y: f32, width: f32, height: f32) -> JsValue {
+    let (x, y, width, height) = crate::plugins::camera::CAMERA.lock().unwrap().screen_rect_to_world(x, y, width, height);
     let index = SPATIAL_INDEX.lock().unwrap();
     let ids = index.query_area(x, y, width, height);
     serde_json::to_string(&ids).unwrap_or_else(|_| "[]".to_string()).into()
@@ -133,6 +203,7 @@ pub fn query_area(x: f32, y: f32, width: f32, height: f32) -> JsValue {
 
 #[wasm_bindgen]
 pub fn hit_test(x: f32, y: f32) -> Option<String> {
+    let (x, y) = crate::plugins::camera::CAMERA.lock().unwrap().screen_to_world(x, y);
     let index = SPATIAL_INDEX.lock().unwrap();
     index.hit_test(x, y)
 }
@@ -148,3 +219,34 @@ pub fn get_element_bounds(id: &str) -> Option<Vec<f32>> {
     let index = SPATIAL_INDEX.lock().unwrap();
     index.get_bounds(id).map(|(x, y, w, h)| vec![x, y, w, h])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_touches_only_changes() {
+        let grid = |offset: f32| -> Vec<ElementBounds> {
+            (0..100).map(|i| ElementBounds::new(format!("el-{}", i), (i % 10) as f32 * 50.0 + offset, (i / 10) as f32 * 50.0, 40.0, 40.0)).collect()
+        };
+        let mut index = SpatialIndex::new();
+        assert_eq!(index.sync(grid(0.0)), 100); // Empty index: bulk load
+        assert_eq!(index.sync(grid(0.0)), 0);
+
+        // One moved, one removed
+        let mut items = grid(0.0);
+        items[5].min_x += 1000.0;
+        items[5].max_x += 1000.0;
+        items.pop();
+        assert_eq!(index.sync(items), 2);
+        assert_eq!(index.len(), 99);
+        assert!(index.get("el-99").is_none());
+        assert_eq!(index.hit_test(1260.0, 10.0).as_deref(), Some("el-5"));
+        assert_eq!(index.hit_test(260.0, 10.0), None);
+
+        // Everything moved: rebuilt, and the tree agrees with the map
+        assert_eq!(index.sync(grid(5.0)), 100);
+        assert_eq!(index.tree.size(), 100);
+        assert_eq!(index.query_area(0.0, 0.0, 100.0, 50.0).len(), 2);
+    }
+}
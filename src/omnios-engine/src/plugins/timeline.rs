@@ -0,0 +1,472 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use lazy_static::lazy_static;
+use wasm_bindgen::prelude::*;
+use crate::plugins::animation::{AnimationValue, Easing};
+
+lazy_static! {
+    pub static ref TIMELINE_ENGINE: Mutex<TimelineEngine> = Mutex::new(TimelineEngine::new());
+}
+
+// --- TIMELINE DATA MODEL ---
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Keyframe {
+    pub time: f32, // seconds from timeline start
+    pub value: f32,
+    /// Curve used from this keyframe to the next one (linear if absent).
+    #[serde(default, deserialize_with = "deserialize_easing")]
+    pub easing: Option<Easing>,
+}
+
+fn deserialize_easing<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Easing>, D::Error> {
+    let v = serde_json::Value::deserialize(d)?;
+    if v.is_null() { return Ok(None); }
+    Easing::from_json(&v)
+        .or_else(|| serde_json::from_value(v.clone()).ok())
+        .map(Some)
+        .ok_or_else(|| serde::de::Error::custom(format!("Unknown easing: {}", v)))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyframeTrack {
+    pub element_id: String,
+    pub property: String,
+    #[serde(default)]
+    pub unit: Option<String>, // CSS unit used on export, e.g. "px", "deg"
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl KeyframeTrack {
+    /// Value at time `t`. Holds the first/last value outside the keyframe range.
+    pub fn sample(&self, t: f32) -> Option<f32> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+        if t <= first.time { return Some(first.value); }
+        if t >= last.time { return Some(last.value); }
+
+        let idx = self.keyframes.windows(2).position(|w| t >= w[0].time && t < w[1].time)?;
+        let (a, b) = (&self.keyframes[idx], &self.keyframes[idx + 1]);
+        let span = b.time - a.time;
+        if span <= 0.0 { return Some(b.value); }
+
+        let progress = a.easing.unwrap_or(Easing::Linear).apply((t - a.time) / span);
+        Some(a.value + (b.value - a.value) * progress)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum LoopMode {
+    #[default]
+    None,
+    Loop,
+    PingPong,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Playback {
+    pub playing: bool,
+    pub time: f32,
+    pub reversed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Timeline {
+    pub id: String,
+    pub tracks: Vec<KeyframeTrack>,
+    /// Explicit length in seconds. Defaults to the last keyframe time.
+    #[serde(default)]
+    pub duration: Option<f32>,
+    #[serde(default)]
+    pub loop_mode: LoopMode,
+    #[serde(default)]
+    pub playback: Playback,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineSample {
+    pub element_id: String,
+    pub property: String,
+    pub value: f32,
+}
+
+impl Timeline {
+    pub fn duration(&self) -> f32 {
+        self.duration.unwrap_or_else(|| {
+            self.tracks.iter()
+                .filter_map(|tr| tr.keyframes.last().map(|k| k.time))
+                .fold(0.0, f32::max)
+        })
+    }
+
+    /// Deterministic: the result depends only on `t`, never on playback history.
+    pub fn sample_at(&self, t: f32) -> Vec<TimelineSample> {
+        self.tracks.iter()
+            .filter_map(|tr| tr.sample(t).map(|value| TimelineSample {
+                element_id: tr.element_id.clone(),
+                property: tr.property.clone(),
+                value,
+            }))
+            .collect()
+    }
+
+    fn normalize(&mut self) {
+        for track in &mut self.tracks {
+            track.keyframes.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(std::cmp::Ordering::Equal));
+        }
+    }
+
+    /// Moves the playhead by `dt` honouring direction and loop mode. Returns false when paused.
+    pub fn advance(&mut self, dt: f32) -> bool {
+        if !self.playback.playing { return false; }
+
+        let duration = self.duration();
+        if duration <= 0.0 {
+            self.playback.playing = false;
+            return true;
+        }
+
+        let dir = if self.playback.reversed { -1.0 } else { 1.0 };
+        let mut time = self.playback.time + dt * dir;
+
+        match self.loop_mode {
+            LoopMode::None => {
+                if time >= duration || time <= 0.0 {
+                    self.playback.playing = false;
+                }
+                time = time.max(0.0).min(duration);
+            },
+            LoopMode::Loop => {
+                time = time.rem_euclid(duration);
+            },
+            LoopMode::PingPong => {
+                // Reflect at both ends; a cycle is there and back
+                time = time.rem_euclid(duration * 2.0);
+                if time > duration {
+                    time = duration * 2.0 - time;
+                    self.playback.reversed = !self.playback.reversed;
+                }
+            },
+        }
+
+        self.playback.time = time;
+        true
+    }
+
+    /// CSS export. One `@keyframes` block per track, all sharing the timeline duration,
+    /// with each keyframe's easing emitted as its segment `animation-timing-function`.
+    pub fn to_css(&self) -> String {
+        let duration = self.duration();
+        let mut css = String::new();
+        let mut rules: Vec<(String, Vec<String>)> = Vec::new();
+
+        let (iterations, direction) = match (self.loop_mode, self.playback.reversed) {
+            (LoopMode::None, false) => ("1", "normal"),
+            (LoopMode::None, true) => ("1", "reverse"),
+            (LoopMode::Loop, false) => ("infinite", "normal"),
+            (LoopMode::Loop, true) => ("infinite", "reverse"),
+            (LoopMode::PingPong, false) => ("infinite", "alternate"),
+            (LoopMode::PingPong, true) => ("infinite", "alternate-reverse"),
+        };
+
+        for (i, track) in self.tracks.iter().enumerate() {
+            if track.keyframes.is_empty() { continue; }
+            let name = format!("{}-{}", css_ident(&self.id), i);
+            let (css_prop, default_unit) = css_property(&track.property);
+            let unit = track.unit.as_deref().unwrap_or(default_unit);
+
+            css.push_str(&format!("@keyframes {} {{\n", name));
+
+            let first = &track.keyframes[0];
+            if first.time > 0.0 {
+                css.push_str(&format!("  0% {{ {}: {}{}; }}\n", css_prop, fmt_num(first.value), unit));
+            }
+            for kf in &track.keyframes {
+                let pct = if duration > 0.0 { kf.time / duration * 100.0 } else { 0.0 };
+                css.push_str(&format!("  {}% {{ {}: {}{};", fmt_num(pct), css_prop, fmt_num(kf.value), unit));
+                if let Some(easing) = kf.easing {
+                    css.push_str(&format!(" animation-timing-function: {};", css_easing(easing)));
+                }
+                css.push_str(" }\n");
+            }
+            let last = &track.keyframes[track.keyframes.len() - 1];
+            if last.time < duration {
+                css.push_str(&format!("  100% {{ {}: {}{}; }}\n", css_prop, fmt_num(last.value), unit));
+            }
+            css.push_str("}\n");
+
+            let decl = format!("{} {}s linear 0s {} {} both", name, fmt_num(duration), iterations, direction);
+            match rules.iter_mut().find(|(id, _)| id == &track.element_id) {
+                Some((_, decls)) => decls.push(decl),
+                None => rules.push((track.element_id.clone(), vec![decl])),
+            }
+        }
+
+        for (element_id, decls) in rules {
+            css.push_str(&format!("[data-id=\"{}\"] {{ animation: {}; }}\n", element_id, decls.join(", ")));
+        }
+
+        css
+    }
+}
+
+fn css_property(prop: &str) -> (String, &'static str) {
+    match prop {
+        "x" => ("left".to_string(), "px"),
+        "y" => ("top".to_string(), "px"),
+        "rotate" => ("rotate".to_string(), "deg"),
+        "opacity" | "scale" | "zIndex" | "flexGrow" | "flexShrink" | "fontWeight" | "lineHeight" => (camel_to_kebab(prop), ""),
+        _ => (camel_to_kebab(prop), "px"),
+    }
+}
+
+fn css_easing(easing: Easing) -> String {
+    match easing {
+        Easing::Linear => "linear".to_string(),
+        Easing::CubicBezier(a, b, c, d) => format!("cubic-bezier({}, {}, {}, {})", fmt_num(a), fmt_num(b), fmt_num(c), fmt_num(d)),
+    }
+}
+
+fn camel_to_kebab(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 4);
+    for ch in s.chars() {
+        if ch.is_ascii_uppercase() {
+            out.push('-');
+            out.push(ch.to_ascii_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+fn css_ident(s: &str) -> String {
+    s.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' }).collect()
+}
+
+fn fmt_num(v: f32) -> String {
+    let s = format!("{:.4}", v);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" { "0".to_string() } else { s.to_string() }
+}
+
+// --- ENGINE ---
+
+pub struct TimelineEngine {
+    pub timelines: HashMap<String, Timeline>,
+}
+
+impl Default for TimelineEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimelineEngine {
+    pub fn new() -> Self {
+        Self { timelines: HashMap::new() }
+    }
+
+    pub fn register(&mut self, mut timeline: Timeline) {
+        timeline.normalize();
+        self.timelines.insert(timeline.id.clone(), timeline);
+    }
+
+    pub fn play(&mut self, id: &str) -> bool {
+        self.with_timeline(id, |tl| {
+            // Restart from the edge if a one-shot already finished
+            let duration = tl.duration();
+            if tl.loop_mode == LoopMode::None {
+                if !tl.playback.reversed && tl.playback.time >= duration { tl.playback.time = 0.0; }
+                if tl.playback.reversed && tl.playback.time <= 0.0 { tl.playback.time = duration; }
+            }
+            tl.playback.playing = true;
+        })
+    }
+
+    pub fn pause(&mut self, id: &str) -> bool {
+        self.with_timeline(id, |tl| tl.playback.playing = false)
+    }
+
+    pub fn seek(&mut self, id: &str, time: f32) -> bool {
+        self.with_timeline(id, |tl| tl.playback.time = time.max(0.0).min(tl.duration()))
+    }
+
+    pub fn reverse(&mut self, id: &str) -> bool {
+        self.with_timeline(id, |tl| tl.playback.reversed = !tl.playback.reversed)
+    }
+
+    pub fn set_loop(&mut self, id: &str, mode: LoopMode) -> bool {
+        self.with_timeline(id, |tl| tl.loop_mode = mode)
+    }
+
+    fn with_timeline(&mut self, id: &str, f: impl FnOnce(&mut Timeline)) -> bool {
+        match self.timelines.get_mut(id) {
+            Some(tl) => { f(tl); true },
+            None => false,
+        }
+    }
+
+    /// Advances every playing timeline and returns the values they drive this frame.
+    pub fn advance(&mut self, dt: f32) -> Vec<TimelineSample> {
+        let mut samples = Vec::new();
+        for tl in self.timelines.values_mut() {
+            if tl.advance(dt) {
+                samples.extend(tl.sample_at(tl.playback.time));
+            }
+        }
+        samples
+    }
+
+    pub fn sample(&self, id: &str, time: f32) -> Option<Vec<TimelineSample>> {
+        self.timelines.get(id).map(|tl| tl.sample_at(time))
+    }
+}
+
+/// Writes timeline output into the animation state so previews read the same channels as transitions.
+pub fn apply_samples(anim_state: &mut HashMap<String, HashMap<String, AnimationValue>>, samples: &[TimelineSample]) {
+    for sample in samples {
+        let props = anim_state.entry(sample.element_id.clone()).or_default();
+        let val = props.entry(sample.property.clone()).or_insert_with(|| AnimationValue::at_rest(sample.value));
+        val.current = sample.value;
+        val.target = sample.value;
+        val.from = sample.value;
+        val.velocity = 0.0;
+        val.sleeping = true;
+    }
+}
+
+// --- WASM Exports ---
+
+#[wasm_bindgen]
+pub fn register_timeline(json: &str) -> Result<(), JsValue> {
+    let timeline: Timeline = serde_json::from_str(json)
+        .map_err(|e| JsValue::from_str(&format!("Timeline Parse Error: {}", e)))?;
+    TIMELINE_ENGINE.lock().unwrap().register(timeline);
+    Ok(())
+}
+
+#[wasm_bindgen]
+pub fn play_timeline(id: &str) -> bool {
+    TIMELINE_ENGINE.lock().unwrap().play(id)
+}
+
+#[wasm_bindgen]
+pub fn pause_timeline(id: &str) -> bool {
+    TIMELINE_ENGINE.lock().unwrap().pause(id)
+}
+
+#[wasm_bindgen]
+pub fn reverse_timeline(id: &str) -> bool {
+    TIMELINE_ENGINE.lock().unwrap().reverse(id)
+}
+
+#[wasm_bindgen]
+pub fn set_timeline_loop(id: &str, mode: &str) -> bool {
+    let mode = match mode {
+        "loop" => LoopMode::Loop,
+        "pingPong" | "ping-pong" => LoopMode::PingPong,
+        _ => LoopMode::None,
+    };
+    TIMELINE_ENGINE.lock().unwrap().set_loop(id, mode)
+}
+
+/// Moves the playhead and immediately applies the sampled values (scrubbing while paused).
+#[wasm_bindgen]
+pub fn seek_timeline(id: &str, time: f32) -> bool {
+    // The frame loop locks ANIMATION_STATE before TIMELINE_ENGINE: release the engine first
+    let samples = {
+        let mut engine = TIMELINE_ENGINE.lock().unwrap();
+        if !engine.seek(id, time) { return false; }
+        let tl = &engine.timelines[id];
+        tl.sample_at(tl.playback.time)
+    };
+    apply_samples(&mut crate::ANIMATION_STATE.lock().unwrap(), &samples);
+    true
+}
+
+#[wasm_bindgen]
+pub fn sample_timeline(id: &str, time: f32) -> String {
+    let engine = TIMELINE_ENGINE.lock().unwrap();
+    match engine.sample(id, time) {
+        Some(samples) => serde_json::to_string(&samples).unwrap_or("[]".to_string()),
+        None => "[]".to_string(),
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_timeline_state(id: &str) -> String {
+    let engine = TIMELINE_ENGINE.lock().unwrap();
+    engine.timelines.get(id)
+        .and_then(|tl| serde_json::to_string(&tl.playback).ok())
+        .unwrap_or("null".to_string())
+}
+
+#[wasm_bindgen]
+pub fn export_timeline_css(id: &str) -> Result<String, JsValue> {
+    let engine = TIMELINE_ENGINE.lock().unwrap();
+    engine.timelines.get(id)
+        .map(|tl| tl.to_css())
+        .ok_or_else(|| JsValue::from_str(&format!("Timeline {} not found", id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fade_timeline() -> Timeline {
+        serde_json::from_value(serde_json::json!({
+            "id": "intro",
+            "tracks": [{
+                "elementId": "card",
+                "property": "opacity",
+                "keyframes": [
+                    { "time": 1.0, "value": 1.0 },
+                    { "time": 0.0, "value": 0.0, "easing": "linear" }
+                ]
+            }]
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_sampling_is_deterministic() {
+        let mut engine = TimelineEngine::new();
+        engine.register(fade_timeline());
+
+        let mid = engine.sample("intro", 0.5).unwrap();
+        assert_eq!(mid[0].value, 0.5);
+        assert_eq!(engine.sample("intro", 0.5).unwrap(), mid);
+        assert_eq!(engine.sample("intro", 5.0).unwrap()[0].value, 1.0);
+    }
+
+    #[test]
+    fn test_ping_pong_playback() {
+        let mut engine = TimelineEngine::new();
+        engine.register(fade_timeline());
+        engine.set_loop("intro", LoopMode::PingPong);
+        engine.play("intro");
+
+        let samples = engine.advance(1.25);
+        assert!((samples[0].value - 0.75).abs() < 1e-4);
+        assert!(engine.timelines["intro"].playback.reversed);
+    }
+
+    #[test]
+    fn test_css_export() {
+        let mut engine = TimelineEngine::new();
+        engine.register(fade_timeline());
+        let css = engine.timelines["intro"].to_css();
+
+        assert!(css.contains("@keyframes intro-0"));
+        assert!(css.contains("0% { opacity: 0; animation-timing-function: linear; }"));
+        assert!(css.contains("100% { opacity: 1; }"));
+        assert!(css.contains("[data-id=\"card\"] { animation: intro-0 1s linear 0s 1 normal both; }"));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::plugins::metering::value_size;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+// --- LOGIC TRACE ---
+// Every node the kernel runs leaves a TraceEvent: when it was entered, which port it left
+// through and the variables before and after (until the trace's snapshot budget is spent).
+// `depth` counts the loop bodies and blueprint calls the node ran in.
+
+/// Upper bound on recorded events per execution (the gas limit usually stops flows well before).
+pub const MAX_TRACE_EVENTS: usize = 10_000;
+
+/// Upper bound on the variables copied into one trace's snapshots; later events go without.
+pub const MAX_SNAPSHOT_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VariableSnapshot {
+    pub execution: HashMap<String, Value>,
+    pub blueprint: HashMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceEvent {
+    pub seq: usize,
+    pub blueprint_id: String,
+    pub node_id: String,
+    pub node_type: String,
+    pub depth: usize, // Nesting in loop bodies and blueprint calls
+    pub step: usize,  // Gas step at entry
+    pub steps: usize, // Steps used by the node, including loop bodies and calls it ran
+    pub port: Option<String>, // None: the branch stopped here (error, wait, break, return, end of gas)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<VariableSnapshot>, // None once the trace's snapshot budget is spent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<VariableSnapshot>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trace {
+    pub events: Vec<TraceEvent>,
+    pub truncated: bool,
+    pub snapshots_truncated: bool, // Snapshots stopped at MAX_SNAPSHOT_BYTES
+    #[serde(skip)]
+    snapshot_bytes: usize,
+}
+
+impl Trace {
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.truncated = false;
+        self.snapshots_truncated = false;
+        self.snapshot_bytes = 0;
+    }
+
+    /// Copies the variables for an event, within MAX_SNAPSHOT_BYTES per trace.
+    pub fn snapshot(&mut self, execution: &HashMap<String, Value>, blueprint: Option<&HashMap<String, Value>>) -> Option<VariableSnapshot> {
+        if self.snapshots_truncated { return None; }
+        let size = |vars: &HashMap<String, Value>| vars.iter().map(|(k, v)| k.len() + value_size(v)).sum::<usize>();
+        let bytes = size(execution) + blueprint.map(size).unwrap_or(0);
+        if self.snapshot_bytes + bytes > MAX_SNAPSHOT_BYTES {
+            self.snapshots_truncated = true;
+            return None;
+        }
+        self.snapshot_bytes += bytes;
+        Some(VariableSnapshot { execution: execution.clone(), blueprint: blueprint.cloned().unwrap_or_default() })
+    }
+
+    /// Whether another event fits. Marks the trace truncated when not, so callers can skip
+    /// building the event (and its snapshots) entirely.
+    pub fn accepts(&mut self) -> bool {
+        if self.events.len() >= MAX_TRACE_EVENTS {
+            self.truncated = true;
+        }
+        !self.truncated
+    }
+
+    /// Records entry into a node. Check `accepts` first.
+    pub fn enter(&mut self, blueprint_id: &str, node_id: &str, node_type: &str, depth: usize, step: usize, before: Option<VariableSnapshot>) -> usize {
+        let seq = self.events.len();
+        self.events.push(TraceEvent {
+            seq,
+            blueprint_id: blueprint_id.to_string(),
+            node_id: node_id.to_string(),
+            node_type: node_type.to_string(),
+            depth,
+            step,
+            steps: 0,
+            port: None,
+            before,
+            after: None,
+        });
+        seq
+    }
+
+    pub fn exit(&mut self, seq: usize, port: Option<String>, steps: usize, after: Option<VariableSnapshot>) {
+        if let Some(event) = self.events.get_mut(seq) {
+            event.port = port;
+            event.steps = steps;
+            event.after = after;
+        }
+    }
+
+    /// One line per event, for logs and `RuntimeContext.execution_trace`.
+    pub fn lines(&self) -> Vec<String> {
+        self.events.iter().map(|e| format!(
+            "{}{} ({}) -> {}",
+            "  ".repeat(e.depth), e.node_id, e.node_type, e.port.as_deref().unwrap_or("stop"),
+        )).collect()
+    }
+}
@@ -0,0 +1,282 @@
+use crate::plugins::expression;
+use crate::plugins::logic_kernel::UnifiedBlueprint;
+use crate::plugins::nodes::{FieldKind, NodeRegistry, ERROR_PORT};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// --- BLUEPRINT VALIDATION ---
+// Static checks for problems the kernel would otherwise skip silently at runtime:
+// dangling connections, unknown ports and node types, unreachable nodes, cycles that
+// don't go through a loop node, missing data fields, bad expressions and type mismatches.
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    Error,   // Will misbehave at runtime
+    Warning, // Suspicious but harmless
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    pub severity: Severity,
+    pub code: String,
+    pub message: String,
+    pub node_ids: Vec<String>,
+    pub connection_ids: Vec<String>,
+}
+
+/// What the blueprint is checked against. Empty sets skip the corresponding checks.
+#[derive(Debug, Clone)]
+pub struct ValidationContext {
+    pub blueprints: HashSet<String>,        // Ids `call_blueprint` may target
+    pub globals: HashMap<String, String>,   // Global variable name -> LogicVariable type
+    pub nodes: NodeRegistry,                // Known node types: ports and data schema
+}
+
+impl Default for ValidationContext {
+    fn default() -> Self {
+        Self { blueprints: HashSet::new(), globals: HashMap::new(), nodes: NodeRegistry::with_builtins() }
+    }
+}
+
+/// Output ports of a node type. Triggers have a single `default` output and no handler.
+fn outputs<'a>(ctx: &'a ValidationContext, node_type: &str) -> Option<Vec<&'a str>> {
+    match ctx.nodes.spec(node_type) {
+        Some(spec) => Some(spec.outputs.iter().map(|p| p.as_str()).collect()),
+        None if is_trigger(node_type) => Some(vec!["default"]),
+        None => None,
+    }
+}
+
+/// Entry points: `on_*` events, `start`, and the server's `api_get`/`api_post`/... triggers.
+fn is_trigger(node_type: &str) -> bool {
+    node_type.starts_with("on_") || (node_type.starts_with("api_") && node_type != "api_request") || node_type == "start"
+}
+
+fn is_loop(node_type: &str) -> bool {
+    matches!(node_type, "for_each" | "while")
+}
+
+/// Whether `value` fits a LogicVariable type (`string`, `number`, `boolean`, `json`). Null fits all.
+fn fits(declared: &str, value: &Value) -> bool {
+    match (declared, value) {
+        (_, Value::Null) | ("json", _) => true,
+        (declared, value) => declared == expression::type_name(value),
+    }
+}
+
+pub fn validate(bp: &UnifiedBlueprint, ctx: &ValidationContext) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut report = |severity, code: &str, message: String, node_ids: Vec<String>, connection_ids: Vec<String>| {
+        findings.push(Finding { severity, code: code.to_string(), message, node_ids, connection_ids });
+    };
+
+    // Deterministic report order
+    let mut node_ids: Vec<&String> = bp.nodes.keys().collect();
+    node_ids.sort();
+
+    // Nodes: types, required data, expressions, variable types
+    for id in &node_ids {
+        let node = &bp.nodes[*id];
+        let spec = match ctx.nodes.spec(&node.r#type) {
+            Some(spec) => spec,
+            None if is_trigger(&node.r#type) => continue,
+            None => {
+                report(Severity::Warning, "unknown_node_type", format!("Node type '{}' has no handler and will be skipped", node.r#type), vec![node.id.clone()], vec![]);
+                continue;
+            }
+        };
+        for field in spec.fields.iter().filter(|f| f.required) {
+            if !field.keys().any(|k| node.data.get(k).is_some_and(|v| !v.is_null())) {
+                let keys: Vec<&str> = field.keys().collect();
+                report(Severity::Error, "missing_field", format!("{} needs '{}'", node.r#type, keys.join("' or '")), vec![node.id.clone()], vec![]);
+            }
+        }
+        for field in spec.fields.iter().filter(|f| f.kind == FieldKind::Expression) {
+            if let Some(src) = node.data.get(&field.name).and_then(|v| v.as_str()) {
+                if let Err(e) = expression::parse(src) {
+                    report(Severity::Error, "invalid_expression", format!("{}: {}", field.name, e), vec![node.id.clone()], vec![]);
+                }
+            }
+        }
+        if node.r#type == "set_var" && node.data.get("expression").is_none() {
+            let name = node.data.get("varName").and_then(|v| v.as_str()).unwrap_or_default();
+            let value = node.data.get("value").unwrap_or(&Value::Null);
+            let declared = bp.variables.get(name).filter(|v| !v.is_null()).map(|v| expression::type_name(v).to_string())
+                .or_else(|| ctx.globals.get(name).cloned());
+            if let Some(declared) = declared {
+                if !fits(&declared, value) {
+                    report(Severity::Error, "type_mismatch", format!("'{}' is {} but is set to {}", name, declared, expression::type_name(value)), vec![node.id.clone()], vec![]);
+                }
+            }
+        }
+        if node.r#type == "call_blueprint" && !ctx.blueprints.is_empty() {
+            if let Some(target) = node.data.get("blueprintId").and_then(|v| v.as_str()) {
+                if target != bp.id && !ctx.blueprints.contains(target) {
+                    report(Severity::Error, "unknown_blueprint", format!("Blueprint '{}' does not exist", target), vec![node.id.clone()], vec![]);
+                }
+            }
+        }
+    }
+
+    // Connections: endpoints and ports
+    let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+    for conn in &bp.connections {
+        let from = bp.nodes.get(&conn.from_id);
+        let to = bp.nodes.get(&conn.to_id);
+        for (end, id) in [(from.is_none(), &conn.from_id), (to.is_none(), &conn.to_id)] {
+            if end {
+                report(Severity::Error, "missing_node", format!("Connection refers to missing node '{}'", id), vec![id.clone()], vec![conn.id.clone()]);
+            }
+        }
+        if let (Some(from), Some(_)) = (from, to) {
+            let port = conn.port.as_deref().unwrap_or("default");
+            if let Some(ports) = outputs(ctx, &from.r#type) {
+                if !ports.contains(&port) && port != ERROR_PORT {
+                    let known = if ports.is_empty() { "no outputs".to_string() } else { ports.join(", ") };
+                    report(Severity::Error, "unknown_port", format!("{} has no '{}' port ({})", from.r#type, port, known), vec![from.id.clone()], vec![conn.id.clone()]);
+                }
+            }
+            edges.entry(conn.from_id.as_str()).or_default().push(conn.to_id.as_str());
+        }
+    }
+
+    // Reachability from triggers (on_call counts: it's the entry for call_blueprint)
+    let mut reached: HashSet<&str> = HashSet::new();
+    let mut queue: VecDeque<&str> = node_ids.iter().filter(|id| is_trigger(&bp.nodes[**id].r#type)).map(|id| id.as_str()).collect();
+    while let Some(id) = queue.pop_front() {
+        if !reached.insert(id) { continue; }
+        queue.extend(edges.get(id).into_iter().flatten());
+    }
+    for id in &node_ids {
+        if !reached.contains(id.as_str()) {
+            report(Severity::Warning, "unreachable_node", format!("'{}' ({}) is not reachable from any trigger", id, bp.nodes[*id].r#type), vec![(*id).clone()], vec![]);
+        }
+    }
+
+    // Cycles: every strongly connected component with more than one node (or a self-loop) must contain a loop node
+    for component in strongly_connected(&node_ids, &edges) {
+        let self_loop = component.len() == 1 && edges.get(component[0]).is_some_and(|next| next.contains(&component[0]));
+        if component.len() < 2 && !self_loop { continue; }
+        if component.iter().any(|id| is_loop(&bp.nodes[*id].r#type)) { continue; }
+
+        let members: HashSet<&str> = component.iter().copied().collect();
+        let connection_ids = bp.connections.iter()
+            .filter(|c| members.contains(c.from_id.as_str()) && members.contains(c.to_id.as_str()))
+            .map(|c| c.id.clone())
+            .collect();
+        report(Severity::Error, "cycle", format!("Cycle through {} without a for_each/while node", component.join(" -> ")),
+            component.iter().map(|id| id.to_string()).collect(), connection_ids);
+    }
+
+    findings
+}
+
+/// Tarjan's algorithm, iterative. Components come out with their node ids sorted.
+fn strongly_connected<'a>(nodes: &[&'a String], edges: &HashMap<&'a str, Vec<&'a str>>) -> Vec<Vec<&'a str>> {
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    let mut low: HashMap<&str, usize> = HashMap::new();
+    let mut on_stack: HashSet<&str> = HashSet::new();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut components = Vec::new();
+    let mut counter = 0;
+
+    for root in nodes {
+        let root = root.as_str();
+        if index.contains_key(root) { continue; }
+        let mut work: Vec<(&str, usize)> = vec![(root, 0)];
+        while let Some((v, i)) = work.pop() {
+            if i == 0 {
+                index.insert(v, counter);
+                low.insert(v, counter);
+                counter += 1;
+                stack.push(v);
+                on_stack.insert(v);
+            }
+            let next = edges.get(v).map(|n| n.as_slice()).unwrap_or(&[]);
+            if let Some(&w) = next.get(i) {
+                work.push((v, i + 1));
+                if !index.contains_key(w) {
+                    work.push((w, 0));
+                } else if on_stack.contains(w) {
+                    let lw = index[w];
+                    let lv = low.get_mut(v).unwrap();
+                    *lv = (*lv).min(lw);
+                }
+                continue;
+            }
+            // v is finished: propagate to its parent and pop its component if it's a root
+            if let Some(&(parent, _)) = work.last() {
+                let lv = low[v];
+                let lp = low.get_mut(parent).unwrap();
+                *lp = (*lp).min(lv);
+            }
+            if low[v] == index[v] {
+                let mut component = Vec::new();
+                while let Some(w) = stack.pop() {
+                    on_stack.remove(w);
+                    component.push(w);
+                    if w == v { break; }
+                }
+                component.sort();
+                components.push(component);
+            }
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_findings() {
+        let bp: UnifiedBlueprint = serde_json::from_value(json!({
+            "id": "bp",
+            "variables": { "count": 0 },
+            "nodes": {
+                "start": { "id": "start", "type": "on_click", "data": {} },
+                "check": { "id": "check", "type": "condition", "data": { "expression": "count >" } },
+                "a": { "id": "a", "type": "set_var", "data": { "varName": "count", "value": "many" } },
+                "b": { "id": "b", "type": "navigate", "data": {} },
+                "orphan": { "id": "orphan", "type": "teleport", "data": {} },
+                "each": { "id": "each", "type": "for_each", "data": { "items": "[1, 2]" } },
+                "body": { "id": "body", "type": "set_var", "data": { "varName": "flag", "value": true } }
+            },
+            "connections": [
+                { "id": "c1", "fromId": "start", "toId": "check" },
+                { "id": "c2", "fromId": "check", "toId": "a", "port": "yes" },
+                { "id": "c3", "fromId": "a", "toId": "b" },
+                { "id": "c4", "fromId": "b", "toId": "a" },
+                { "id": "c5", "fromId": "b", "toId": "ghost" },
+                { "id": "c6", "fromId": "start", "toId": "each" },
+                { "id": "c7", "fromId": "each", "toId": "body", "port": "body" },
+                { "id": "c8", "fromId": "body", "toId": "each" }
+            ]
+        })).unwrap();
+        let ctx = ValidationContext { globals: HashMap::from([("flag".to_string(), "boolean".to_string())]), ..Default::default() };
+        let findings = validate(&bp, &ctx);
+        let codes: Vec<(&str, &str)> = findings.iter().map(|f| (f.code.as_str(), f.node_ids[0].as_str())).collect();
+
+        assert!(codes.contains(&("invalid_expression", "check")));
+        assert!(codes.contains(&("type_mismatch", "a")));
+        assert!(codes.contains(&("missing_field", "b")));
+        assert!(codes.contains(&("unknown_node_type", "orphan")));
+        assert!(codes.contains(&("unreachable_node", "orphan")));
+        assert!(codes.contains(&("missing_node", "ghost")));
+
+        let port = findings.iter().find(|f| f.code == "unknown_port").unwrap();
+        assert_eq!((port.node_ids.clone(), port.connection_ids.clone()), (vec!["check".to_string()], vec!["c2".to_string()]));
+
+        // a <-> b is a bare cycle; each <-> body goes through a loop node
+        let cycles: Vec<&Finding> = findings.iter().filter(|f| f.code == "cycle").collect();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].node_ids, vec!["a", "b"]);
+        assert_eq!(cycles[0].connection_ids, vec!["c3", "c4"]);
+        assert!(!findings.iter().any(|f| f.node_ids.contains(&"body".to_string())));
+    }
+}
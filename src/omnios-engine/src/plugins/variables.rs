@@ -0,0 +1,135 @@
+use crate::plugins::expression::loose_eq;
+use crate::plugins::logic_kernel::UnifiedBlueprint;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+// --- LOGIC VARIABLES ---
+// Three scopes, innermost first:
+//   execution  lives for one `execute` call (trigger to end of flow)
+//   blueprint  persists per blueprint, seeded from `UnifiedBlueprint.variables`
+//   global     project globals (ProjectState.global_variables, by name)
+// Global writes are recorded as changes; the engine applies them to ProjectState and bindings.
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum VariableScope {
+    Execution,
+    Blueprint,
+    Global,
+}
+
+impl VariableScope {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "execution" | "local" => Some(VariableScope::Execution),
+            "blueprint" => Some(VariableScope::Blueprint),
+            "global" => Some(VariableScope::Global),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VariableChange {
+    pub id: Option<String>, // ProjectState variable id, filled in when applied
+    pub name: String,
+    pub value: Value,
+    pub previous: Option<Value>,
+    pub blueprint_id: String, // Blueprint whose flow made the write
+}
+
+#[derive(Default, Debug)]
+pub struct VariableStore {
+    pub execution: HashMap<String, Value>,
+    pub blueprint: HashMap<String, HashMap<String, Value>>, // Blueprint id -> values written so far
+    pub globals: HashMap<String, Value>,
+    changes: Vec<VariableChange>,
+}
+
+impl VariableStore {
+    /// Starts a fresh execution scope.
+    pub fn begin_execution(&mut self) {
+        self.execution.clear();
+    }
+
+    fn blueprint_value<'a>(&'a self, bp: &'a UnifiedBlueprint, name: &str) -> Option<&'a Value> {
+        self.blueprint.get(&bp.id).and_then(|vars| vars.get(name)).or_else(|| bp.variables.get(name))
+    }
+
+    /// Innermost scope that defines `name`.
+    pub fn scope_of(&self, bp: &UnifiedBlueprint, name: &str) -> Option<VariableScope> {
+        if self.execution.contains_key(name) {
+            Some(VariableScope::Execution)
+        } else if self.blueprint_value(bp, name).is_some() {
+            Some(VariableScope::Blueprint)
+        } else if self.globals.contains_key(name) {
+            Some(VariableScope::Global)
+        } else {
+            None
+        }
+    }
+
+    pub fn get<'a>(&'a self, bp: &'a UnifiedBlueprint, name: &str) -> Option<&'a Value> {
+        self.execution.get(name)
+            .or_else(|| self.blueprint_value(bp, name))
+            .or_else(|| self.globals.get(name))
+    }
+
+    /// Writes `name`. Without an explicit scope the innermost existing variable is updated,
+    /// and unknown names become execution variables. Returns the scope written.
+    pub fn set(&mut self, bp: &UnifiedBlueprint, name: &str, value: Value, scope: Option<VariableScope>) -> VariableScope {
+        let scope = scope.or_else(|| self.scope_of(bp, name)).unwrap_or(VariableScope::Execution);
+        match scope {
+            VariableScope::Execution => { self.execution.insert(name.to_string(), value); }
+            VariableScope::Blueprint => {
+                self.blueprint.entry(bp.id.clone()).or_default().insert(name.to_string(), value);
+            }
+            VariableScope::Global => {
+                let previous = self.globals.insert(name.to_string(), value.clone());
+                if previous.as_ref().is_none_or(|p| !loose_eq(p, &value)) {
+                    self.changes.push(VariableChange { id: None, name: name.to_string(), value, previous, blueprint_id: bp.id.clone() });
+                }
+            }
+        }
+        scope
+    }
+
+    /// Global writes since the last drain, oldest first.
+    pub fn drain_changes(&mut self) -> Vec<VariableChange> {
+        std::mem::take(&mut self.changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_scope_resolution_and_changes() {
+        let bp: UnifiedBlueprint = serde_json::from_value(json!({ "id": "bp", "nodes": {}, "connections": [], "variables": { "count": 0 } })).unwrap();
+        let mut store = VariableStore::default();
+        store.globals.insert("isLoggedIn".to_string(), json!(false));
+
+        assert_eq!(store.set(&bp, "count", json!(1), None), VariableScope::Blueprint);
+        assert_eq!(store.set(&bp, "isLoggedIn", json!(true), None), VariableScope::Global);
+        assert_eq!(store.set(&bp, "temp", json!("x"), None), VariableScope::Execution);
+        // Same value again: no second change
+        store.set(&bp, "isLoggedIn", json!(true), None);
+
+        let changes = store.drain_changes();
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].name.as_str(), &changes[0].previous), ("isLoggedIn", &Some(json!(false))));
+
+        store.begin_execution();
+        assert_eq!(store.get(&bp, "temp"), None);
+        assert_eq!(store.get(&bp, "count"), Some(&json!(1)));
+
+        // A local shadows the global of the same name until the execution ends
+        store.set(&bp, "isLoggedIn", json!("shadow"), Some(VariableScope::Execution));
+        assert_eq!(store.get(&bp, "isLoggedIn"), Some(&json!("shadow")));
+        assert!(store.drain_changes().is_empty());
+    }
+}
@@ -49,14 +49,9 @@ impl ParticlesPlugin {
         // Remove dead
         parts.retain(|p| p.life > 0.0);
     }
-}
-
-impl OmniosPlugin for ParticlesPlugin {
-    fn name(&self) -> &str { "ParticlesPlugin" }
-    fn on_register(&mut self, _context: &mut PluginContext) {}
-    fn render(&self, _ctx: &RenderContext) { 
-        self.update(0.016);
 
+    /// Draw-only pass, used by the frame scheduler once simulation steps have run.
+    pub fn draw(&self) {
         #[cfg(feature = "browser")]
         {
             use wasm_bindgen::JsCast;
@@ -103,6 +98,15 @@ impl OmniosPlugin for ParticlesPlugin {
     }
 }
 
+impl OmniosPlugin for ParticlesPlugin {
+    fn name(&self) -> &str { "ParticlesPlugin" }
+    fn on_register(&mut self, _context: &mut PluginContext) {}
+    fn render(&self, ctx: &RenderContext) { 
+        self.update(ctx.dt);
+        self.draw();
+    }
+}
+
 // --- Plugin 2: FilterPlugin (Post-Processing) ---
 pub struct FilterPlugin {
     pub active_filter: Arc<Mutex<Option<String>>>, // "blur", "sepia"
@@ -21,11 +21,9 @@ pub struct ParityReport {
 #[wasm_bindgen]
 pub fn get_full_layout_dump() -> String {
     let index = SPATIAL_INDEX.lock().unwrap();
-    let mut snapshots = Vec::new();
+    let mut snapshots = Vec::with_capacity(index.len());
 
-    // SPATIAL_INDEX stores RTree<ElementBounds>.
-    // "item" will be &ElementBounds
-    for item in index.tree.iter() {
+    for (_, item) in index.iter_all() {
         snapshots.push(LayoutSnapshot {
             id: item.id.clone(),
             x: item.min_x,
@@ -51,9 +49,8 @@ pub fn compute_parity_score(dom_dump_json: &str) -> String {
     let mut flagged = Vec::new();
 
     for dom_item in dom_snapshots {
-        // Find corresponding Rust item
-        // Linear search over tree items
-        let rust_item = index.tree.iter().find(|i| i.id == dom_item.id);
+        // Find corresponding Rust item (id map, no tree scan)
+        let rust_item = index.get(&dom_item.id);
         
         if let Some(rust_item) = rust_item {
              let r_x = rust_item.min_x;
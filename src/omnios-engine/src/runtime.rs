@@ -26,6 +26,92 @@ impl RuntimeContext {
     }
 }
 
+// --- Serverless execution ---
+// Stateless: each request carries its blueprint. Effects the flow queues are mocked (`mocks` answers
+// HTTP by URL) unless the request sets `mockEffects: false` and the host has allowed some hosts;
+// only those can then be reached, so a request can't make the server call internal services.
+
+use crate::plugins::trace::TraceEvent;
+use crate::plugins::effects::{EffectOutcome, QueuedEffect};
+use crate::plugins::logic_kernel::{LogicError, UnifiedBlueprint};
+use crate::plugins::metering::{ExecutionError, ExecutionLimits};
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeRequest {
+    pub blueprint: UnifiedBlueprint,
+    pub trigger: String,
+    #[serde(default)]
+    pub payload: serde_json::Value,
+    #[serde(default)]
+    pub globals: HashMap<String, serde_json::Value>,
+    #[serde(default = "mock_by_default")]
+    pub mock_effects: bool,
+    #[serde(default)]
+    pub mocks: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub limits: Option<ExecutionLimits>, // Clamped to the kernel defaults; those when absent
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeResponse {
+    pub status: String, // "success" | "error"
+    pub variables: HashMap<String, serde_json::Value>,
+    pub globals: HashMap<String, serde_json::Value>,
+    pub effects: Vec<QueuedEffect>,
+    pub outcomes: Vec<EffectOutcome>,
+    pub errors: Vec<LogicError>, // Unhandled node failures, including failed effects
+    pub error: Option<ExecutionError>, // Set when a limit aborted the flow
+    pub trace: Vec<TraceEvent>,
+}
+
+fn mock_by_default() -> bool {
+    true
+}
+
+pub struct RuntimeAdapter;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RuntimeAdapter {
+    /// `allowed_hosts`: hosts native HTTP effects may reach. Empty: every effect is mocked.
+    pub fn handle_request(req: RuntimeRequest, allowed_hosts: &[String]) -> RuntimeResponse {
+        use crate::plugins::effects::{MockEffects, NativeEffects};
+        use crate::plugins::logic_kernel::LogicKernel;
+
+        let blueprint_id = req.blueprint.id.clone();
+        let mut kernel = LogicKernel::new();
+        kernel.variables.globals = req.globals;
+        // Requests may tighten the kernel's limits, never loosen them
+        if let Some(limits) = req.limits {
+            kernel.limits = limits.clamped_to(&ExecutionLimits::default());
+        }
+        kernel.register_blueprint(req.blueprint);
+        let aborted = kernel.execute(&blueprint_id, &req.trigger, &req.payload).err();
+
+        // Awaited effects and timers are run to completion; the response holds the whole flow
+        let (effects, outcomes) = if req.mock_effects || allowed_hosts.is_empty() {
+            kernel.run_until_idle(&mut MockEffects { responses: req.mocks, ..Default::default() })
+        } else {
+            kernel.run_until_idle(&mut NativeEffects { allowed_hosts: Some(allowed_hosts.to_vec()) })
+        };
+
+        let error = aborted.or_else(|| kernel.error.take());
+        // Failed effects count only when unhandled: they are in `errors` then
+        let failed = error.is_some() || !kernel.errors.is_empty();
+        RuntimeResponse {
+            status: if failed { "error" } else { "success" }.to_string(),
+            variables: std::mem::take(&mut kernel.variables.execution),
+            globals: std::mem::take(&mut kernel.variables.globals),
+            effects,
+            outcomes,
+            errors: std::mem::take(&mut kernel.errors),
+            error,
+            trace: std::mem::take(&mut kernel.trace.events),
+        }
+    }
+}
+
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -33,28 +119,40 @@ pub fn execute_headless_flow(blueprint_id: &str, input_json: &str) -> Result<Str
     let input: serde_json::Value = serde_json::from_str(input_json)
         .map_err(|e| JsValue::from_str(&format!("Invalid JSON Input: {}", e)))?;
 
-    let state_guard = PROJECT_STATE.lock().map_err(|e| e.to_string())
-        .map_err(|e| JsValue::from_str(&e))?;
-    
-    let state = state_guard.as_ref().ok_or_else(|| JsValue::from_str("No active project state"))?;
+    {
+        let state_guard = PROJECT_STATE.lock().map_err(|e| e.to_string())
+            .map_err(|e| JsValue::from_str(&e))?;
 
-    let blueprint = state.blueprints.get(blueprint_id)
-        .ok_or_else(|| JsValue::from_str(&format!("Blueprint {} not found", blueprint_id)))?;
+        let state = state_guard.as_ref().ok_or_else(|| JsValue::from_str("No active project state"))?;
+
+        if !state.blueprints.contains_key(blueprint_id) {
+            return Err(JsValue::from_str(&format!("Blueprint {} not found", blueprint_id)));
+        }
+    } // The kernel must not run while the project is locked
 
-    // Simple execution simulation for now
     let mut context = RuntimeContext::new();
     context.set_variable("input", input.clone());
-    
-    // Logic Kernel hook (mock)
-    // In real implementation: 
-    // let result = crate::LOGIC_KERNEL.lock().unwrap().execute(blueprint_id, "start", &input);
-    
+
+    crate::run_logic(blueprint_id, "start", &input);
+    let (errors, error) = {
+        let kernel = crate::LOGIC_KERNEL.lock().unwrap();
+        context.execution_trace = kernel.trace.lines();
+        for (name, value) in &kernel.variables.execution {
+            context.set_variable(name, value.clone());
+        }
+        (kernel.errors.clone(), kernel.error.clone())
+    };
+
     let result = serde_json::json!({
-        "status": "success",
+        "status": if errors.is_empty() && error.is_none() { "success" } else { "error" },
         "blueprint": blueprint_id,
         "input_echo": input,
         "execution_env": "headless_rust",
-        "timestamp": 123456789
+        "timestamp": 123456789,
+        "variables": context.variables,
+        "trace": context.execution_trace,
+        "errors": errors,
+        "error": error
     });
 
     serde_json::to_string(&result).map_err(|e| JsValue::from_str(&e.to_string()))
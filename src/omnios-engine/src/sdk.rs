@@ -1,5 +1,6 @@
 use std::any::Any;
 use std::collections::HashMap;
+use crate::plugins::nodes::NodeRegistry;
 
 // --- CONTEXT STRUCTS ---
 
@@ -7,14 +8,16 @@ use std::collections::HashMap;
 /// Allows the plugin to inspect the engine capability or register hooks.
 pub struct PluginContext {
     pub engine_version: String,
+    /// Custom logic node types. Installed into the logic kernel after `on_register`.
+    pub nodes: NodeRegistry,
     // Future: pub taffy: &mut TaffyTree
-    // Future: pub registry: &mut logic_registry
 }
 
 impl PluginContext {
     pub fn new() -> Self {
         Self {
             engine_version: env!("CARGO_PKG_VERSION").to_string(),
+            nodes: NodeRegistry::default(),
         }
     }
 }
@@ -25,6 +28,10 @@ pub struct RenderContext {
     pub width: f32,
     pub height: f32,
     pub frame_count: u64,
+    /// Seconds covered by this call (the fixed step when driven by the frame scheduler).
+    pub dt: f32,
+    /// Interpolation factor between the last two simulation steps (0..1).
+    pub alpha: f32,
     // Future: WGPU Device/Queue or CanvasContext2d
 }
 
@@ -49,17 +56,26 @@ pub trait OmniosPlugin: Any + Send {
 
 pub struct PluginRegistry {
     plugins: Vec<Box<dyn OmniosPlugin>>,
+    nodes: NodeRegistry,
 }
 
 impl PluginRegistry {
     pub fn new() -> Self {
-        Self { plugins: Vec::new() }
+        Self { plugins: Vec::new(), nodes: NodeRegistry::default() }
     }
 
-    pub fn register(&mut self, mut plugin: Box<dyn OmniosPlugin>) {
+    /// Registers the plugin and returns the logic node types it added.
+    pub fn register(&mut self, mut plugin: Box<dyn OmniosPlugin>) -> NodeRegistry {
         let mut context = PluginContext::new();
         plugin.on_register(&mut context);
+        self.nodes.extend(context.nodes.clone());
         self.plugins.push(plugin);
+        context.nodes
+    }
+
+    /// Logic node types added by all registered plugins.
+    pub fn nodes(&self) -> &NodeRegistry {
+        &self.nodes
     }
     
     pub fn count(&self) -> usize {